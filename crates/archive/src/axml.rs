@@ -0,0 +1,270 @@
+//! Android 二进制 XML（AXML）解码
+//!
+//! APK 中的 `AndroidManifest.xml` 经过 aapt 编译：字符串集中存放在字符串池中，元素和属性
+//! 以定长结构引用字符串池下标。解码结果与文本 XML 一样转换为 [`XmlElement`] 列表，
+//! 同时还原为可读的 XML 文本。属性值引用的资源（如 `@string/app_name`）需要 `resources.arsc`
+//! 才能解析，这里只显示资源 ID。
+
+use crate::package::XmlElement;
+use crate::ArchiveError;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+const RES_STRING_POOL_TYPE: u16 = 0x0001;
+const RES_XML_TYPE: u16 = 0x0003;
+const RES_XML_START_NAMESPACE_TYPE: u16 = 0x0100;
+const RES_XML_START_ELEMENT_TYPE: u16 = 0x0102;
+const RES_XML_END_ELEMENT_TYPE: u16 = 0x0103;
+const RES_XML_RESOURCE_MAP_TYPE: u16 = 0x0180;
+
+/// 字符串池使用 UTF-8 编码的标记
+const UTF8_FLAG: u32 = 0x100;
+/// 下标为空
+const NO_INDEX: u32 = 0xFFFF_FFFF;
+
+/// 文件头是否为二进制 XML
+pub(crate) fn is_axml(data: &[u8]) -> bool {
+    data.len() >= 8 && u16::from_le_bytes([data[0], data[1]]) == RES_XML_TYPE
+}
+
+/// 解码二进制 XML，返回元素列表和还原的 XML 文本
+pub(crate) fn decode(data: &[u8]) -> Result<(Vec<XmlElement>, String), ArchiveError> {
+    let data = Bytes(data);
+    let header_size = data.u16(2)? as usize;
+    let total = (data.u32(4)? as usize).min(data.0.len());
+
+    let mut strings = Vec::new();
+    let mut resource_ids = Vec::new();
+    // 命名空间 URI 到前缀，如 `http://schemas.android.com/apk/res/android` -> `android`
+    let mut prefixes: HashMap<String, String> = HashMap::new();
+    let mut pending_namespaces = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    let mut elements = Vec::new();
+    let mut text = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    // 上一个开始标签还没有写出 `>`，遇到紧接的结束标签时写成 `/>`
+    let mut open_tag = false;
+
+    let mut pos = header_size;
+    while pos + 8 <= total {
+        let chunk_type = data.u16(pos)?;
+        let chunk_header = data.u16(pos + 2)? as usize;
+        let chunk_size = data.u32(pos + 4)? as usize;
+        if chunk_size < 8 || pos + chunk_size > total {
+            return Err(malformed());
+        }
+        let chunk = Bytes(&data.0[pos..pos + chunk_size]);
+
+        match chunk_type {
+            RES_STRING_POOL_TYPE => strings = string_pool(&chunk)?,
+            RES_XML_RESOURCE_MAP_TYPE => {
+                resource_ids = chunk.0[chunk_header.min(chunk_size)..]
+                    .chunks_exact(4)
+                    .map(|id| u32::from_le_bytes([id[0], id[1], id[2], id[3]]))
+                    .collect();
+            },
+            RES_XML_START_NAMESPACE_TYPE => {
+                let prefix = string_at(&strings, chunk.u32(chunk_header)?);
+                let uri = string_at(&strings, chunk.u32(chunk_header + 4)?);
+                prefixes.insert(uri.clone(), prefix.clone());
+                pending_namespaces.push((prefix, uri));
+            },
+            RES_XML_START_ELEMENT_TYPE => {
+                let name = string_at(&strings, chunk.u32(chunk_header + 4)?);
+                let attr_start = chunk.u16(chunk_header + 8)? as usize;
+                let attr_size = chunk.u16(chunk_header + 10)? as usize;
+                let attr_count = chunk.u16(chunk_header + 12)? as usize;
+
+                if open_tag {
+                    text.push_str(">\n");
+                }
+                let _ = write!(text, "{}<{}", "  ".repeat(stack.len()), name);
+                for (prefix, uri) in pending_namespaces.drain(..) {
+                    let _ = write!(text, " xmlns:{}=\"{}\"", prefix, escape(&uri));
+                }
+
+                let mut attrs = Vec::with_capacity(attr_count);
+                for i in 0..attr_count {
+                    let attr = chunk_header + attr_start + i * attr_size;
+                    let namespace = chunk.u32(attr)?;
+                    let name_index = chunk.u32(attr + 4)?;
+                    let raw_value = chunk.u32(attr + 8)?;
+                    let data_type = chunk.u8(attr + 15)?;
+                    let value_data = chunk.u32(attr + 16)?;
+
+                    let mut attr_name = string_at(&strings, name_index);
+                    // 混淆过的 APK 会清空属性名，只能根据资源 ID 还原常用属性
+                    if attr_name.is_empty() {
+                        if let Some(known) = resource_ids
+                            .get(name_index as usize)
+                            .and_then(|id| framework_attr(*id))
+                        {
+                            attr_name = known.to_string();
+                        }
+                    }
+                    let value = if raw_value != NO_INDEX {
+                        string_at(&strings, raw_value)
+                    } else {
+                        typed_value(data_type, value_data, &strings)
+                    };
+
+                    let prefix = (namespace != NO_INDEX)
+                        .then(|| prefixes.get(&string_at(&strings, namespace)))
+                        .flatten();
+                    match prefix {
+                        Some(prefix) => {
+                            let _ = write!(text, " {prefix}:{attr_name}=\"{}\"", escape(&value));
+                        },
+                        None => {
+                            let _ = write!(text, " {attr_name}=\"{}\"", escape(&value));
+                        },
+                    }
+                    attrs.push((attr_name, value));
+                }
+                open_tag = true;
+
+                stack.push(name);
+                elements.push(XmlElement {
+                    path: stack.join("/"),
+                    attrs,
+                    text: String::new(),
+                });
+            },
+            RES_XML_END_ELEMENT_TYPE => {
+                let name = stack.pop().unwrap_or_default();
+                if open_tag {
+                    text.push_str(" />\n");
+                    open_tag = false;
+                } else {
+                    let _ = writeln!(text, "{}</{}>", "  ".repeat(stack.len()), name);
+                }
+            },
+            _ => {},
+        }
+        pos += chunk_size;
+    }
+
+    Ok((elements, text))
+}
+
+/// 解析字符串池
+fn string_pool(chunk: &Bytes<'_>) -> Result<Vec<String>, ArchiveError> {
+    let header_size = chunk.u16(2)? as usize;
+    let count = chunk.u32(8)? as usize;
+    let flags = chunk.u32(16)?;
+    let strings_start = chunk.u32(20)? as usize;
+    if count > chunk.0.len() / 4 {
+        return Err(malformed());
+    }
+
+    let mut strings = Vec::with_capacity(count);
+    for i in 0..count {
+        let start = strings_start + chunk.u32(header_size + i * 4)? as usize;
+        let string = if flags & UTF8_FLAG != 0 {
+            // UTF-16 长度和 UTF-8 字节数各占 1~2 字节
+            let (_, skip) = utf8_length(chunk, start)?;
+            let (len, skip2) = utf8_length(chunk, start + skip)?;
+            let from = start + skip + skip2;
+            let bytes = chunk.0.get(from..from + len).ok_or_else(malformed)?;
+            String::from_utf8_lossy(bytes).into_owned()
+        } else {
+            let mut len = chunk.u16(start)? as usize;
+            let mut from = start + 2;
+            if len & 0x8000 != 0 {
+                len = ((len & 0x7FFF) << 16) | chunk.u16(start + 2)? as usize;
+                from += 2;
+            }
+            let units = (0..len)
+                .map(|i| chunk.u16(from + i * 2))
+                .collect::<Result<Vec<_>, _>>()?;
+            String::from_utf16_lossy(&units)
+        };
+        strings.push(string);
+    }
+    Ok(strings)
+}
+
+fn utf8_length(chunk: &Bytes<'_>, pos: usize) -> Result<(usize, usize), ArchiveError> {
+    let first = chunk.u8(pos)? as usize;
+    if first & 0x80 != 0 {
+        Ok((((first & 0x7F) << 8) | chunk.u8(pos + 1)? as usize, 2))
+    } else {
+        Ok((first, 1))
+    }
+}
+
+fn string_at(strings: &[String], index: u32) -> String {
+    strings.get(index as usize).cloned().unwrap_or_default()
+}
+
+/// 按 `Res_value` 的类型格式化属性值
+fn typed_value(data_type: u8, data: u32, strings: &[String]) -> String {
+    match data_type {
+        // TYPE_NULL
+        0x00 => String::new(),
+        // TYPE_REFERENCE
+        0x01 => format!("@0x{data:08x}"),
+        // TYPE_ATTRIBUTE
+        0x02 => format!("?0x{data:08x}"),
+        // TYPE_STRING
+        0x03 => string_at(strings, data),
+        // TYPE_FLOAT
+        0x04 => f32::from_bits(data).to_string(),
+        // TYPE_INT_HEX
+        0x11 => format!("0x{data:x}"),
+        // TYPE_INT_BOOLEAN
+        0x12 => (data != 0).to_string(),
+        // TYPE_INT_COLOR_*
+        0x1C..=0x1F => format!("#{data:08x}"),
+        // TYPE_INT_DEC 及其他整数类型
+        _ => (data as i32).to_string(),
+    }
+}
+
+/// 清单中常用的 `android:` 属性的资源 ID
+fn framework_attr(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0x0101_0001 => "label",
+        0x0101_0002 => "icon",
+        0x0101_0003 => "name",
+        0x0101_000f => "debuggable",
+        0x0101_020c => "minSdkVersion",
+        0x0101_021b => "versionCode",
+        0x0101_021c => "versionName",
+        0x0101_0270 => "targetSdkVersion",
+        0x0101_0271 => "maxSdkVersion",
+        0x0101_0572 => "compileSdkVersion",
+        0x0101_0573 => "compileSdkVersionCodename",
+        _ => return None,
+    })
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn malformed() -> ArchiveError {
+    ArchiveError::Other("Malformed binary XML".into())
+}
+
+/// 带边界检查的小端读取
+struct Bytes<'a>(&'a [u8]);
+
+impl Bytes<'_> {
+    fn u8(&self, pos: usize) -> Result<u8, ArchiveError> {
+        self.0.get(pos).copied().ok_or_else(malformed)
+    }
+
+    fn u16(&self, pos: usize) -> Result<u16, ArchiveError> {
+        let bytes = self.0.get(pos..pos + 2).ok_or_else(malformed)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&self, pos: usize) -> Result<u32, ArchiveError> {
+        let bytes = self.0.get(pos..pos + 4).ok_or_else(malformed)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+}
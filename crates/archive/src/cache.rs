@@ -0,0 +1,519 @@
+//! 列举结果的磁盘缓存
+//!
+//! TAR 系列（尤其 `.tar.xz`、`.tar.zst`）只有完整解压一遍才能列出全部条目，多 GB 的归档每次
+//! 打开都要重新解压。列举结果按归档路径和嵌套层级保存在缓存目录中，归档（含全部分卷）的
+//! 大小或修改时间变化后失效；缓存目录超过预算时先删除最久未使用的缓存文件。
+//!
+//! 缓存文件为 JSON Lines：第一行为头部，之后每行一个条目。写入与列举同步进行，
+//! 不在内存中保留完整列表；列举完整结束后才改名为正式的缓存文件。
+
+use crate::{volume, ArchiveError, Extract};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs::{self, File},
+    hash::{Hash, Hasher},
+    io::{BufRead, BufReader, BufWriter, Lines, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// 缓存文件格式版本，`Extract` 的字段变化时递增
+const CACHE_VERSION: u32 = 3;
+
+const CACHE_EXTENSION: &str = "jsonl";
+
+/// 同一进程内区分临时文件
+static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// 归档文件（分卷时为每一卷）的大小和修改时间
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FileStamp {
+    size: u64,
+    /// 修改时间距 UNIX 纪元的纳秒数，文件系统不支持时为 0
+    modified: u64,
+}
+
+/// 缓存文件的第一行
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheHeader {
+    version: u32,
+    /// 规范化后的归档路径和嵌套层级，用于排除散列冲突
+    path: String,
+    nested: Vec<String>,
+    stamps: Vec<FileStamp>,
+    /// 列举时的归档大小和分卷数，命中缓存时原样返回
+    archive_size: u64,
+    volumes: usize,
+}
+
+/// 列举结果的磁盘缓存
+#[derive(Debug, Clone)]
+pub struct ListingCache {
+    dir: PathBuf,
+    /// 缓存目录的总大小上限（字节）
+    max_bytes: u64,
+    /// 小于该大小的归档列举很快，不缓存
+    min_archive_size: u64,
+}
+
+impl Default for ListingCache {
+    /// 系统临时目录下的 `quicklook_archive_listing`，预算 256 MiB，只缓存 8 MiB 以上的归档
+    fn default() -> Self {
+        Self::new(
+            std::env::temp_dir().join("quicklook_archive_listing"),
+            256 * 1024 * 1024,
+        )
+    }
+}
+
+impl ListingCache {
+    pub fn new<P: Into<PathBuf>>(dir: P, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+            min_archive_size: 8 * 1024 * 1024,
+        }
+    }
+
+    /// 只缓存不小于 `size` 字节的归档（分卷时按总大小）
+    pub fn with_min_archive_size(mut self, size: u64) -> Self {
+        self.min_archive_size = size;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 删除全部缓存文件，返回删除的文件数
+    pub fn clear(&self) -> Result<u32, ArchiveError> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let mut removed = 0;
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            if entry.path().is_file() && fs::remove_file(entry.path()).is_ok() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// 查找仍然有效的缓存，命中时更新其修改时间作为最近使用时间
+    pub(crate) fn lookup(&self, path: &Path, nested: &[String]) -> Option<CacheReader> {
+        let key = CacheKey::new(path, nested)?;
+        let file = key.file(&self.dir);
+        let mut lines = BufReader::new(File::open(&file).ok()?).lines();
+        let header: CacheHeader = serde_json::from_str(&lines.next()?.ok()?).ok()?;
+        if header.version != CACHE_VERSION
+            || header.path != key.path
+            || header.nested != nested
+            || header.stamps != key.stamps
+        {
+            let _ = fs::remove_file(&file);
+            return None;
+        }
+
+        if let Ok(handle) = File::options().write(true).open(&file) {
+            let _ = handle.set_modified(SystemTime::now());
+        }
+        Some(CacheReader {
+            file,
+            lines,
+            archive_size: header.archive_size,
+            volumes: header.volumes,
+        })
+    }
+
+    /// 为即将进行的列举创建缓存写入器；归档太小或无法写入缓存目录时返回 `None`
+    pub(crate) fn writer(
+        &self,
+        path: &Path,
+        nested: &[String],
+        archive_size: u64,
+        volumes: usize,
+    ) -> Option<CacheWriter> {
+        let key = CacheKey::new(path, nested)?;
+        if key.stamps.iter().map(|stamp| stamp.size).sum::<u64>() < self.min_archive_size {
+            return None;
+        }
+
+        let open = || -> std::io::Result<CacheWriter> {
+            fs::create_dir_all(&self.dir)?;
+            let temp = self.dir.join(format!(
+                "{:016x}.{}.{}.tmp",
+                key.hash,
+                std::process::id(),
+                TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            let mut output = BufWriter::new(File::create(&temp)?);
+            let header = CacheHeader {
+                version: CACHE_VERSION,
+                path: key.path.clone(),
+                nested: nested.to_vec(),
+                stamps: key.stamps.clone(),
+                archive_size,
+                volumes,
+            };
+            serde_json::to_writer(&mut output, &header)?;
+            output.write_all(b"\n")?;
+            Ok(CacheWriter {
+                cache: self.clone(),
+                file: key.file(&self.dir),
+                temp,
+                output: Some(output),
+            })
+        };
+        match open() {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                log::warn!("Failed to create listing cache: {}", e);
+                None
+            },
+        }
+    }
+
+    /// 缓存目录超过预算时，按最近使用时间从旧到新删除缓存文件
+    fn trim(&self) -> std::io::Result<()> {
+        let mut files = Vec::new();
+        let mut total = 0;
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(CACHE_EXTENSION) {
+                continue;
+            }
+            let Ok(meta) = entry.metadata() else {
+                continue;
+            };
+            total += meta.len();
+            files.push((meta.modified().unwrap_or(UNIX_EPOCH), meta.len(), path));
+        }
+
+        files.sort();
+        for (_, size, path) in files {
+            if total <= self.max_bytes {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() {
+                total -= size;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 缓存文件名的来源：规范化路径、嵌套层级和各分卷的大小与修改时间
+struct CacheKey {
+    path: String,
+    stamps: Vec<FileStamp>,
+    hash: u64,
+}
+
+impl CacheKey {
+    fn new(path: &Path, nested: &[String]) -> Option<Self> {
+        let path = fs::canonicalize(path).ok()?;
+        let volumes = match volume::discover_volumes(&path).ok()? {
+            Some(set) => set.volumes,
+            None => vec![path.clone()],
+        };
+        let stamps = volumes
+            .iter()
+            .map(|volume| {
+                let meta = fs::metadata(volume).ok()?;
+                let modified = meta
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+                Some(FileStamp { size: meta.len(), modified })
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        let path = path.to_string_lossy().into_owned();
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        nested.hash(&mut hasher);
+        Some(Self { path, stamps, hash: hasher.finish() })
+    }
+
+    fn file(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{:016x}.{CACHE_EXTENSION}", self.hash))
+    }
+}
+
+/// 逐行读取命中的缓存
+pub(crate) struct CacheReader {
+    file: PathBuf,
+    lines: Lines<BufReader<File>>,
+    pub(crate) archive_size: u64,
+    pub(crate) volumes: usize,
+}
+
+impl Iterator for CacheReader {
+    type Item = Result<Extract, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let line = match self.lines.next()? {
+            Ok(line) => line,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(serde_json::from_str(&line).map_err(|e| {
+            // 损坏的缓存删除后，下次重新列举
+            let _ = fs::remove_file(&self.file);
+            ArchiveError::Other(format!("Corrupted listing cache: {e}"))
+        }))
+    }
+}
+
+/// 边列举边写入缓存，[`CacheWriter::commit`] 之前丢弃会删除临时文件
+pub(crate) struct CacheWriter {
+    cache: ListingCache,
+    file: PathBuf,
+    temp: PathBuf,
+    /// 写入失败后为 `None`，之后的条目不再写入
+    output: Option<BufWriter<File>>,
+}
+
+impl CacheWriter {
+    pub(crate) fn push(&mut self, entry: &Extract) {
+        let Some(output) = &mut self.output else {
+            return;
+        };
+        let result = serde_json::to_writer(&mut *output, entry)
+            .map_err(std::io::Error::from)
+            .and_then(|()| output.write_all(b"\n"));
+        if let Err(e) = result {
+            log::warn!("Failed to write listing cache: {}", e);
+            self.output = None;
+        }
+    }
+
+    /// 列举完整结束后改名为正式的缓存文件，并按预算清理旧缓存
+    pub(crate) fn commit(mut self) {
+        let Some(output) = self.output.take() else {
+            return;
+        };
+        let result = output
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .and_then(|_| fs::rename(&self.temp, &self.file))
+            .and_then(|()| self.cache.trim());
+        if let Err(e) = result {
+            log::warn!("Failed to save listing cache: {}", e);
+        }
+    }
+}
+
+impl Drop for CacheWriter {
+    fn drop(&mut self) {
+        // 已改名时临时文件不存在，删除失败可以忽略
+        self.output = None;
+        let _ = fs::remove_file(&self.temp);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ListingCache;
+    use crate::Extract;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("quicklook_cache_{}_{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn cache_in(dir: &Path, max_bytes: u64) -> ListingCache {
+        ListingCache::new(dir.join("cache"), max_bytes).with_min_archive_size(0)
+    }
+
+    fn store(cache: &ListingCache, path: &Path, names: &[&str]) {
+        let mut writer = cache.writer(path, &[], 42, 1).unwrap();
+        for name in names {
+            writer.push(&Extract::new(name.to_string(), 1, None, false));
+        }
+        writer.commit();
+    }
+
+    fn cached_names(cache: &ListingCache, path: &Path) -> Option<Vec<String>> {
+        let reader = cache.lookup(path, &[])?;
+        Some(reader.map(|entry| entry.unwrap().name).collect())
+    }
+
+    fn set_modified(path: &Path, time: SystemTime) {
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    fn cache_files(cache: &ListingCache) -> Vec<PathBuf> {
+        fs::read_dir(cache.dir())
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    #[test]
+    fn hits_until_source_changes() {
+        let dir = scratch_dir("stamps");
+        let cache = cache_in(&dir, u64::MAX);
+        let archive = dir.join("a.tar");
+        fs::write(&archive, b"archive").unwrap();
+
+        assert!(cache.lookup(&archive, &[]).is_none());
+        store(&cache, &archive, &["x", "y"]);
+        let reader = cache.lookup(&archive, &[]).unwrap();
+        assert_eq!((reader.archive_size, reader.volumes), (42, 1));
+        assert_eq!(cached_names(&cache, &archive).unwrap(), ["x", "y"]);
+        // 嵌套层级不同是另一份缓存
+        assert!(cache.lookup(&archive, &["inner.zip".to_string()]).is_none());
+
+        fs::write(&archive, b"archive, resized").unwrap();
+        assert!(cache.lookup(&archive, &[]).is_none());
+
+        store(&cache, &archive, &["x"]);
+        assert!(cache.lookup(&archive, &[]).is_some());
+        set_modified(&archive, SystemTime::now() - Duration::from_secs(3600));
+        assert!(cache.lookup(&archive, &[]).is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn misses_when_any_volume_changes() {
+        let dir = scratch_dir("volumes");
+        let cache = cache_in(&dir, u64::MAX);
+        let first = dir.join("data.bin.001");
+        fs::write(&first, b"part one").unwrap();
+        fs::write(dir.join("data.bin.002"), b"part two").unwrap();
+
+        store(&cache, &first, &["x"]);
+        assert!(cache.lookup(&first, &[]).is_some());
+
+        fs::write(dir.join("data.bin.002"), b"part two, rewritten").unwrap();
+        assert!(cache.lookup(&first, &[]).is_none());
+
+        store(&cache, &first, &["x"]);
+        fs::write(dir.join("data.bin.003"), b"part three").unwrap();
+        assert!(cache.lookup(&first, &[]).is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn trims_least_recently_used() {
+        let dir = scratch_dir("trim");
+        let archives: Vec<PathBuf> = ["a.tar", "b.tar", "c.tar"]
+            .iter()
+            .map(|name| dir.join(name))
+            .collect();
+        for archive in &archives {
+            fs::write(archive, b"archive").unwrap();
+        }
+
+        // 三个缓存文件大小相同，预算只够放下两个
+        let probe = cache_in(&dir, u64::MAX);
+        store(&probe, &archives[0], &["x"]);
+        let size = fs::metadata(&cache_files(&probe)[0]).unwrap().len();
+        let cache = cache_in(&dir, size * 2 + size / 2);
+
+        store(&cache, &archives[1], &["x"]);
+        let now = SystemTime::now();
+        for (index, file) in cache_files(&cache).iter().enumerate() {
+            set_modified(file, now - Duration::from_secs(100 + index as u64));
+        }
+        // 命中的缓存成为最近使用
+        assert!(cache.lookup(&archives[0], &[]).is_some());
+        store(&cache, &archives[2], &["x"]);
+
+        assert_eq!(cache_files(&cache).len(), 2);
+        assert!(cache.lookup(&archives[1], &[]).is_none());
+        assert!(cache.lookup(&archives[0], &[]).is_some());
+        assert!(cache.lookup(&archives[2], &[]).is_some());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ignores_unfinished_writes() {
+        let dir = scratch_dir("partial");
+        let cache = cache_in(&dir, u64::MAX);
+        let archive = dir.join("a.tar");
+        fs::write(&archive, b"archive").unwrap();
+
+        let mut writer = cache.writer(&archive, &[], 42, 1).unwrap();
+        writer.push(&Extract::new("x".to_string(), 1, None, false));
+        assert!(cache.lookup(&archive, &[]).is_none());
+        drop(writer);
+        assert!(cache.lookup(&archive, &[]).is_none());
+        assert!(cache_files(&cache).is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(feature = "tar")]
+    fn skips_cache_with_password_or_encoding() {
+        use crate::{CancelToken, NameEncoding};
+
+        let dir = scratch_dir("options");
+        let cache = cache_in(&dir, u64::MAX);
+        let archive = dir.join("a.tar");
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "real.txt", &b"data"[..])
+            .unwrap();
+        fs::write(&archive, builder.into_inner().unwrap()).unwrap();
+        store(&cache, &archive, &["cached.txt"]);
+
+        let list = |password: Option<&str>, encoding: NameEncoding| {
+            let mut names = Vec::new();
+            let summary = Extract::list_archive_each_cached(
+                &archive,
+                &[],
+                password,
+                encoding,
+                &cache,
+                &CancelToken::new(),
+                &mut |entry| {
+                    names.push(entry.name);
+                    Ok(true)
+                },
+                &mut |_| {},
+            )
+            .unwrap();
+            (names, summary.from_cache)
+        };
+
+        assert_eq!(
+            list(None, NameEncoding::Auto),
+            (vec!["cached.txt".into()], true)
+        );
+        assert_eq!(
+            list(Some("pw"), NameEncoding::Auto),
+            (vec!["real.txt".into()], false)
+        );
+        assert_eq!(
+            list(None, NameEncoding::Gbk),
+            (vec!["real.txt".into()], false)
+        );
+        // 跳过缓存的列举也不会覆盖已有的缓存
+        assert_eq!(list(None, NameEncoding::Auto).0, ["cached.txt"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,81 @@
+//! 长时间操作（完整性测试、大归档列举）的取消标记
+
+use crate::ArchiveError;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// 取消标记，克隆后的标记共享同一个状态，可以在其他线程中调用 [`CancelToken::cancel`]
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 请求取消，正在进行的操作会在下一个检查点返回 [`ArchiveError::Cancelled`]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// 已取消时返回 [`ArchiveError::Cancelled`]
+    pub fn check(&self) -> Result<(), ArchiveError> {
+        if self.is_cancelled() {
+            Err(ArchiveError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 读完 `reader` 并丢弃数据，返回读取的字节数；每读一块检查一次 `cancel`
+#[cfg(any(feature = "tar", feature = "sevenz"))]
+pub(crate) fn drain(
+    reader: &mut dyn std::io::Read,
+    cancel: &CancelToken,
+) -> Result<u64, ArchiveError> {
+    let mut buf = vec![0; 64 * 1024];
+    let mut total = 0;
+    loop {
+        cancel.check()?;
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        total += n as u64;
+    }
+}
+
+/// 每次读取前检查 `cancel` 的数据流，用于第三方库内部的读取循环（如 tar 跳过条目数据）
+///
+/// 取消后读取返回错误，调用方出错时应再用 [`CancelToken::check`] 区分取消与真正的读取错误。
+#[cfg(feature = "tar")]
+pub(crate) struct CancelReader<'a, R> {
+    inner: R,
+    cancel: &'a CancelToken,
+}
+
+#[cfg(feature = "tar")]
+impl<'a, R> CancelReader<'a, R> {
+    pub(crate) fn new(inner: R, cancel: &'a CancelToken) -> Self {
+        Self { inner, cancel }
+    }
+}
+
+#[cfg(feature = "tar")]
+impl<R: std::io::Read> std::io::Read for CancelReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            return Err(std::io::Error::other("Operation cancelled"));
+        }
+        self.inner.read(buf)
+    }
+}
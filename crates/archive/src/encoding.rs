@@ -0,0 +1,411 @@
+//! 条目名的字符编码
+//!
+//! ZIP 只有设置了 UTF-8 标志位（通用标志位第 11 位）或带有 Info-ZIP Unicode Path
+//! 扩展字段（0x7075）的条目名才确定是 UTF-8，其余按创建时系统的代码页保存：
+//! 简体中文 GBK、日文 Shift_JIS、韩文 EUC-KR、旧 DOS 工具的 CP437 等。
+//! 自动识别时把整个归档中需要猜测的条目名放在一起统计，比逐个判断可靠。
+//! CAB 与 ZIP 类似，没有 UTF-8 属性位的文件名按代码页保存。
+//!
+//! 7Z 和 RAR 的条目名本身是 Unicode，但在非 UTF-8 区域设置下创建的归档可能把原始字节
+//! 逐个当作 U+0000–U+00FF 保存（unrar 无法转换的字节则映射到私用区 U+E080–U+E0FF），
+//! 这类名称先还原为字节再按同样的规则解码。
+
+use crate::ArchiveError;
+use serde::Serialize;
+use std::{fmt, str::FromStr};
+
+/// 条目名的字符编码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NameEncoding {
+    /// 根据归档中全部条目名自动识别
+    #[default]
+    Auto,
+    Utf8,
+    /// 简体中文（按 GB18030 解码，兼容 GB2312/GBK）
+    Gbk,
+    /// 繁体中文
+    Big5,
+    /// 日文
+    ShiftJis,
+    /// 韩文
+    EucKr,
+    /// DOS 美国/西欧
+    Cp437,
+    /// DOS 俄文
+    Cp866,
+}
+
+impl NameEncoding {
+    /// 全部编码，按界面中的显示顺序
+    pub const ALL: [Self; 8] = [
+        Self::Auto,
+        Self::Utf8,
+        Self::Gbk,
+        Self::Big5,
+        Self::ShiftJis,
+        Self::EucKr,
+        Self::Cp437,
+        Self::Cp866,
+    ];
+
+    /// 标识名，与序列化结果相同
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Utf8 => "utf8",
+            Self::Gbk => "gbk",
+            Self::Big5 => "big5",
+            Self::ShiftJis => "shift_jis",
+            Self::EucKr => "euc_kr",
+            Self::Cp437 => "cp437",
+            Self::Cp866 => "cp866",
+        }
+    }
+}
+
+impl fmt::Display for NameEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl FromStr for NameEncoding {
+    type Err = ArchiveError;
+
+    /// 解析标识名，也接受常见别名和 Windows 代码页编号（不区分大小写，`-` 与 `_` 等价）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let label = s.trim().to_ascii_lowercase().replace('-', "_");
+        Ok(match label.as_str() {
+            "" | "auto" => Self::Auto,
+            "utf8" | "utf_8" | "cp65001" => Self::Utf8,
+            "gbk" | "gb2312" | "gb18030" | "cp936" => Self::Gbk,
+            "big5" | "cp950" => Self::Big5,
+            "shift_jis" | "sjis" | "cp932" => Self::ShiftJis,
+            "euc_kr" | "cp949" | "uhc" => Self::EucKr,
+            "cp437" | "ibm437" | "dos" => Self::Cp437,
+            "cp866" | "ibm866" => Self::Cp866,
+            _ => {
+                return Err(ArchiveError::Other(format!(
+                    "Unknown filename encoding: {s}"
+                )))
+            },
+        })
+    }
+}
+
+#[cfg(any(feature = "zip", feature = "rar", feature = "sevenz", feature = "cab"))]
+pub(crate) use decode::NameDecoder;
+
+#[cfg(any(feature = "zip", feature = "rar", feature = "sevenz", feature = "cab"))]
+mod decode {
+    use super::NameEncoding;
+    use encoding_rs::{Encoding, BIG5, EUC_KR, GB18030, GBK, IBM866, SHIFT_JIS, UTF_8};
+
+    /// CP437 中 0x80–0xFF 对应的字符
+    const CP437_HIGH: &str = concat!(
+        "ÇüéâäàåçêëèïîìÄÅ",
+        "ÉæÆôöòûùÿÖÜ¢£¥₧ƒ",
+        "áíóúñÑªº¿⌐¬½¼¡«»",
+        "░▒▓│┤╡╢╖╕╣║╗╝╜╛┐",
+        "└┴┬├─┼╞╟╚╔╩╦╠═╬╧",
+        "╨╤╥╙╘╒╓╫╪┘┌█▄▌▐▀",
+        "αßΓπΣσµτΦΘΩδ∞φε∩",
+        "≡±≥≤⌠⌡÷≈°∙·√ⁿ²■\u{A0}",
+    );
+
+    /// 多字节编码的候选，常用字比例相同时靠前的优先
+    const MULTI_BYTE: [NameEncoding; 4] = [
+        NameEncoding::ShiftJis,
+        NameEncoding::EucKr,
+        NameEncoding::Gbk,
+        NameEncoding::Big5,
+    ];
+
+    /// 非 ASCII 字符中落在常用字区的比例不低于该值才采用多字节编码
+    const MIN_COMMON_RATIO: f64 = 0.5;
+
+    /// unrar 把无法转换的字节 `b` 映射为 U+E000 + `b`，并在前面加上该标记
+    #[cfg(any(feature = "rar", feature = "sevenz"))]
+    const UNRAR_MAPPED_MARK: char = '\u{FFFE}';
+
+    /// 一个归档内条目名的解码规则，`None` 表示按 UTF-8 解码或保持原样
+    #[derive(Debug, Clone, Copy)]
+    pub(crate) struct NameDecoder(Option<NameEncoding>);
+
+    impl NameDecoder {
+        /// 按原始字节保存条目名的格式（ZIP、CAB）：`raw_names` 为需要猜测编码的条目名，
+        /// 已确定是 UTF-8 的条目名不必传入
+        #[cfg(any(feature = "zip", feature = "cab"))]
+        pub(crate) fn for_raw<'a>(
+            encoding: NameEncoding,
+            raw_names: impl IntoIterator<Item = &'a [u8]>,
+        ) -> Self {
+            match encoding {
+                NameEncoding::Auto => {
+                    let raw_names: Vec<&[u8]> = raw_names.into_iter().collect();
+                    Self(Some(detect(&raw_names)))
+                },
+                encoding => Self(Some(encoding)),
+            }
+        }
+
+        /// 以 Unicode 保存条目名的格式（7Z、RAR）：自动识别时只有还原出的字节能按
+        /// UTF-8 或多字节编码解码时才修正，避免把正常的西欧文字名称当作乱码
+        #[cfg(any(feature = "rar", feature = "sevenz"))]
+        pub(crate) fn for_unicode<'a>(
+            encoding: NameEncoding,
+            names: impl IntoIterator<Item = &'a str>,
+        ) -> Self {
+            match encoding {
+                NameEncoding::Auto => {
+                    let bytes: Vec<Vec<u8>> = names.into_iter().filter_map(legacy_bytes).collect();
+                    if bytes.is_empty() {
+                        return Self(None);
+                    }
+                    let bytes: Vec<&[u8]> = bytes.iter().map(Vec::as_slice).collect();
+                    let detected = detect(&bytes);
+                    let repair = detected == NameEncoding::Utf8 || MULTI_BYTE.contains(&detected);
+                    Self(repair.then_some(detected))
+                },
+                encoding => Self(Some(encoding)),
+            }
+        }
+
+        /// 解码原始字节，无效的字节替换为 U+FFFD
+        #[cfg(any(feature = "zip", feature = "cab"))]
+        pub(crate) fn decode(&self, raw: &[u8]) -> String {
+            match self.0 {
+                Some(NameEncoding::Cp437) => decode_cp437(raw),
+                Some(encoding) => match encoding_of(encoding) {
+                    Some(encoding) => encoding.decode_without_bom_handling(raw).0.into_owned(),
+                    None => String::from_utf8_lossy(raw).into_owned(),
+                },
+                None => String::from_utf8_lossy(raw).into_owned(),
+            }
+        }
+
+        /// 修正 Unicode 条目名：还原出的字节能完整解码时使用解码结果，否则保持原样
+        #[cfg(any(feature = "rar", feature = "sevenz"))]
+        pub(crate) fn repair(&self, name: String) -> String {
+            let Some(encoding) = self.0 else {
+                return name;
+            };
+            let Some(bytes) = legacy_bytes(&name) else {
+                return name;
+            };
+            let decoded = match encoding_of(encoding) {
+                Some(encoding) => encoding
+                    .decode_without_bom_handling_and_without_replacement(&bytes)
+                    .map(|decoded| decoded.into_owned()),
+                None => Some(decode_cp437(&bytes)),
+            };
+            decoded.unwrap_or(name)
+        }
+    }
+
+    /// 识别一组原始条目名的编码
+    fn detect(names: &[&[u8]]) -> NameEncoding {
+        let names: Vec<&[u8]> = names
+            .iter()
+            .copied()
+            .filter(|name| !name.is_ascii())
+            .collect();
+        if names.iter().all(|name| std::str::from_utf8(name).is_ok()) {
+            return NameEncoding::Utf8;
+        }
+
+        // chardetng 面向网页正文，条目名较短时容易猜成单字节编码，
+        // 因此只采用它给出的多字节编码，并要求常用字比例达标
+        let mut detector = chardetng::EncodingDetector::new();
+        for name in &names {
+            detector.feed(name, false);
+            detector.feed(b"\n", false);
+        }
+        detector.feed(b"", true);
+        let guess = detector.guess(None, false);
+        let guess = match guess {
+            _ if guess == GBK || guess == GB18030 => Some(NameEncoding::Gbk),
+            _ if guess == BIG5 => Some(NameEncoding::Big5),
+            _ if guess == SHIFT_JIS => Some(NameEncoding::ShiftJis),
+            _ if guess == EUC_KR => Some(NameEncoding::EucKr),
+            _ if guess == IBM866 => return NameEncoding::Cp866,
+            _ => None,
+        };
+        if let Some(encoding) = guess {
+            if decodes_all(encoding, &names) && common_ratio(encoding, &names) >= MIN_COMMON_RATIO {
+                return encoding;
+            }
+        }
+
+        let mut best = None;
+        for encoding in MULTI_BYTE {
+            if !decodes_all(encoding, &names) {
+                continue;
+            }
+            let ratio = common_ratio(encoding, &names);
+            if ratio >= MIN_COMMON_RATIO && best.map_or(true, |(_, best)| ratio > best) {
+                best = Some((encoding, ratio));
+            }
+        }
+        // 都不像时按 ZIP 规范的默认代码页处理
+        best.map_or(NameEncoding::Cp437, |(encoding, _)| encoding)
+    }
+
+    fn encoding_of(encoding: NameEncoding) -> Option<&'static Encoding> {
+        match encoding {
+            NameEncoding::Auto | NameEncoding::Utf8 => Some(UTF_8),
+            NameEncoding::Gbk => Some(GB18030),
+            NameEncoding::Big5 => Some(BIG5),
+            NameEncoding::ShiftJis => Some(SHIFT_JIS),
+            NameEncoding::EucKr => Some(EUC_KR),
+            NameEncoding::Cp866 => Some(IBM866),
+            NameEncoding::Cp437 => None,
+        }
+    }
+
+    fn decodes_all(encoding: NameEncoding, names: &[&[u8]]) -> bool {
+        let encoding = encoding_of(encoding).unwrap_or(UTF_8);
+        names.iter().all(|name| {
+            encoding
+                .decode_without_bom_handling_and_without_replacement(name)
+                .is_some()
+        })
+    }
+
+    /// 非 ASCII 字符中落在该编码常用字区的比例
+    ///
+    /// 常用字区：GBK 为 GB2312 区（A1A1–F7FE），Big5 为常用字和标点（A140–C67E），
+    /// Shift_JIS 为假名和第一水准汉字（82xx、83xx、889F–9872），EUC-KR 为韩文音节（B0A1–C8FE）。
+    /// 其他编码的文字按这些编码解码时大多落在生僻字或符号区。
+    fn common_ratio(encoding: NameEncoding, names: &[&[u8]]) -> f64 {
+        let (mut total, mut common) = (0usize, 0usize);
+        for name in names {
+            let mut i = 0;
+            while i < name.len() {
+                let lead = name[i];
+                if lead < 0x80 {
+                    i += 1;
+                    continue;
+                }
+                total += 1;
+                // Shift_JIS 的半角片假名是单字节
+                if encoding == NameEncoding::ShiftJis && (0xA1..=0xDF).contains(&lead) {
+                    i += 1;
+                    continue;
+                }
+                let trail = name.get(i + 1).copied().unwrap_or(0);
+                // GB18030 四字节字符的第二字节为数字
+                let len = if encoding == NameEncoding::Gbk && trail.is_ascii_digit() {
+                    4
+                } else {
+                    2
+                };
+                let code = u16::from_be_bytes([lead, trail]);
+                let is_common = match encoding {
+                    NameEncoding::Gbk => len == 2 && (0xA1..=0xF7).contains(&lead) && trail >= 0xA1,
+                    NameEncoding::Big5 => (0xA140..=0xC67E).contains(&code),
+                    NameEncoding::ShiftJis => {
+                        matches!(lead, 0x82 | 0x83) || (0x889F..=0x9872).contains(&code)
+                    },
+                    NameEncoding::EucKr => (0xB0..=0xC8).contains(&lead) && trail >= 0xA1,
+                    _ => false,
+                };
+                common += usize::from(is_common);
+                i += len;
+            }
+        }
+        if total == 0 {
+            0.0
+        } else {
+            common as f64 / total as f64
+        }
+    }
+
+    /// 把按单字节保存在 Unicode 名称中的原始字节还原出来；
+    /// 名称不含这类字节或含有其他非 ASCII 字符时返回 `None`
+    #[cfg(any(feature = "rar", feature = "sevenz"))]
+    fn legacy_bytes(name: &str) -> Option<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(name.len());
+        for c in name.chars().filter(|c| *c != UNRAR_MAPPED_MARK) {
+            let byte = match u32::from(c) {
+                code @ 0..=0xFF => code as u8,
+                code @ 0xE080..=0xE0FF => (code - 0xE000) as u8,
+                _ => return None,
+            };
+            bytes.push(byte);
+        }
+        (!bytes.is_ascii()).then_some(bytes)
+    }
+
+    fn decode_cp437(raw: &[u8]) -> String {
+        raw.iter()
+            .map(|&byte| match byte {
+                0..=0x7F => char::from(byte),
+                _ => CP437_HIGH
+                    .chars()
+                    .nth(usize::from(byte - 0x80))
+                    .unwrap_or(char::REPLACEMENT_CHARACTER),
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn encode(encoding: &'static Encoding, names: &[&str]) -> Vec<Vec<u8>> {
+            names
+                .iter()
+                .map(|name| encoding.encode(name).0.into_owned())
+                .collect()
+        }
+
+        fn detect_names(encoding: &'static Encoding, names: &[&str]) -> NameEncoding {
+            let raw = encode(encoding, names);
+            let raw: Vec<&[u8]> = raw.iter().map(Vec::as_slice).collect();
+            detect(&raw)
+        }
+
+        #[test]
+        fn detects_legacy_codepages_across_all_names() {
+            let zh = ["新建文件夹/", "新建文件夹/报告.docx", "国家.txt"];
+            assert_eq!(detect_names(GB18030, &zh), NameEncoding::Gbk);
+            let ja = ["ドキュメント/", "ドキュメント/報告書.txt", "日本語.txt"];
+            assert_eq!(detect_names(SHIFT_JIS, &ja), NameEncoding::ShiftJis);
+            let ko = ["문서/", "문서/보고서.hwp", "한글.txt"];
+            assert_eq!(detect_names(EUC_KR, &ko), NameEncoding::EucKr);
+            let big5 = ["新增資料夾/", "新增資料夾/報告.doc", "圖片.jpg"];
+            assert_eq!(detect_names(BIG5, &big5), NameEncoding::Big5);
+            let ru = ["Документы/", "Документы/отчёт.txt"];
+            assert_eq!(detect_names(IBM866, &ru), NameEncoding::Cp866);
+
+            // CP437 的 "café.txt"、"Prüfung.txt"
+            let dos: [&[u8]; 2] = [b"caf\x82.txt", b"Pr\x81fung.txt"];
+            assert_eq!(detect(&dos), NameEncoding::Cp437);
+            #[cfg(any(feature = "zip", feature = "cab"))]
+            {
+                let decoder = NameDecoder::for_raw(NameEncoding::Auto, dos);
+                assert_eq!(decoder.decode(dos[1]), "Prüfung.txt");
+            }
+        }
+
+        #[cfg(any(feature = "rar", feature = "sevenz"))]
+        #[cfg(any(feature = "rar", feature = "sevenz"))]
+        #[test]
+        fn repairs_bytes_stored_as_latin1() {
+            let mojibake: String = GB18030
+                .encode("文档.txt")
+                .0
+                .iter()
+                .map(|&byte| char::from(byte))
+                .collect();
+            let decoder = NameDecoder::for_unicode(NameEncoding::Auto, [mojibake.as_str()]);
+            assert_eq!(decoder.repair(mojibake), "文档.txt");
+
+            // 正常的西欧文字名称保持原样
+            let decoder = NameDecoder::for_unicode(NameEncoding::Auto, ["café.txt"]);
+            assert_eq!(decoder.repair("café.txt".to_string()), "café.txt");
+        }
+    }
+}
@@ -3,11 +3,30 @@ use std::fmt;
 #[derive(Debug)]
 pub enum ArchiveError {
     IoError(std::io::Error),
+    #[cfg(feature = "zip")]
     ZipError(zip::result::ZipError),
+    #[cfg(feature = "sevenz")]
     SevenZError(sevenz_rust::Error),
+    #[cfg(feature = "cpio")]
     CpioError(hadris_cpio::error::CpioError),
     UnsupportedFormat(String),
+    /// 格式能够识别，但对应的 cargo feature 未启用，值为 feature 名
+    FormatDisabled(&'static str),
     InvalidPath(String),
+    EntryNotFound(String),
+    /// 条目是符号链接或硬链接，没有可以解压的文件数据；`target` 为链接指向的路径（如果已知）
+    LinkEntry {
+        entry: String,
+        target: Option<String>,
+    },
+    /// 分卷归档缺少的分卷文件名
+    MissingVolumes(Vec<String>),
+    /// 归档或条目已加密，但没有提供密码
+    PasswordRequired,
+    /// 提供的密码无法解密归档或条目
+    WrongPassword,
+    /// 操作被 [`crate::CancelToken`] 取消
+    Cancelled,
     Other(String),
 }
 
@@ -15,11 +34,29 @@ impl fmt::Display for ArchiveError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ArchiveError::IoError(err) => write!(f, "IO error: {err}"),
+            #[cfg(feature = "zip")]
             ArchiveError::ZipError(err) => write!(f, "ZIP error: {err}"),
+            #[cfg(feature = "sevenz")]
             ArchiveError::SevenZError(err) => write!(f, "7Z error: {err}"),
+            #[cfg(feature = "cpio")]
             ArchiveError::CpioError(err) => write!(f, "CPIO error: {err}"),
             ArchiveError::UnsupportedFormat(fmt) => write!(f, "Unsupported format: {fmt}"),
+            ArchiveError::FormatDisabled(feature) => write!(
+                f,
+                "Format disabled at build time: enable the `{feature}` feature of quicklook-archive"
+            ),
             ArchiveError::InvalidPath(path) => write!(f, "Invalid path: {path}"),
+            ArchiveError::EntryNotFound(name) => write!(f, "Entry not found: {name}"),
+            ArchiveError::LinkEntry { entry, target } => match target {
+                Some(target) => write!(f, "Entry is a link: {entry} -> {target}"),
+                None => write!(f, "Entry is a link: {entry}"),
+            },
+            ArchiveError::MissingVolumes(names) => {
+                write!(f, "Missing archive volumes: {}", names.join(", "))
+            },
+            ArchiveError::PasswordRequired => write!(f, "Password required"),
+            ArchiveError::WrongPassword => write!(f, "Wrong password"),
+            ArchiveError::Cancelled => write!(f, "Operation cancelled"),
             ArchiveError::Other(msg) => write!(f, "Error: {msg}"),
         }
     }
@@ -29,8 +66,11 @@ impl std::error::Error for ArchiveError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             ArchiveError::IoError(err) => Some(err),
+            #[cfg(feature = "zip")]
             ArchiveError::ZipError(err) => Some(err),
+            #[cfg(feature = "sevenz")]
             ArchiveError::SevenZError(err) => Some(err),
+            #[cfg(feature = "cpio")]
             ArchiveError::CpioError(err) => Some(err),
             _ => None,
         }
@@ -43,18 +83,32 @@ impl From<std::io::Error> for ArchiveError {
     }
 }
 
+#[cfg(feature = "zip")]
 impl From<zip::result::ZipError> for ArchiveError {
     fn from(err: zip::result::ZipError) -> Self {
-        ArchiveError::ZipError(err)
+        use zip::result::ZipError;
+        match err {
+            ZipError::InvalidPassword => ArchiveError::WrongPassword,
+            ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+                ArchiveError::PasswordRequired
+            },
+            err => ArchiveError::ZipError(err),
+        }
     }
 }
 
+#[cfg(feature = "sevenz")]
 impl From<sevenz_rust::Error> for ArchiveError {
     fn from(err: sevenz_rust::Error) -> Self {
-        ArchiveError::SevenZError(err)
+        match err {
+            sevenz_rust::Error::PasswordRequired => ArchiveError::PasswordRequired,
+            sevenz_rust::Error::MaybeBadPassword(_) => ArchiveError::WrongPassword,
+            err => ArchiveError::SevenZError(err),
+        }
     }
 }
 
+#[cfg(feature = "cpio")]
 impl From<hadris_cpio::error::CpioError> for ArchiveError {
     fn from(err: hadris_cpio::error::CpioError) -> Self {
         ArchiveError::CpioError(err)
@@ -1,38 +1,145 @@
-use crate::{ArchiveError, Extract};
+use crate::reader::{
+    collect_entries, ArchiveMetadata, ArchiveReader, EntryFn, Input, ListFn, StreamInput, VisitFn,
+};
+use crate::{ArchiveError, ArchiveFormat, EntryTime, Extract};
 use std::{
     fs::File,
-    path::Path,
-    time::{Duration, UNIX_EPOCH},
+    io::Read,
+    path::{Path, PathBuf},
 };
 
-/// 列举 AR 文件条目（`.deb` 内层格式、`.a` 静态库等）
+/// 列举 AR 文件条目（`.a` 静态库等；`.deb` 由 deb 读取器列出其中安装的文件）
 pub fn list_ar_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
-    let file = File::open(path)?;
-    let mut archive = ar::Archive::new(file);
-    let mut entries = Vec::new();
+    list_ar_entries_from(File::open(path)?)
+}
+
+/// 从任意数据流列举 AR 条目（用于嵌套在其他归档中的 AR）
+pub(crate) fn list_ar_entries_from<R: Read>(reader: R) -> Result<Vec<Extract>, ArchiveError> {
+    collect_entries(|f| each_ar_entry(reader, f))
+}
+
+/// 顺序读取 AR 流，每读到一个头部就交给 `f`
+pub(crate) fn each_ar_entry<R: Read>(reader: R, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+    let mut archive = ar::Archive::new(reader);
 
     while let Some(entry_result) = archive.next_entry() {
         let mut entry = entry_result?;
-        let identifier = entry.header().identifier();
-        let name = std::str::from_utf8(identifier)
-            .map(str::to_string)
-            .unwrap_or_else(|_| String::from_utf8_lossy(identifier).into_owned());
-        let size = entry.header().size();
-        let is_dir = name.ends_with('/');
-
-        // AR 头部存储 mtime（UNIX 秒），转换为与其他格式一致的本地时区时间格式
-        let mtime = entry.header().mtime();
-        let dt = UNIX_EPOCH + Duration::from_secs(mtime);
-        let last_modified = chrono::DateTime::<chrono::Local>::from(dt)
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string();
-
-        entries.push(Extract::new(name, size, last_modified, is_dir));
+        if !f(ar_entry(entry.header()))? {
+            break;
+        }
 
         // 跳过当前条目的数据，使 reader 推进到下一个 header。
         use std::io::{copy, sink};
         let _ = copy(&mut entry, &mut sink());
     }
 
-    Ok(entries)
+    Ok(())
+}
+
+/// AR 成员头部记录的信息
+fn ar_entry(header: &ar::Header) -> Extract {
+    let identifier = header.identifier();
+    let name = std::str::from_utf8(identifier)
+        .map(str::to_string)
+        .unwrap_or_else(|_| String::from_utf8_lossy(identifier).into_owned());
+    let size = header.size();
+    let is_dir = name.ends_with('/');
+
+    // AR 头部存储 mtime（UNIX 秒）；`ar D` 生成的确定性归档中为 0
+    let mtime = header.mtime();
+    let last_modified = i64::try_from(mtime).ok().and_then(EntryTime::from_unix);
+
+    let mut item = Extract::new(name, size, last_modified, is_dir);
+    // AR 成员不压缩
+    item.compressed_size = Some(size);
+    item.compression_method = Some("store".to_string());
+    item.set_mode(header.mode());
+    item.uid = Some(u64::from(header.uid()));
+    item.gid = Some(u64::from(header.gid()));
+    item
+}
+
+/// 顺序读取 AR 流中的每个成员
+pub(crate) fn for_each_ar_entry<R: Read>(
+    reader: R,
+    f: &mut VisitFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut archive = ar::Archive::new(reader);
+
+    while let Some(entry_result) = archive.next_entry() {
+        let mut entry = entry_result?;
+        let item = ar_entry(entry.header());
+        if item.dir {
+            continue;
+        }
+        if !f(&item, Ok(&mut entry))? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// 顺序扫描 AR 流，找到指定成员后把它的数据流交给 `f` 处理
+pub(crate) fn with_ar_entry<R: Read, T>(
+    reader: R,
+    entry_name: &str,
+    f: &mut dyn FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut archive = ar::Archive::new(reader);
+
+    while let Some(entry_result) = archive.next_entry() {
+        let mut entry = entry_result?;
+        if String::from_utf8_lossy(entry.header().identifier()) == entry_name {
+            return f(&mut entry);
+        }
+    }
+
+    Err(ArchiveError::EntryNotFound(entry_name.to_string()))
+}
+
+/// 解压 AR 中的单个成员到目标目录
+pub fn extract_ar_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    with_ar_entry(File::open(path)?, entry_name, &mut |data| {
+        super::write_entry_to_dir(data, entry_name, dest_dir)
+    })
+}
+
+/// AR 读取器
+pub struct ArReader<'a> {
+    input: StreamInput<'a>,
+}
+
+impl<'a> ArchiveReader<'a> for ArReader<'a> {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self { input: StreamInput::new(input) })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Ar)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        list_ar_entries_from(self.input.take()?)
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        each_ar_entry(self.input.take()?, f)
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        with_ar_entry(self.input.take()?, entry_name, f)
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        for_each_ar_entry(self.input.take()?, f)
+    }
 }
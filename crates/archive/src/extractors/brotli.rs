@@ -0,0 +1,32 @@
+use crate::{ArchiveError, ArchiveFormat, Extract};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// Brotli 解码器的输入缓冲区大小
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// 创建 Brotli 流式解码器
+pub(crate) fn brotli_decoder<'a, R: Read + 'a>(reader: R) -> Box<dyn Read + 'a> {
+    Box::new(brotli_decompressor::Decompressor::new(reader, BUFFER_SIZE))
+}
+
+/// 列举 TAR.BR 文件条目（内容不是 TAR 时返回一个虚拟条目）
+pub fn list_tar_br_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    super::compressed::list_compressed_entries(ArchiveFormat::TarBr, path.as_ref())
+}
+
+/// 解压 TAR.BR 中的单个文件到目标目录
+pub fn extract_tar_br_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarBr,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
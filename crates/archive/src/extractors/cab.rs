@@ -0,0 +1,496 @@
+//! Microsoft Cabinet（.cab），Windows 安装程序、驱动包和系统更新使用
+//!
+//! 文件数据按文件夹（folder）连续压缩，读取一个文件需要从所在文件夹的开头解压。支持不压缩和
+//! MSZIP（每个数据块以 `CK` 开头，是独立的 deflate 数据，但可以引用前一块的 32 KiB 历史）；
+//! Quantum、LZX 压缩以及跨多个 cab 的文件只能列举，读取时返回 [`ArchiveError::UnsupportedFormat`]。
+
+use crate::encoding::{NameDecoder, NameEncoding};
+use crate::reader::{ArchiveMetadata, ArchiveReader, EntryFn, Input, ReadSeek, SeekInput, VisitFn};
+use crate::{ArchiveError, ArchiveFormat, EntryTime, Extract};
+use miniz_oxide::inflate::core::{decompress, inflate_flags, DecompressorOxide};
+use miniz_oxide::inflate::TINFLStatus;
+use std::{
+    io::{self, Read, SeekFrom},
+    path::Path,
+};
+
+const SIGNATURE: &[u8; 4] = b"MSCF";
+const HEADER_SIZE: usize = 36;
+const FOLDER_SIZE: usize = 8;
+const FILE_HEADER_SIZE: usize = 16;
+const DATA_HEADER_SIZE: usize = 8;
+
+/// 头部标志：存在上一个/下一个 cab 的名称、存在保留区
+const FLAG_PREV_CABINET: u16 = 0x0001;
+const FLAG_NEXT_CABINET: u16 = 0x0002;
+const FLAG_RESERVE_PRESENT: u16 = 0x0004;
+
+/// 文件属性中表示文件名为 UTF-8 的标志位，没有时按代码页保存
+const ATTR_NAME_IS_UTF: u16 = 0x80;
+
+/// 文件夹序号的特殊值：文件从上一个 cab 延续、延续到下一个 cab，或两者兼有
+const FOLDER_CONTINUED_FROM_PREV: u16 = 0xFFFD;
+const FOLDER_CONTINUED_TO_NEXT: u16 = 0xFFFE;
+const FOLDER_CONTINUED_PREV_AND_NEXT: u16 = 0xFFFF;
+
+/// MSZIP 数据块的最大解压大小，也是可以引用的历史长度
+const MSZIP_BLOCK_SIZE: usize = 32 * 1024;
+
+/// 以 `\0` 结尾的名称（文件名、相邻 cab 的名称）的最大长度
+const MAX_NAME_LEN: usize = 256;
+
+/// 文件夹的压缩方式，取 typeCompress 的低 4 位
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compression {
+    None,
+    MsZip,
+    Quantum,
+    Lzx,
+    Unknown(u16),
+}
+
+impl Compression {
+    fn from_raw(raw: u16) -> Self {
+        match raw & 0x000F {
+            0 => Self::None,
+            1 => Self::MsZip,
+            2 => Self::Quantum,
+            3 => Self::Lzx,
+            other => Self::Unknown(other),
+        }
+    }
+
+    fn name(self) -> String {
+        match self {
+            Self::None => "store".to_string(),
+            Self::MsZip => "mszip".to_string(),
+            Self::Quantum => "quantum".to_string(),
+            Self::Lzx => "lzx".to_string(),
+            Self::Unknown(raw) => format!("unknown({raw})"),
+        }
+    }
+}
+
+/// CFFOLDER：一段连续压缩的数据
+struct Folder {
+    /// 第一个 CFDATA 数据块的偏移
+    data_offset: u64,
+    blocks: u16,
+    compression: Compression,
+}
+
+/// CFFILE：文件在所属文件夹解压后数据中的位置
+struct CabFile {
+    size: u32,
+    offset: u32,
+    folder: u16,
+}
+
+/// 解析后的 cab 目录
+struct Cabinet {
+    folders: Vec<Folder>,
+    /// 每个 CFDATA 数据块头部之后的保留字节数
+    data_reserve: u8,
+    entries: Vec<(Extract, CabFile)>,
+}
+
+fn u16_le(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_le(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// 读取以 `\0` 结尾的字符串（不含结尾的 `\0`）
+fn read_cstring(reader: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut byte = [0u8; 1];
+    loop {
+        reader.read_exact(&mut byte)?;
+        if byte[0] == 0 {
+            return Ok(bytes);
+        }
+        if bytes.len() == MAX_NAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "CAB name is not terminated",
+            ));
+        }
+        bytes.push(byte[0]);
+    }
+}
+
+/// 读取头部、文件夹表和文件表，条目名按 `encoding` 解码
+fn read_cabinet(image: &mut dyn ReadSeek, encoding: NameEncoding) -> Result<Cabinet, ArchiveError> {
+    let mut header = [0u8; HEADER_SIZE];
+    image.read_exact(&mut header)?;
+    if &header[..4] != SIGNATURE {
+        return Err(ArchiveError::UnsupportedFormat(
+            "Not a Microsoft Cabinet file".into(),
+        ));
+    }
+    let files_offset = u32_le(&header, 16);
+    let folder_count = u16_le(&header, 26);
+    let file_count = u16_le(&header, 28);
+    let flags = u16_le(&header, 30);
+
+    let (mut folder_reserve, mut data_reserve) = (0, 0);
+    if flags & FLAG_RESERVE_PRESENT != 0 {
+        let mut reserve = [0u8; 4];
+        image.read_exact(&mut reserve)?;
+        folder_reserve = usize::from(reserve[2]);
+        data_reserve = reserve[3];
+        image.seek(SeekFrom::Current(i64::from(u16_le(&reserve, 0))))?;
+    }
+    // 上一个/下一个 cab 的文件名和磁盘名
+    for flag in [FLAG_PREV_CABINET, FLAG_NEXT_CABINET] {
+        if flags & flag != 0 {
+            read_cstring(image)?;
+            read_cstring(image)?;
+        }
+    }
+
+    let mut folders = Vec::with_capacity(usize::from(folder_count));
+    let mut raw = vec![0u8; FOLDER_SIZE + folder_reserve];
+    for _ in 0..folder_count {
+        image.read_exact(&mut raw)?;
+        folders.push(Folder {
+            data_offset: u64::from(u32_le(&raw, 0)),
+            blocks: u16_le(&raw, 4),
+            compression: Compression::from_raw(u16_le(&raw, 6)),
+        });
+    }
+
+    image.seek(SeekFrom::Start(u64::from(files_offset)))?;
+    let mut files = Vec::with_capacity(usize::from(file_count));
+    for _ in 0..file_count {
+        let mut raw = [0u8; FILE_HEADER_SIZE];
+        image.read_exact(&mut raw)?;
+        let name = read_cstring(image)?;
+        files.push((raw, name));
+    }
+
+    // 没有 UTF-8 标志的非 ASCII 文件名一起统计编码
+    let legacy = |(raw, name): &&([u8; FILE_HEADER_SIZE], Vec<u8>)| {
+        u16_le(raw, 14) & ATTR_NAME_IS_UTF == 0 && !name.is_ascii()
+    };
+    let decoder = if encoding != NameEncoding::Auto || !files.iter().any(|file| legacy(&file)) {
+        NameDecoder::for_raw(encoding, [])
+    } else {
+        NameDecoder::for_raw(
+            encoding,
+            files.iter().filter(legacy).map(|(_, name)| name.as_slice()),
+        )
+    };
+
+    let entries = files
+        .into_iter()
+        .map(|(raw, name)| {
+            let attributes = u16_le(&raw, 14);
+            let name = if attributes & ATTR_NAME_IS_UTF != 0 {
+                String::from_utf8_lossy(&name).into_owned()
+            } else {
+                decoder.decode(&name)
+            };
+            let file = CabFile {
+                size: u32_le(&raw, 0),
+                offset: u32_le(&raw, 4),
+                folder: u16_le(&raw, 8),
+            };
+            let mut item = Extract::new(
+                name.replace('\\', "/"),
+                u64::from(file.size),
+                EntryTime::from_dos(u16_le(&raw, 10), u16_le(&raw, 12)),
+                false,
+            );
+            item.compression_method =
+                folder_of(&folders, file.folder).map(|folder| folder.compression.name());
+            (item, file)
+        })
+        .collect();
+
+    Ok(Cabinet { folders, data_reserve, entries })
+}
+
+/// 文件所在的文件夹；跨 cab 的文件延续自第一个或延续到最后一个文件夹
+fn folder_of(folders: &[Folder], index: u16) -> Option<&Folder> {
+    match index {
+        FOLDER_CONTINUED_FROM_PREV | FOLDER_CONTINUED_PREV_AND_NEXT => folders.first(),
+        FOLDER_CONTINUED_TO_NEXT => folders.last(),
+        index => folders.get(usize::from(index)),
+    }
+}
+
+/// CFDATA 的校验和：按小端 32 位字异或，末尾不足 4 字节的部分按大端拼接
+fn checksum(data: &[u8], seed: u32) -> u32 {
+    let mut chunks = data.chunks_exact(4);
+    let mut sum = seed;
+    for chunk in &mut chunks {
+        sum ^= u32_le(chunk, 0);
+    }
+    let tail = chunks
+        .remainder()
+        .iter()
+        .fold(0u32, |acc, &byte| (acc << 8) | u32::from(byte));
+    sum ^ tail
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// 顺序解压一个文件夹的数据
+struct FolderReader<'r> {
+    image: &'r mut dyn ReadSeek,
+    compression: Compression,
+    data_reserve: u8,
+    /// 下一个 CFDATA 的偏移及剩余块数
+    next_block: u64,
+    remaining_blocks: u16,
+    /// 当前块解压后的数据；MSZIP 时前面保留最多 32 KiB 的历史
+    buffer: Vec<u8>,
+    pos: usize,
+    inflater: Box<DecompressorOxide>,
+    /// 已交出的字节数，即在文件夹解压后数据中的位置
+    position: u64,
+}
+
+impl<'r> FolderReader<'r> {
+    fn new(
+        image: &'r mut dyn ReadSeek,
+        folder: &Folder,
+        data_reserve: u8,
+    ) -> Result<Self, ArchiveError> {
+        if !matches!(folder.compression, Compression::None | Compression::MsZip) {
+            return Err(ArchiveError::UnsupportedFormat(format!(
+                "CAB {} compression",
+                folder.compression.name()
+            )));
+        }
+        Ok(Self {
+            image,
+            compression: folder.compression,
+            data_reserve,
+            next_block: folder.data_offset,
+            remaining_blocks: folder.blocks,
+            buffer: Vec::new(),
+            pos: 0,
+            inflater: Box::default(),
+            position: 0,
+        })
+    }
+
+    /// 跳到文件夹解压后数据中的 `offset`，只能向后跳
+    fn skip_to(&mut self, offset: u64) -> io::Result<()> {
+        let skip = offset.saturating_sub(self.position);
+        let skipped = io::copy(&mut self.by_ref().take(skip), &mut io::sink())?;
+        if skipped < skip {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        Ok(())
+    }
+
+    fn load_block(&mut self) -> io::Result<()> {
+        self.image.seek(SeekFrom::Start(self.next_block))?;
+        let mut header = [0u8; DATA_HEADER_SIZE];
+        self.image.read_exact(&mut header)?;
+        let expected = u32_le(&header, 0);
+        let packed_size = usize::from(u16_le(&header, 4));
+        let unpacked_size = usize::from(u16_le(&header, 6));
+        if unpacked_size == 0 {
+            return Err(invalid_data("CAB data continues in the next cabinet"));
+        }
+        self.image
+            .seek(SeekFrom::Current(i64::from(self.data_reserve)))?;
+        let mut data = vec![0u8; packed_size];
+        self.image.read_exact(&mut data)?;
+        self.next_block += (DATA_HEADER_SIZE + usize::from(self.data_reserve) + packed_size) as u64;
+        self.remaining_blocks -= 1;
+
+        if expected != 0 && checksum(&header[4..8], checksum(&data, 0)) != expected {
+            return Err(invalid_data("CAB data block checksum mismatch"));
+        }
+
+        match self.compression {
+            Compression::MsZip => self.inflate_block(&data, unpacked_size),
+            _ => {
+                if packed_size != unpacked_size {
+                    return Err(invalid_data("CAB stored block size mismatch"));
+                }
+                self.buffer = data;
+                self.pos = 0;
+                Ok(())
+            },
+        }
+    }
+
+    /// 解压一个 MSZIP 块，上一块末尾的 32 KiB 留在缓冲区开头供其引用
+    fn inflate_block(&mut self, data: &[u8], unpacked_size: usize) -> io::Result<()> {
+        let data = data
+            .strip_prefix(b"CK")
+            .ok_or_else(|| invalid_data("Invalid MSZIP block signature"))?;
+        let history = self.buffer.len().min(MSZIP_BLOCK_SIZE);
+        self.buffer.drain(..self.buffer.len() - history);
+        self.buffer.resize(history + unpacked_size, 0);
+
+        self.inflater.init();
+        let (status, _, written) = decompress(
+            &mut self.inflater,
+            data,
+            &mut self.buffer,
+            history,
+            inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
+        );
+        if status != TINFLStatus::Done || written != unpacked_size {
+            return Err(invalid_data("Corrupt MSZIP block"));
+        }
+        self.pos = history;
+        Ok(())
+    }
+}
+
+impl Read for FolderReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buffer.len() {
+            if self.remaining_blocks == 0 {
+                return Ok(0);
+            }
+            self.load_block()?;
+        }
+        let n = buf.len().min(self.buffer.len() - self.pos);
+        buf[..n].copy_from_slice(&self.buffer[self.pos..self.pos + n]);
+        self.pos += n;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+/// 列举 cab 中的文件
+pub fn list_cab_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    CabReader::open(Input::Path(path.as_ref().to_path_buf()), None)?.list()
+}
+
+pub struct CabReader {
+    input: SeekInput,
+    encoding: NameEncoding,
+    /// 第一次列举或读取时解析的目录
+    cabinet: Option<Cabinet>,
+}
+
+impl CabReader {
+    fn load_cabinet(&mut self) -> Result<&Cabinet, ArchiveError> {
+        if self.cabinet.is_none() {
+            let (mut image, _) = self.input.reader()?;
+            self.cabinet = Some(read_cabinet(&mut *image, self.encoding)?);
+        }
+        Ok(self.cabinet.as_ref().expect("cabinet loaded"))
+    }
+}
+
+/// 打开文件所在的文件夹，跨 cab 的文件无法读取
+fn open_folder<'r>(
+    image: &'r mut dyn ReadSeek,
+    cabinet: &Cabinet,
+    file: &CabFile,
+) -> Result<FolderReader<'r>, ArchiveError> {
+    let folder = match file.folder {
+        FOLDER_CONTINUED_FROM_PREV | FOLDER_CONTINUED_TO_NEXT | FOLDER_CONTINUED_PREV_AND_NEXT => {
+            None
+        },
+        index => cabinet.folders.get(usize::from(index)),
+    };
+    let folder = folder.ok_or_else(|| {
+        ArchiveError::UnsupportedFormat("CAB file spanning multiple cabinets".into())
+    })?;
+    FolderReader::new(image, folder, cabinet.data_reserve)
+}
+
+impl<'a> ArchiveReader<'a> for CabReader {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self {
+            input: SeekInput::new(input)?,
+            encoding: NameEncoding::Auto,
+            cabinet: None,
+        })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Cab)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        Ok(self
+            .load_cabinet()?
+            .entries
+            .iter()
+            .map(|(item, _)| item.clone())
+            .collect())
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn set_name_encoding(&mut self, encoding: NameEncoding) {
+        self.encoding = encoding;
+        self.cabinet = None;
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        self.load_cabinet()?;
+        let cabinet = self.cabinet.as_ref().expect("cabinet loaded");
+        let (_, file) = cabinet
+            .entries
+            .iter()
+            .find(|(item, _)| item.name == entry_name)
+            .ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))?;
+        let (mut image, _) = self.input.reader()?;
+        let mut folder = open_folder(&mut *image, cabinet, file)?;
+        folder.skip_to(u64::from(file.offset))?;
+        f(&mut folder.take(u64::from(file.size)))
+    }
+
+    /// 按文件夹顺序解压，每个文件夹只解压一遍
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        self.load_cabinet()?;
+        let cabinet = self.cabinet.as_ref().expect("cabinet loaded");
+        let (mut image, _) = self.input.reader()?;
+
+        let mut order: Vec<&(Extract, CabFile)> = cabinet.entries.iter().collect();
+        order.sort_by_key(|(_, file)| (file.folder, file.offset));
+
+        let mut current: Option<(u16, FolderReader)> = None;
+        for (item, file) in order {
+            // 偏移比当前位置靠前（重复引用同一段数据）时从文件夹开头重新解压
+            let reusable = matches!(&current, Some((index, folder))
+                if *index == file.folder && folder.position <= u64::from(file.offset));
+            if !reusable {
+                current = None;
+                match open_folder(&mut *image, cabinet, file) {
+                    Ok(folder) => current = Some((file.folder, folder)),
+                    Err(err) => {
+                        if !f(item, Err(err))? {
+                            break;
+                        }
+                        continue;
+                    },
+                }
+            }
+            let (_, folder) = current.as_mut().expect("folder opened");
+            let keep_going = match folder.skip_to(u64::from(file.offset)) {
+                Ok(()) => f(item, Ok(&mut folder.by_ref().take(u64::from(file.size))))?,
+                Err(err) => f(item, Err(err.into()))?,
+            };
+            if !keep_going {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
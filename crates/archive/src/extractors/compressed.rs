@@ -0,0 +1,405 @@
+//! 单流压缩文件（.gz/.bz2/.xz/.zst/.lz4/.lzma/.lz/.br/.Z）
+//!
+//! 解压后的内容是 TAR 时按 TAR 处理；否则（如 `access.log.gz`、`dump.sql.xz`）视为只包含
+//! 一个文件的归档。该文件的条目名优先取 gzip 头部的原始文件名（FNAME），
+//! 缺省时为去掉压缩扩展名后的文件名。
+
+use crate::cancel::{drain, CancelReader};
+use crate::format::{is_tar_header, read_header};
+use crate::reader::{
+    collect_entries, ArchiveMetadata, ArchiveReader, EntryFn, Input, ListFn, StreamInput, VisitFn,
+};
+use crate::{ArchiveError, ArchiveFormat, CancelToken, EntryTime, Extract};
+use std::{
+    fs::File,
+    io::{BufReader, Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+/// 解压后的数据流，以及从压缩头部读到的信息
+pub(crate) struct Payload<'a> {
+    /// gzip 头部记录的原始文件名
+    name: Option<String>,
+    /// gzip 头部记录的修改时间（UNIX 秒）
+    mtime: Option<u32>,
+    /// 解压后的内容是否为 TAR
+    is_tar: bool,
+    /// 完整的解压数据流（已读取的开头部分会被重放）
+    reader: Box<dyn Read + 'a>,
+}
+
+/// 打开压缩流并探测解压后的内容是否为 TAR
+pub(crate) fn open_payload<'a, R: Read + 'a>(
+    format: ArchiveFormat,
+    reader: R,
+) -> Result<Payload<'a>, ArchiveError> {
+    let mut name = None;
+    let mut mtime = None;
+
+    let (head, decoder): (Vec<u8>, Box<dyn Read + 'a>) = match format {
+        ArchiveFormat::TarGz => {
+            // 日志轮转等场景常见多个 gzip 成员首尾相接，需要全部解压
+            let mut decoder = flate2::read::MultiGzDecoder::new(reader);
+            let head = read_header(&mut decoder)?;
+            if let Some(header) = decoder.header() {
+                name = header
+                    .filename()
+                    .map(|raw| String::from_utf8_lossy(raw).into_owned())
+                    .filter(|name| !name.is_empty());
+                mtime = Some(header.mtime()).filter(|mtime| *mtime != 0);
+            }
+            (head, Box::new(decoder))
+        },
+        format if is_stream_format(format) => {
+            let mut decoder = super::tar::tar_stream(format, reader)?;
+            (read_header(&mut decoder)?, decoder)
+        },
+        other => return Err(ArchiveError::UnsupportedFormat(format!("{other:?}"))),
+    };
+
+    Ok(Payload {
+        name,
+        mtime,
+        is_tar: is_tar_header(&head),
+        reader: Box::new(Cursor::new(head).chain(decoder)),
+    })
+}
+
+/// 是否为由 [`CompressedReader`] 处理的单流压缩格式
+fn is_stream_format(format: ArchiveFormat) -> bool {
+    matches!(
+        format,
+        ArchiveFormat::TarGz
+            | ArchiveFormat::TarBz2
+            | ArchiveFormat::TarXz
+            | ArchiveFormat::TarZst
+            | ArchiveFormat::TarLz4
+            | ArchiveFormat::TarLzma
+            | ArchiveFormat::TarLzip
+            | ArchiveFormat::TarBr
+            | ArchiveFormat::TarZ
+    )
+}
+
+/// 压缩流的压缩方法名称
+fn stream_method_name(format: ArchiveFormat) -> &'static str {
+    match format {
+        ArchiveFormat::TarGz => "gzip",
+        ArchiveFormat::TarBz2 => "bzip2",
+        ArchiveFormat::TarXz => "xz",
+        ArchiveFormat::TarZst => "zstd",
+        ArchiveFormat::TarLz4 => "lz4",
+        ArchiveFormat::TarLzma => "lzma",
+        ArchiveFormat::TarLzip => "lzip",
+        ArchiveFormat::TarBr => "brotli",
+        ArchiveFormat::TarZ => "compress",
+        _ => "store",
+    }
+}
+
+/// 单文件压缩流中虚拟条目的名称
+fn payload_entry_name(payload: &Payload<'_>, container_name: &str) -> String {
+    if let Some(name) = &payload.name {
+        // FNAME 只应包含文件名，去掉可能混入的目录部分
+        if let Some(base) = name
+            .rsplit(['/', '\\'])
+            .next()
+            .filter(|base| !base.is_empty())
+        {
+            return base.to_string();
+        }
+    }
+
+    Path::new(container_name)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or_else(|| "data".to_string())
+}
+
+/// 列举压缩流：内部为 TAR 时返回 TAR 条目，否则返回一个虚拟条目
+///
+/// `container_name` 为压缩文件自身的文件名，`container_mtime` 在 gzip 头部没有时间时使用，
+/// `container_size` 作为虚拟条目的压缩后大小。虚拟条目的大小需要完整解压一遍统计，
+/// 解压过程中检查 `cancel`。
+pub(crate) fn list_compressed_from<R: Read>(
+    format: ArchiveFormat,
+    reader: R,
+    container_name: &str,
+    container_mtime: Option<EntryTime>,
+    container_size: Option<u64>,
+    cancel: &CancelToken,
+) -> Result<Vec<Extract>, ArchiveError> {
+    collect_entries(|f| {
+        each_compressed_entry(
+            format,
+            reader,
+            container_name,
+            container_mtime,
+            container_size,
+            cancel,
+            f,
+        )
+    })
+}
+
+/// 逐条列举压缩流，参数含义同 [`list_compressed_from`]
+pub(crate) fn each_compressed_entry<R: Read>(
+    format: ArchiveFormat,
+    reader: R,
+    container_name: &str,
+    container_mtime: Option<EntryTime>,
+    container_size: Option<u64>,
+    cancel: &CancelToken,
+    f: &mut ListFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut payload = open_payload(format, reader)?;
+    if payload.is_tar {
+        // tar 在条目之间跳过数据时也要能取消，大条目需要解压很久
+        let reader = CancelReader::new(payload.reader, cancel);
+        return super::tar::each_tar_entry(reader, f).or_else(|e| {
+            cancel.check()?;
+            Err(e)
+        });
+    }
+
+    let name = payload_entry_name(&payload, container_name);
+    let last_modified = payload
+        .mtime
+        .and_then(|secs| EntryTime::from_unix(i64::from(secs)))
+        .or(container_mtime);
+    // 压缩头部不可靠地记录原始大小：gzip 的 ISIZE 只有 32 位，多成员时只是最后一个成员的大小，
+    // 而不解压无法知道有几个成员，只能完整解压一遍统计
+    let size = drain(&mut payload.reader, cancel)?;
+
+    let mut item = Extract::new(name, size, last_modified, false);
+    item.compression_method = Some(stream_method_name(format).to_string());
+    item.compressed_size = container_size;
+    f(item)?;
+    Ok(())
+}
+
+/// 在压缩流中定位 `entry_name`，并把它的数据流交给 `f` 处理
+pub(crate) fn with_compressed_entry<R: Read, T>(
+    format: ArchiveFormat,
+    reader: R,
+    container_name: &str,
+    entry_name: &str,
+    f: &mut dyn FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut payload = open_payload(format, reader)?;
+    if payload.is_tar {
+        return super::tar::with_tar_entry(payload.reader, entry_name, f);
+    }
+    if payload_entry_name(&payload, container_name) != entry_name {
+        return Err(ArchiveError::EntryNotFound(entry_name.to_string()));
+    }
+
+    f(&mut payload.reader)
+}
+
+/// 依次访问压缩流中的条目：内部为 TAR 时访问每个 TAR 条目，否则访问唯一的虚拟条目
+///
+/// 虚拟条目的大小要解压后才知道，访问时为 0。
+pub(crate) fn for_each_compressed_entry<R: Read>(
+    format: ArchiveFormat,
+    reader: R,
+    container_name: &str,
+    f: &mut VisitFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut payload = open_payload(format, reader)?;
+    if payload.is_tar {
+        return super::tar::for_each_tar_entry(payload.reader, f);
+    }
+
+    let name = payload_entry_name(&payload, container_name);
+    let mut item = Extract::new(name, 0, None, false);
+    item.compression_method = Some(stream_method_name(format).to_string());
+    f(&item, Ok(&mut payload.reader))?;
+    Ok(())
+}
+
+fn container_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// 列举磁盘上的压缩文件
+pub(crate) fn list_compressed_entries(
+    format: ArchiveFormat,
+    path: &Path,
+) -> Result<Vec<Extract>, ArchiveError> {
+    let file = File::open(path)?;
+    let meta = file.metadata()?;
+    list_compressed_from(
+        format,
+        BufReader::new(file),
+        &container_name(path),
+        meta.modified().ok().map(EntryTime::from_system_time),
+        Some(meta.len()),
+        &CancelToken::new(),
+    )
+}
+
+/// 解压磁盘上压缩文件中的单个条目到目标目录
+pub(crate) fn extract_compressed_entry(
+    format: ArchiveFormat,
+    path: &Path,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    let file = BufReader::new(File::open(path)?);
+    with_compressed_entry(
+        format,
+        file,
+        &container_name(path),
+        entry_name,
+        &mut |data| super::write_entry_to_dir(data, entry_name, dest_dir),
+    )
+}
+
+/// 单流压缩文件（.gz/.bz2/.xz/.zst 等）读取器，内部为 TAR 时按 TAR 处理
+pub struct CompressedReader<'a> {
+    format: ArchiveFormat,
+    name: String,
+    input: StreamInput<'a>,
+    cancel: CancelToken,
+}
+
+impl<'a> ArchiveReader<'a> for CompressedReader<'a> {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        let name = input.name();
+        // 压缩算法根据文件头识别，无法识别时再根据扩展名判断
+        let (header, input) = match input {
+            Input::Path(path) => (read_header(&mut File::open(&path)?)?, Input::Path(path)),
+            Input::Volumes(set) => (
+                read_header(&mut File::open(set.first())?)?,
+                Input::Volumes(set),
+            ),
+            Input::Stream { mut reader, name } => {
+                let header = read_header(&mut reader)?;
+                let reader = Box::new(Cursor::new(header.clone()).chain(reader));
+                (header, Input::Stream { reader, name })
+            },
+        };
+        let format = ArchiveFormat::from_magic(&header)
+            .or_else(|| ArchiveFormat::from_path(&name))
+            .filter(|format| is_stream_format(*format))
+            .ok_or_else(|| ArchiveError::UnsupportedFormat(name.clone()))?;
+
+        Ok(Self {
+            format,
+            name,
+            input: StreamInput::new(input),
+            cancel: CancelToken::new(),
+        })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(self.format)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        let metadata = self.metadata();
+        list_compressed_from(
+            self.format,
+            self.input.take()?,
+            &self.name,
+            metadata.modified,
+            metadata.archive_size,
+            &self.cancel,
+        )
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        let metadata = self.metadata();
+        each_compressed_entry(
+            self.format,
+            self.input.take()?,
+            &self.name,
+            metadata.modified,
+            metadata.archive_size,
+            &self.cancel,
+            f,
+        )
+    }
+
+    fn set_cancel(&mut self, cancel: &CancelToken) {
+        self.cancel = cancel.clone();
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        with_compressed_entry(self.format, self.input.take()?, &self.name, entry_name, f)
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        for_each_compressed_entry(self.format, self.input.take()?, &self.name, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::list_compressed_from;
+    use crate::{ArchiveFormat, CancelToken, EntryTime};
+    use std::io::Write;
+
+    fn list(format: ArchiveFormat, data: &[u8], container_name: &str) -> Vec<(String, u64)> {
+        let entries = list_compressed_from(
+            format,
+            data,
+            container_name,
+            EntryTime::from_unix(1_704_067_200),
+            Some(data.len() as u64),
+            &CancelToken::new(),
+        )
+        .unwrap();
+        entries
+            .into_iter()
+            .map(|entry| (entry.name, entry.size))
+            .collect()
+    }
+
+    fn gzip(filename: Option<&str>, data: &[u8]) -> Vec<u8> {
+        let mut builder = flate2::GzBuilder::new();
+        if let Some(filename) = filename {
+            builder = builder.filename(filename);
+        }
+        let mut encoder = builder.write(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn lists_gzip_payload_under_original_name() {
+        let data = gzip(Some("dump.sql"), b"select 1;");
+        assert_eq!(
+            list(ArchiveFormat::TarGz, &data, "backup.gz"),
+            [("dump.sql".to_string(), 9)]
+        );
+
+        // FNAME 混入的目录部分被去掉，缺少 FNAME 时使用去掉扩展名的文件名
+        let data = gzip(Some("var/log/app.log"), b"ok");
+        assert_eq!(list(ArchiveFormat::TarGz, &data, "x.gz")[0].0, "app.log");
+        let data = gzip(None, b"ok");
+        assert_eq!(
+            list(ArchiveFormat::TarGz, &data, "access.log.gz")[0].0,
+            "access.log"
+        );
+    }
+
+    #[test]
+    fn lists_xz_payload_under_file_stem() {
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder.write_all(&[b'x'; 1000]).unwrap();
+        let data = encoder.finish().unwrap();
+        assert_eq!(
+            list(ArchiveFormat::TarXz, &data, "access.log.xz"),
+            [("access.log".to_string(), 1000)]
+        );
+    }
+}
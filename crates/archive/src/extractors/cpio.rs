@@ -1,42 +1,255 @@
-use crate::{ArchiveError, Extract};
-use hadris_cpio::mode::FileType;
-use hadris_cpio::sync::CpioReader;
+use crate::reader::{
+    collect_entries, ArchiveMetadata, ArchiveReader, EntryFn, Input, ListFn, StreamInput, VisitFn,
+};
+use crate::{ArchiveError, ArchiveFormat, EntryTime, Extract};
+use hadris_cpio::{
+    mode::FileType,
+    sync::{CpioEntryOwned, CpioMagic},
+};
 use std::{
     fs::File,
-    io::BufReader,
-    path::Path,
-    time::{Duration, UNIX_EPOCH},
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
 };
 
 /// 列举 CPIO 文件条目
 pub fn list_cpio_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    list_cpio_entries_from(BufReader::new(File::open(path)?))
+}
+
+/// 从任意数据流列举 CPIO 条目（用于嵌套在其他归档中的 CPIO）
+pub(crate) fn list_cpio_entries_from<R: Read>(reader: R) -> Result<Vec<Extract>, ArchiveError> {
+    collect_entries(|f| each_cpio_entry(reader, f))
+}
+
+/// 符号链接目标的最大长度，超出部分丢弃
+const MAX_LINK_TARGET: u64 = 4096;
+
+/// 顺序读取的 CPIO 流：头部由 hadris-cpio 解析，条目数据直接从底层数据流按大小读取，
+/// 不整块读入内存
+struct CpioStream<R> {
+    reader: R,
+}
+
+impl<R: Read> CpioStream<R> {
+    fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    /// 读取下一个头部，读到 `TRAILER!!!` 时返回 `None`
+    ///
+    /// hadris-cpio 的读取器只记录当前位置和是否结束，每个头部新建一个即可。
+    fn next_entry(&mut self) -> Result<Option<CpioEntryOwned>, ArchiveError> {
+        Ok(hadris_cpio::sync::CpioReader::new(&mut self.reader).next_entry_alloc()?)
+    }
+
+    /// 把条目数据交给 `f`，之后丢弃 `f` 没有读完的部分以及 4 字节对齐的填充
+    fn with_data<T>(
+        &mut self,
+        entry: &CpioEntryOwned,
+        f: impl FnOnce(&mut dyn Read) -> Result<T, ArchiveError>,
+    ) -> Result<T, ArchiveError> {
+        let size = u64::from(entry.file_size());
+        let mut data = (&mut self.reader).take(size);
+        let result = f(&mut data)?;
+        std::io::copy(&mut data, &mut std::io::sink())?;
+        if data.limit() > 0 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+
+        let mut padding = [0; 3];
+        let len = ((4 - size % 4) % 4) as usize;
+        self.reader.read_exact(&mut padding[..len])?;
+        Ok(result)
+    }
+
+    fn skip_data(&mut self, entry: &CpioEntryOwned) -> Result<(), ArchiveError> {
+        self.with_data(entry, |_| Ok(()))
+    }
+}
+
+/// 顺序读取 CPIO 流，每读到一个头部就交给 `f`
+pub(crate) fn each_cpio_entry<R: Read>(reader: R, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+    let mut stream = CpioStream::new(reader);
+
+    while let Some(entry) = stream.next_entry()? {
+        let mut item = cpio_entry(&entry);
+        if matches!(entry.file_type(), FileType::Symlink) {
+            // 符号链接的目标存放在条目数据中
+            let target = stream.with_data(&entry, |data| {
+                let mut target = Vec::new();
+                data.take(MAX_LINK_TARGET).read_to_end(&mut target)?;
+                Ok(target)
+            })?;
+            item.link_target = Some(String::from_utf8_lossy(&target).into_owned());
+        } else {
+            stream.skip_data(&entry)?;
+        }
+        if !f(item)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// CPIO 头部记录的条目信息
+fn cpio_entry(entry: &CpioEntryOwned) -> Extract {
+    let header = entry.header();
+    let name = entry
+        .name_str()
+        .map(str::to_string)
+        .unwrap_or_else(|_| "<invalid>".to_string());
+    let size = entry.file_size() as u64;
+    let mtime = header.mtime;
+    let is_dir = matches!(entry.file_type(), FileType::Directory);
+
+    let last_modified = EntryTime::from_unix(i64::from(mtime));
+
+    let mut item = Extract::new(name, size, last_modified, is_dir);
+    item.set_mode(header.mode);
+    item.uid = Some(u64::from(header.uid));
+    item.gid = Some(u64::from(header.gid));
+    item
+}
+
+/// 顺序读取 CPIO 流中的每个条目；`070702` 格式的条目校验头部记录的字节和
+pub(crate) fn for_each_cpio_entry<R: Read>(
+    reader: R,
+    f: &mut VisitFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut stream = CpioStream::new(reader);
+
+    while let Some(entry) = stream.next_entry()? {
+        let item = cpio_entry(&entry);
+        if item.dir {
+            stream.skip_data(&entry)?;
+            continue;
+        }
+        let keep_going = stream.with_data(&entry, |data| {
+            if entry.magic() != CpioMagic::NewcCrc {
+                return f(&item, Ok(data));
+            }
+            let mut data = ChecksumReader {
+                inner: data,
+                expected: entry.header().check,
+                sum: 0,
+            };
+            f(&item, Ok(&mut data))
+        })?;
+        if !keep_going {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// 读到末尾时校验 `070702` 格式的字节和，不符时返回 `InvalidData` 错误
+struct ChecksumReader<'a> {
+    inner: &'a mut dyn Read,
+    expected: u32,
+    sum: u32,
+}
+
+impl Read for ChecksumReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.sum = buf[..n]
+            .iter()
+            .fold(self.sum, |sum, b| sum.wrapping_add(u32::from(*b)));
+        if n == 0 && !buf.is_empty() && self.sum != self.expected {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "CPIO checksum mismatch: expected {:#x}, got {:#x}",
+                    self.expected, self.sum
+                ),
+            ));
+        }
+        Ok(n)
+    }
+}
+
+/// 顺序扫描 CPIO 流，找到指定条目后把它的数据流交给 `f` 处理
+pub(crate) fn with_cpio_entry<R: Read, T>(
+    reader: R,
+    entry_name: &str,
+    f: &mut dyn FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut stream = CpioStream::new(reader);
+
+    while let Some(entry) = stream.next_entry()? {
+        if entry.name() != entry_name.as_bytes() {
+            stream.skip_data(&entry)?;
+            continue;
+        }
+        match entry.file_type() {
+            FileType::Directory => return Err(ArchiveError::InvalidPath(entry_name.to_string())),
+            // 符号链接的数据是链接目标，不是文件内容
+            FileType::Symlink => {
+                let target = stream.with_data(&entry, |data| {
+                    let mut target = Vec::new();
+                    data.take(MAX_LINK_TARGET).read_to_end(&mut target)?;
+                    Ok(target)
+                })?;
+                return Err(ArchiveError::LinkEntry {
+                    entry: entry_name.to_string(),
+                    target: Some(String::from_utf8_lossy(&target).into_owned()),
+                });
+            },
+            _ => {},
+        }
+
+        return stream.with_data(&entry, |data| f(data));
+    }
+
+    Err(ArchiveError::EntryNotFound(entry_name.to_string()))
+}
+
+/// 解压 CPIO 中的单个文件到目标目录
+pub fn extract_cpio_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
     let file = File::open(path)?;
-    let mut reader = CpioReader::new(BufReader::new(file));
-    let mut entries = Vec::new();
+    with_cpio_entry(BufReader::new(file), entry_name, &mut |data| {
+        super::write_entry_to_dir(data, entry_name, dest_dir)
+    })
+}
 
-    loop {
-        let entry = match reader.next_entry_alloc() {
-            Ok(Some(entry)) => entry,
-            Ok(None) => break,
-            Err(e) => return Err(ArchiveError::CpioError(e)),
-        };
+/// CPIO 读取器
+pub struct CpioReader<'a> {
+    input: StreamInput<'a>,
+}
 
-        let header = entry.header();
-        let name = entry
-            .name_str()
-            .map(str::to_string)
-            .unwrap_or_else(|_| "<invalid>".to_string());
-        let size = entry.file_size() as u64;
-        let mtime = header.mtime;
-        let is_dir = matches!(entry.file_type(), FileType::Directory);
+impl<'a> ArchiveReader<'a> for CpioReader<'a> {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self { input: StreamInput::new(input) })
+    }
 
-        let dt = UNIX_EPOCH + Duration::from_secs(mtime as u64);
-        let last_modified = chrono::DateTime::<chrono::Local>::from(dt).to_rfc3339();
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Cpio)
+    }
 
-        entries.push(Extract::new(name, size, last_modified, is_dir));
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        list_cpio_entries_from(self.input.take()?)
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        each_cpio_entry(self.input.take()?, f)
+    }
 
-        reader.skip_entry_data_owned(&entry)?;
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
     }
 
-    Ok(entries)
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        with_cpio_entry(self.input.take()?, entry_name, f)
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        for_each_cpio_entry(self.input.take()?, f)
+    }
 }
@@ -0,0 +1,221 @@
+//! Debian 软件包（.deb）
+//!
+//! 外层是 AR，依次包含 `debian-binary`、`control.tar.*` 和 `data.tar.*`。列举的是 `data.tar.*`
+//! 中安装到系统的文件；`control.tar.*` 中的 `control` 和维护脚本作为软件包信息读取。
+
+use crate::package::{self, PackageInfo, PackageScript};
+use crate::reader::{
+    collect_entries, ArchiveMetadata, ArchiveReader, EntryFn, Input, ListFn, StreamInput, VisitFn,
+};
+use crate::{ArchiveError, ArchiveFormat, Extract};
+use std::io::Read;
+
+/// `control.tar.*` 中作为脚本展示的文件
+const MAINTAINER_SCRIPTS: &[&str] = &["preinst", "postinst", "prerm", "postrm", "config"];
+
+/// 按成员名的压缩扩展名包上解压层，返回 TAR 数据流
+fn member_tar<'a, R: Read + 'a>(name: &str, member: R) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+    let format = match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("tar") => ArchiveFormat::Tar,
+        Some("gz") => ArchiveFormat::TarGz,
+        Some("bz2") => ArchiveFormat::TarBz2,
+        Some("xz") => ArchiveFormat::TarXz,
+        Some("zst") => ArchiveFormat::TarZst,
+        Some("lzma") => return super::tar::lzma_decoder(member),
+        _ => return Err(ArchiveError::UnsupportedFormat(name.to_string())),
+    };
+    super::tar::tar_stream(format, member)
+}
+
+/// 顺序读取 deb 的 AR 成员，找到 `control.tar` 或 `data.tar` 后把解压后的 TAR 流交给 `f`
+fn with_member<R: Read, T>(
+    reader: R,
+    prefix: &str,
+    f: &mut dyn FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut archive = ar::Archive::new(reader);
+
+    while let Some(entry_result) = archive.next_entry() {
+        let mut entry = entry_result?;
+        // GNU ar 的成员名可能以 `/` 结尾
+        let name = String::from_utf8_lossy(entry.header().identifier())
+            .trim_end_matches('/')
+            .to_string();
+        if name == prefix || name.starts_with(&format!("{prefix}.")) {
+            let mut tar = member_tar(&name, &mut entry)?;
+            return f(&mut tar);
+        }
+    }
+
+    Err(ArchiveError::Other(format!(
+        "Missing {prefix} in deb package"
+    )))
+}
+
+/// 逐条列举 deb 中安装的文件
+pub(crate) fn each_deb_entry<R: Read>(reader: R, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+    with_member(reader, "data.tar", &mut |data| {
+        super::tar::each_tar_entry(data, f)
+    })
+}
+
+/// 读取 `control.tar.*` 中的 `control` 文件和维护脚本
+pub(crate) fn deb_package_info<R: Read>(reader: R) -> Result<PackageInfo, ArchiveError> {
+    with_member(reader, "control.tar", &mut |data| {
+        let mut archive = tar::Archive::new(data);
+        let mut control = None;
+        let mut scripts = Vec::new();
+
+        for entry_result in archive.entries()? {
+            let mut entry = entry_result?;
+            if !entry.header().entry_type().is_file() {
+                continue;
+            }
+            let path = entry.path()?.to_string_lossy().into_owned();
+            let name = path.trim_start_matches("./");
+            if name == "control" {
+                control = Some(package::read_manifest(&mut entry)?);
+            } else if MAINTAINER_SCRIPTS.contains(&name) {
+                scripts.push(PackageScript {
+                    name: name.to_string(),
+                    content: package::read_manifest(&mut entry)?,
+                });
+            }
+        }
+
+        let control = control
+            .ok_or_else(|| ArchiveError::Other("Missing control file in deb package".into()))?;
+        Ok(package::debian(control, scripts))
+    })
+}
+
+/// deb 读取器，条目为 `data.tar.*` 中的文件
+pub struct DebReader<'a> {
+    input: StreamInput<'a>,
+}
+
+impl<'a> ArchiveReader<'a> for DebReader<'a> {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self { input: StreamInput::new(input) })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Deb)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        let reader = self.input.take()?;
+        collect_entries(|f| each_deb_entry(reader, f))
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        each_deb_entry(self.input.take()?, f)
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        with_member(self.input.take()?, "data.tar", &mut |data| {
+            super::tar::with_tar_entry(data, entry_name, f)
+        })
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        with_member(self.input.take()?, "data.tar", &mut |data| {
+            super::tar::for_each_tar_entry(data, f)
+        })
+    }
+
+    fn package_info(&mut self) -> Result<Option<PackageInfo>, ArchiveError> {
+        deb_package_info(self.input.take()?).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deb_package_info, each_deb_entry};
+
+    /// 不压缩的 TAR，`files` 为 `(路径, 内容)`
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *data).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    /// 按 AR 格式拼接成员：60 字节头部，数据补齐到偶数长度
+    fn deb(members: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut bytes = b"!<arch>\n".to_vec();
+        for (name, data) in members {
+            let header = format!(
+                "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+                name,
+                0,
+                0,
+                0,
+                100644,
+                data.len()
+            );
+            bytes.extend_from_slice(header.as_bytes());
+            bytes.extend_from_slice(data);
+            if data.len() % 2 == 1 {
+                bytes.push(b'\n');
+            }
+        }
+        bytes
+    }
+
+    const CONTROL: &[u8] = b"Package: demo\nVersion: 1.0-2\nArchitecture: amd64\n\
+Maintainer: Dev <dev@example.com>\nDepends: libc6 (>= 2.34), bash\n\
+Description: Demo tool\n Longer text.\n .\n Second paragraph.\n";
+
+    #[test]
+    fn reads_control_fields() {
+        let control = tar(&[("./control", CONTROL), ("./postinst", b"#!/bin/sh\n")]);
+        let data = tar(&[("./usr/bin/demo", b"bin")]);
+        let package = deb(&[
+            ("debian-binary", b"2.0\n"),
+            ("control.tar/", &control),
+            ("data.tar", &data),
+        ]);
+
+        let info = deb_package_info(package.as_slice()).unwrap();
+        assert_eq!(info.name.as_deref(), Some("demo"));
+        assert_eq!(info.version.as_deref(), Some("1.0-2"));
+        assert_eq!(info.architecture.as_deref(), Some("amd64"));
+        assert_eq!(info.description.as_deref(), Some("Demo tool"));
+        assert_eq!(info.dependencies, ["libc6 (>= 2.34)", "bash"]);
+        assert_eq!(
+            info.field(&["Description"]).as_deref(),
+            Some("Demo tool\nLonger text.\n\nSecond paragraph.")
+        );
+        assert_eq!(info.scripts[0].name, "postinst");
+
+        let mut names = Vec::new();
+        each_deb_entry(package.as_slice(), &mut |entry| {
+            names.push(entry.name);
+            Ok(true)
+        })
+        .unwrap();
+        assert_eq!(names, ["usr/bin/demo"]);
+    }
+
+    #[test]
+    fn rejects_truncated_package() {
+        let control = tar(&[("./control", CONTROL)]);
+        let package = deb(&[("debian-binary", b"2.0\n"), ("control.tar", &control)]);
+        for len in [4, 8 + 30, 8 + 68 + 60, package.len() - 700] {
+            assert!(deb_package_info(&package[..len]).is_err());
+        }
+
+        let package = deb(&[("debian-binary", b"2.0\n"), ("data.tar", &tar(&[]))]);
+        assert!(deb_package_info(package.as_slice()).is_err());
+    }
+}
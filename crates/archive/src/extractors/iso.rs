@@ -0,0 +1,705 @@
+//! ISO 9660 光盘映像，支持 Rock Ridge 和 Joliet 扩展
+//!
+//! 同时带有 UDF 文件系统的映像（DVD、Windows 安装盘等）优先按 UDF 读取，见 [`super::udf`]。
+//! 两种文件系统都先建立条目索引，记录每个条目的数据在映像中的位置，读取时直接定位。
+
+use super::udf;
+use crate::reader::{ArchiveMetadata, ArchiveReader, EntryFn, Input, ReadSeek, SeekInput, VisitFn};
+use crate::{ArchiveError, ArchiveFormat, EntryTime, Extract};
+use chrono::NaiveDate;
+use std::{
+    collections::HashSet,
+    io::{self, Read, SeekFrom},
+    path::Path,
+};
+
+/// 卷描述符所在的扇区大小，卷描述符从第 16 个扇区开始
+pub(crate) const SECTOR_SIZE: u64 = 2048;
+pub(crate) const FIRST_DESCRIPTOR_SECTOR: u64 = 16;
+
+/// 最多查看的卷描述符数量
+const MAX_DESCRIPTORS: u64 = 64;
+
+/// 目录的数据超过该大小时视为映像损坏
+pub(crate) const MAX_DIRECTORY_SIZE: u64 = 64 << 20;
+
+/// Rock Ridge 的 CE 续接区最多跟随的次数
+const MAX_CONTINUATIONS: usize = 16;
+
+/// 条目数据在映像中的一段
+#[derive(Debug, Clone)]
+pub(crate) enum Extent {
+    /// 从映像偏移 `offset` 开始的 `len` 字节
+    Data { offset: u64, len: u64 },
+    /// 未分配（稀疏）的 `len` 个零字节
+    Zero(u64),
+    /// 直接记录在元数据中的数据（UDF 的内嵌数据）
+    Inline(Vec<u8>),
+}
+
+impl Extent {
+    fn len(&self) -> u64 {
+        match self {
+            Self::Data { len, .. } | Self::Zero(len) => *len,
+            Self::Inline(data) => data.len() as u64,
+        }
+    }
+}
+
+/// 映像中的一个条目及其数据位置
+pub(crate) struct ImageEntry {
+    pub(crate) item: Extract,
+    pub(crate) extents: Vec<Extent>,
+}
+
+/// 依次读取条目的各段数据，总长度截断为条目大小
+pub(crate) struct ExtentReader<'r> {
+    image: &'r mut dyn ReadSeek,
+    extents: &'r [Extent],
+    /// 当前段内已读取的字节数
+    position: u64,
+    remaining: u64,
+}
+
+impl<'r> ExtentReader<'r> {
+    pub(crate) fn new(image: &'r mut dyn ReadSeek, extents: &'r [Extent], size: u64) -> Self {
+        Self {
+            image,
+            extents,
+            position: 0,
+            remaining: size,
+        }
+    }
+}
+
+impl Read for ExtentReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.remaining == 0 || buf.is_empty() {
+                return Ok(0);
+            }
+            let Some(extent) = self.extents.first() else {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Image extents end before the entry size",
+                ));
+            };
+            let len = extent.len();
+            if self.position >= len {
+                self.extents = &self.extents[1..];
+                self.position = 0;
+                continue;
+            }
+
+            let want = (buf.len() as u64)
+                .min(len - self.position)
+                .min(self.remaining) as usize;
+            let read = match extent {
+                Extent::Data { offset, .. } => {
+                    self.image.seek(SeekFrom::Start(offset + self.position))?;
+                    match self.image.read(&mut buf[..want])? {
+                        0 => {
+                            return Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "Entry data extends beyond the end of the image",
+                            ))
+                        },
+                        read => read,
+                    }
+                },
+                Extent::Zero(_) => {
+                    buf[..want].fill(0);
+                    want
+                },
+                Extent::Inline(data) => {
+                    let start = self.position as usize;
+                    buf[..want].copy_from_slice(&data[start..start + want]);
+                    want
+                },
+            };
+            self.position += read as u64;
+            self.remaining -= read as u64;
+            return Ok(read);
+        }
+    }
+}
+
+/// 读取映像中 `offset` 开始的 `len` 字节，映像不够长时报错
+pub(crate) fn read_at(
+    image: &mut dyn ReadSeek,
+    offset: u64,
+    len: usize,
+) -> Result<Vec<u8>, ArchiveError> {
+    let mut data = vec![0; len];
+    image.seek(SeekFrom::Start(offset))?;
+    image.read_exact(&mut data)?;
+    Ok(data)
+}
+
+pub(crate) fn u16_le(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+pub(crate) fn u32_le(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap_or_default())
+}
+
+/// 条目名的记录方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Names {
+    /// ISO 9660 原始的 8.3 大写名称
+    Plain,
+    /// Joliet 补充卷描述符中的 UCS-2 名称
+    Joliet,
+    /// Rock Ridge 的 NM 记录，`skip` 为每条系统使用区开头需要跳过的字节数
+    RockRidge { skip: usize },
+}
+
+/// 目录记录（ECMA-119 9.1）中用到的字段
+struct DirRecord<'a> {
+    extent: u32,
+    size: u32,
+    recorded: Option<EntryTime>,
+    flags: u8,
+    name: &'a [u8],
+    system_use: &'a [u8],
+}
+
+impl<'a> DirRecord<'a> {
+    const DIRECTORY: u8 = 0x02;
+    const ASSOCIATED: u8 = 0x04;
+    const MULTI_EXTENT: u8 = 0x80;
+
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let len = usize::from(*data.first()?);
+        let data = data.get(..len)?;
+        let name_len = usize::from(*data.get(32)?);
+        let name = data.get(33..33 + name_len)?;
+        // 名称长度为偶数时后面有一个填充字节
+        let system_use = data
+            .get(33 + name_len + (1 - name_len % 2)..)
+            .unwrap_or_default();
+        Some(Self {
+            extent: u32_le(data, 2),
+            size: u32_le(data, 10),
+            recorded: short_time(&data[18..25]),
+            flags: data[25],
+            name,
+            system_use,
+        })
+    }
+
+    /// `.` 和 `..` 分别记录为单个字节 0 和 1
+    fn is_self_or_parent(&self) -> bool {
+        matches!(self.name, [0] | [1])
+    }
+}
+
+/// 目录记录中的 7 字节时间：1900 年起的年份、月、日、时、分、秒，以及以 15 分钟为单位的时区偏移
+fn short_time(raw: &[u8]) -> Option<EntryTime> {
+    let date = NaiveDate::from_ymd_opt(
+        1900 + i32::from(raw[0]),
+        u32::from(raw[1]),
+        u32::from(raw[2]),
+    )?;
+    let time = date.and_hms_opt(u32::from(raw[3]), u32::from(raw[4]), u32::from(raw[5]))?;
+    Some(EntryTime::from_local(
+        time,
+        Some(i32::from(raw[6] as i8) * 15),
+    ))
+}
+
+/// Rock Ridge 长格式的 17 字节时间：`YYYYMMDDHHMMSScc` 十六个数字加时区偏移
+fn long_time(raw: &[u8]) -> Option<EntryTime> {
+    let digits = std::str::from_utf8(&raw[..16]).ok()?;
+    let field = |range: std::ops::Range<usize>| digits.get(range)?.parse::<u32>().ok();
+    let date = NaiveDate::from_ymd_opt(field(0..4)? as i32, field(4..6)?, field(6..8)?)?;
+    let time = date.and_hms_milli_opt(
+        field(8..10)?,
+        field(10..12)?,
+        field(12..14)?,
+        field(14..16)? * 10,
+    )?;
+    Some(EntryTime::from_local(
+        time,
+        Some(i32::from(raw[16] as i8) * 15),
+    ))
+}
+
+/// 去掉 ISO 9660 名称的版本号（`;1`）以及没有扩展名时留下的 `.`
+fn plain_name(raw: &[u8]) -> String {
+    let name = String::from_utf8_lossy(raw);
+    let name = name.split_once(';').map_or(&*name, |(name, _)| name);
+    name.strip_suffix('.').unwrap_or(name).to_string()
+}
+
+fn joliet_name(raw: &[u8]) -> String {
+    let units = raw
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    let name = char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect::<String>();
+    match name.split_once(';') {
+        Some((name, _)) => name.to_string(),
+        None => name,
+    }
+}
+
+/// 条目的 Rock Ridge 属性（IEEE P1282）
+#[derive(Default)]
+struct RockRidge {
+    name: Option<Vec<u8>>,
+    mode: Option<u32>,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    link_target: Option<String>,
+    modified: Option<EntryTime>,
+    /// 被移到别处的深层目录（CL），值为其实际位置
+    child_link: Option<u32>,
+    /// 从深层位置移来的目录（RE），在原位置由 CL 记录列出
+    relocated: bool,
+}
+
+/// SUSP 记录：两字节标识和完整的记录数据
+type SuspEntry = ([u8; 2], Vec<u8>);
+
+/// 读取系统使用区中的 SUSP 记录，跟随 CE 续接区，返回 (标识, 记录数据)
+fn susp_entries(
+    image: &mut dyn ReadSeek,
+    block_size: u64,
+    system_use: &[u8],
+) -> Result<Vec<SuspEntry>, ArchiveError> {
+    let mut entries = Vec::new();
+    let mut area = system_use.to_vec();
+    for _ in 0..MAX_CONTINUATIONS {
+        let mut continuation = None;
+        let mut pos = 0;
+        while pos + 4 <= area.len() {
+            let len = usize::from(area[pos + 2]);
+            if len < 4 || pos + len > area.len() {
+                break;
+            }
+            let data = &area[pos..pos + len];
+            let signature = [data[0], data[1]];
+            match &signature {
+                b"ST" => break,
+                b"CE" if len >= 28 => {
+                    let offset =
+                        u64::from(u32_le(data, 4)) * block_size + u64::from(u32_le(data, 12));
+                    continuation = Some((offset, u32_le(data, 20) as usize));
+                },
+                _ => entries.push((signature, data.to_vec())),
+            }
+            pos += len;
+        }
+        let Some((offset, len)) = continuation else {
+            break;
+        };
+        area = read_at(image, offset, len.min(SECTOR_SIZE as usize))?;
+    }
+    Ok(entries)
+}
+
+fn rock_ridge(entries: &[SuspEntry]) -> RockRidge {
+    let mut rr = RockRidge::default();
+    let mut link: Vec<String> = Vec::new();
+    // 上一个 SL 组件未结束，下一个组件接在它后面
+    let mut link_continues = false;
+    for (signature, data) in entries {
+        match signature {
+            // CURRENT、PARENT 标志表示 `.`、`..`
+            b"NM" if data.len() >= 5 && data[4] & 0x06 == 0 => {
+                rr.name
+                    .get_or_insert_with(Vec::new)
+                    .extend_from_slice(&data[5..]);
+            },
+            b"PX" if data.len() >= 36 => {
+                rr.mode = Some(u32_le(data, 4));
+                rr.uid = Some(u32_le(data, 20));
+                rr.gid = Some(u32_le(data, 28));
+            },
+            b"SL" if data.len() >= 5 => {
+                let mut pos = 5;
+                while pos + 2 <= data.len() {
+                    let (flags, len) = (data[pos], usize::from(data[pos + 1]));
+                    let Some(content) = data.get(pos + 2..pos + 2 + len) else {
+                        break;
+                    };
+                    let component = match flags & 0x0E {
+                        0x02 => ".".to_string(),
+                        0x04 => "..".to_string(),
+                        0x08 => String::new(),
+                        _ => String::from_utf8_lossy(content).into_owned(),
+                    };
+                    match link.last_mut() {
+                        Some(last) if link_continues => last.push_str(&component),
+                        _ => link.push(component),
+                    }
+                    link_continues = flags & 0x01 != 0;
+                    pos += 2 + len;
+                }
+            },
+            b"TF" if data.len() >= 5 => {
+                let flags = data[4];
+                let size = if flags & 0x80 != 0 { 17 } else { 7 };
+                // 依次为创建、修改、访问……时间，只取修改时间
+                if flags & 0x02 != 0 {
+                    let start = 5 + if flags & 0x01 != 0 { size } else { 0 };
+                    if let Some(raw) = data.get(start..start + size) {
+                        rr.modified = if size == 17 {
+                            long_time(raw)
+                        } else {
+                            short_time(raw)
+                        };
+                    }
+                }
+            },
+            b"CL" if data.len() >= 8 => rr.child_link = Some(u32_le(data, 4)),
+            b"RE" => rr.relocated = true,
+            _ => {},
+        }
+    }
+    if !link.is_empty() {
+        // 根目录组件为空字符串，拼接后得到以 `/` 开头的绝对路径
+        rr.link_target = Some(match link.as_slice() {
+            [root] if root.is_empty() => "/".to_string(),
+            _ => link.join("/"),
+        });
+    }
+    rr
+}
+
+/// 卷描述符中选出的目录树
+struct IsoVolume {
+    root_extent: u32,
+    root_size: u32,
+    block_size: u64,
+    names: Names,
+}
+
+/// 读取卷描述符：有 Rock Ridge 时使用主卷描述符，否则优先使用 Joliet 补充卷描述符
+fn read_volume(image: &mut dyn ReadSeek) -> Result<IsoVolume, ArchiveError> {
+    let mut primary = None;
+    let mut joliet = None;
+    for sector in FIRST_DESCRIPTOR_SECTOR..FIRST_DESCRIPTOR_SECTOR + MAX_DESCRIPTORS {
+        let Ok(descriptor) = read_at(image, sector * SECTOR_SIZE, SECTOR_SIZE as usize) else {
+            break;
+        };
+        if &descriptor[1..6] != b"CD001" {
+            break;
+        }
+        let root = || {
+            let record = DirRecord::parse(&descriptor[156..190])?;
+            let block_size = u64::from(u16_le(&descriptor, 128));
+            Some((record.extent, record.size, block_size))
+        };
+        match descriptor[0] {
+            1 if primary.is_none() => primary = root(),
+            // 转义序列 `%/@`、`%/C`、`%/E` 表示 UCS-2 Level 1~3
+            2 if joliet.is_none()
+                && descriptor[88..90] == *b"%/"
+                && matches!(descriptor[90], b'@' | b'C' | b'E') =>
+            {
+                joliet = root()
+            },
+            255 => break,
+            _ => {},
+        }
+    }
+
+    let (root_extent, root_size, block_size) =
+        primary.ok_or_else(|| ArchiveError::Other("Not an ISO 9660 image".into()))?;
+    let block_size = if block_size == 0 {
+        SECTOR_SIZE
+    } else {
+        block_size
+    };
+
+    // Rock Ridge 在根目录 `.` 记录的系统使用区开头放置 SP 记录，并登记 ER 扩展
+    let root = read_at(image, u64::from(root_extent) * block_size, 255)?;
+    if let Some(record) = DirRecord::parse(&root) {
+        if record.system_use.starts_with(b"SP")
+            && record.system_use.get(4..6) == Some(&[0xBE, 0xEF])
+        {
+            let skip = usize::from(record.system_use.get(6).copied().unwrap_or(0));
+            let entries = susp_entries(image, block_size, record.system_use)?;
+            if entries
+                .iter()
+                .any(|(signature, _)| matches!(signature, b"ER" | b"RR" | b"PX" | b"NM"))
+            {
+                return Ok(IsoVolume {
+                    root_extent,
+                    root_size,
+                    block_size,
+                    names: Names::RockRidge { skip },
+                });
+            }
+        }
+    }
+
+    Ok(match joliet {
+        Some((extent, size, joliet_block_size)) => IsoVolume {
+            root_extent: extent,
+            root_size: size,
+            block_size: if joliet_block_size == 0 {
+                SECTOR_SIZE
+            } else {
+                joliet_block_size
+            },
+            names: Names::Joliet,
+        },
+        None => IsoVolume {
+            root_extent,
+            root_size,
+            block_size,
+            names: Names::Plain,
+        },
+    })
+}
+
+/// 遍历 ISO 9660 目录树，按目录顺序返回全部条目
+pub(crate) fn read_iso_entries(image: &mut dyn ReadSeek) -> Result<Vec<ImageEntry>, ArchiveError> {
+    let volume = read_volume(image)?;
+    let mut entries: Vec<ImageEntry> = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![(String::new(), volume.root_extent, volume.root_size)];
+
+    while let Some((prefix, extent, size)) = pending.pop() {
+        if !visited.insert(extent) || u64::from(size) > MAX_DIRECTORY_SIZE {
+            continue;
+        }
+        let offset = u64::from(extent) * volume.block_size;
+        let data = read_at(image, offset, size as usize)?;
+        let mut children = Vec::new();
+        // 上一条记录带有 MULTI_EXTENT 标志，本条记录是同一文件的下一段
+        let mut continues_previous = false;
+
+        let mut pos = 0;
+        while pos < data.len() {
+            // 记录不跨越逻辑块，块末尾不足一条记录时用 0 填充
+            if data[pos] == 0 {
+                pos = (pos / volume.block_size as usize + 1) * volume.block_size as usize;
+                continue;
+            }
+            let Some(record) = DirRecord::parse(&data[pos..]) else {
+                break;
+            };
+            pos += usize::from(data[pos]);
+            if record.is_self_or_parent() || record.flags & DirRecord::ASSOCIATED != 0 {
+                continue;
+            }
+
+            let segment = Extent::Data {
+                offset: u64::from(record.extent) * volume.block_size,
+                len: u64::from(record.size),
+            };
+            if continues_previous {
+                if let Some(entry) = entries.last_mut() {
+                    entry.item.size += u64::from(record.size);
+                    entry.extents.push(segment);
+                }
+                continues_previous = record.flags & DirRecord::MULTI_EXTENT != 0;
+                continue;
+            }
+            continues_previous = record.flags & DirRecord::MULTI_EXTENT != 0;
+
+            let rr = match volume.names {
+                Names::RockRidge { skip } => {
+                    let system_use = record.system_use.get(skip..).unwrap_or_default();
+                    rock_ridge(&susp_entries(image, volume.block_size, system_use)?)
+                },
+                _ => RockRidge::default(),
+            };
+            if rr.relocated {
+                continues_previous = false;
+                continue;
+            }
+            let name = match (volume.names, &rr.name) {
+                (Names::RockRidge { .. }, Some(name)) => String::from_utf8_lossy(name).into_owned(),
+                (Names::Joliet, _) => joliet_name(record.name),
+                _ => plain_name(record.name),
+            };
+            let path = format!("{prefix}{name}");
+            let is_dir = record.flags & DirRecord::DIRECTORY != 0 || rr.child_link.is_some();
+
+            let last_modified = rr.modified.or(record.recorded);
+            let mut item = if is_dir {
+                Extract::new(format!("{path}/"), 0, last_modified, true)
+            } else {
+                Extract::new(path.clone(), u64::from(record.size), last_modified, false)
+            };
+            if let Some(mode) = rr.mode {
+                item.set_mode(mode);
+            }
+            item.uid = rr.uid.map(u64::from);
+            item.gid = rr.gid.map(u64::from);
+            if let Some(target) = rr.link_target {
+                item.size = 0;
+                item.link_target = Some(target);
+            }
+
+            if is_dir {
+                let (extent, size) = match rr.child_link {
+                    // 移走的目录需要从它自己的 `.` 记录读取大小
+                    Some(extent) => {
+                        let offset = u64::from(extent) * volume.block_size;
+                        let first = read_at(image, offset, 34)?;
+                        (extent, u32_le(&first, 10))
+                    },
+                    None => (record.extent, record.size),
+                };
+                children.push((format!("{path}/"), extent, size));
+                entries.push(ImageEntry { item, extents: Vec::new() });
+            } else {
+                let extents = if item.link_target.is_some() {
+                    Vec::new()
+                } else {
+                    vec![segment]
+                };
+                entries.push(ImageEntry { item, extents });
+            }
+        }
+        // 栈顶先处理，逆序压入以保持目录顺序
+        pending.extend(children.into_iter().rev());
+    }
+
+    Ok(entries)
+}
+
+/// 按文件系统读取映像的全部条目：带 UDF 的映像优先按 UDF 读取，失败时退回 ISO 9660
+fn read_image_entries(
+    image: &mut dyn ReadSeek,
+    format: &mut ArchiveFormat,
+) -> Result<Vec<ImageEntry>, ArchiveError> {
+    if *format == ArchiveFormat::Udf {
+        match udf::read_udf_entries(image) {
+            Ok(entries) => return Ok(entries),
+            Err(e) => {
+                log::warn!(
+                    "Failed to read UDF file system, falling back to ISO 9660: {}",
+                    e
+                );
+                *format = ArchiveFormat::Iso;
+            },
+        }
+    }
+    read_iso_entries(image)
+}
+
+/// 列举光盘映像（ISO 9660 或 UDF）的条目
+pub fn list_iso_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    IsoReader::open(Input::Path(path.as_ref().to_path_buf()), None)?.list()
+}
+
+/// 光盘映像读取器，根据卷识别序列选择 UDF 或 ISO 9660；嵌套的映像会读入内存
+pub struct IsoReader {
+    input: SeekInput,
+    format: ArchiveFormat,
+    /// 第一次列举或读取时建立的条目索引
+    index: Option<Vec<ImageEntry>>,
+}
+
+impl IsoReader {
+    fn load_index(&mut self) -> Result<&[ImageEntry], ArchiveError> {
+        if self.index.is_none() {
+            let (mut image, _) = self.input.reader()?;
+            self.index = Some(read_image_entries(&mut *image, &mut self.format)?);
+        }
+        Ok(self.index.as_deref().unwrap_or_default())
+    }
+}
+
+impl<'a> ArchiveReader<'a> for IsoReader {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        let input = SeekInput::new(input)?;
+        let (mut image, _) = input.reader()?;
+        let format = if udf::has_udf(&mut *image) {
+            ArchiveFormat::Udf
+        } else {
+            ArchiveFormat::Iso
+        };
+        drop(image);
+        Ok(Self { input, format, index: None })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(self.format)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        Ok(self
+            .load_index()?
+            .iter()
+            .map(|entry| entry.item.clone())
+            .collect())
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        self.load_index()?;
+        let entries = self.index.as_deref().unwrap_or_default();
+        let entry = entries
+            .iter()
+            .find(|entry| entry.item.name == entry_name)
+            .ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))?;
+        if entry.item.dir {
+            return Err(ArchiveError::InvalidPath(entry_name.to_string()));
+        }
+        super::ensure_not_link(&entry.item)?;
+        let (mut image, _) = self.input.reader()?;
+        f(&mut ExtentReader::new(
+            &mut *image,
+            &entry.extents,
+            entry.item.size,
+        ))
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        self.load_index()?;
+        let entries = self.index.as_deref().unwrap_or_default();
+        let (mut image, _) = self.input.reader()?;
+        for entry in entries.iter().filter(|entry| !entry.item.dir) {
+            let mut data = ExtentReader::new(&mut *image, &entry.extents, entry.item.size);
+            if !f(&entry.item, Ok(&mut data))? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plain_name, rock_ridge};
+    use crate::EntryTime;
+
+    #[test]
+    fn parses_rock_ridge_names_links_and_times() {
+        let entries = vec![
+            (*b"NM", b"NM\x0b\x01\x00long-name".to_vec()),
+            // `/usr/` 加上跨组件续接的 `li` + `b`
+            (
+                *b"SL",
+                b"SL\x13\x01\x00\x08\x00\x00\x03usr\x01\x02li\x00\x01b".to_vec(),
+            ),
+            // 只有修改时间，2024-01-02 10:00:00 +08:00
+            (
+                *b"TF",
+                b"TF\x0c\x01\x02\x7c\x01\x02\x0a\x00\x00\x20".to_vec(),
+            ),
+        ];
+        let rr = rock_ridge(&entries);
+        assert_eq!(rr.name.as_deref(), Some(&b"long-name"[..]));
+        assert_eq!(rr.link_target.as_deref(), Some("/usr/lib"));
+        assert_eq!(
+            rr.modified,
+            "2024-01-02T02:00:00Z".parse::<EntryTime>().ok()
+        );
+        assert_eq!(plain_name(b"README.TXT;1"), "README.TXT");
+        assert_eq!(plain_name(b"MAKEFILE.;1"), "MAKEFILE");
+    }
+}
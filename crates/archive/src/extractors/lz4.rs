@@ -0,0 +1,29 @@
+use crate::{ArchiveError, ArchiveFormat, Extract};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// 创建 LZ4 帧格式的流式解码器，首尾相接的多个帧会依次解压
+pub(crate) fn lz4_decoder<'a, R: Read + 'a>(reader: R) -> Box<dyn Read + 'a> {
+    Box::new(lz4_flex::frame::FrameDecoder::new(reader))
+}
+
+/// 列举 TAR.LZ4 文件条目（内容不是 TAR 时返回一个虚拟条目）
+pub fn list_tar_lz4_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    super::compressed::list_compressed_entries(ArchiveFormat::TarLz4, path.as_ref())
+}
+
+/// 解压 TAR.LZ4 中的单个文件到目标目录
+pub fn extract_tar_lz4_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarLz4,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
@@ -0,0 +1,208 @@
+//! lzip（`.lz`）压缩流
+//!
+//! 每个成员由 6 字节头部（`LZIP`、版本号、编码后的字典大小）、以结束标记收尾的 LZMA 数据
+//! （固定 lc=3、lp=0、pb=2）和 20 字节尾部（CRC32、解压后大小、成员大小）组成，
+//! 多个成员可以首尾相接。xz2 没有提供裸 LZMA1 解码器，这里为每个成员拼出一个
+//! 解压后大小未知的 LZMA-Alone 头部，再把成员数据交给它解码。
+
+use crate::{ArchiveError, ArchiveFormat, Extract};
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+use xz2::stream::{Action, Status, Stream};
+
+const MAGIC: &[u8; 4] = b"LZIP";
+const HEADER_LEN: usize = 6;
+const TRAILER_LEN: usize = 20;
+/// lc=3、lp=0、pb=2 对应的 LZMA 属性字节
+const LZMA_PROPERTIES: u8 = 0x5D;
+/// lzip 允许的字典大小范围：4 KiB 到 512 MiB
+const MIN_DICT_SIZE: u32 = 1 << 12;
+const MAX_DICT_SIZE: u32 = 1 << 29;
+
+/// 创建 lzip 流式解码器，读取并检查第一个成员的头部
+pub(crate) fn lzip_decoder<'a, R: Read + 'a>(
+    reader: R,
+) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+    let mut reader = BufReader::new(reader);
+    let mut header = [0u8; HEADER_LEN];
+    reader.read_exact(&mut header)?;
+    if !header.starts_with(MAGIC) {
+        return Err(ArchiveError::Other("Not an lzip stream".into()));
+    }
+    let stream = member_stream(&header)?;
+    Ok(Box::new(LzipDecoder {
+        reader,
+        stream,
+        prefix_pos: 0,
+        prefix: alone_header(dict_size(header[5])),
+        crc: crc32fast::Hasher::new(),
+        data_size: 0,
+        member_size: HEADER_LEN as u64,
+        done: false,
+    }))
+}
+
+/// 解码头部中的字典大小：低 5 位为 2 的幂次，高 3 位为要减去的 1/16 份数
+fn dict_size(byte: u8) -> u32 {
+    let base = 1u32 << (byte & 0x1F);
+    base - (base / 16) * u32::from(byte >> 5)
+}
+
+/// 检查成员头部并创建对应的 LZMA 解码器
+fn member_stream(header: &[u8; HEADER_LEN]) -> io::Result<Stream> {
+    if header[4] != 1 {
+        return Err(invalid_data(format!(
+            "Unsupported lzip version: {}",
+            header[4]
+        )));
+    }
+    let dict = dict_size(header[5]);
+    if !(MIN_DICT_SIZE..=MAX_DICT_SIZE).contains(&dict) {
+        return Err(invalid_data(format!(
+            "Invalid lzip dictionary size: {dict}"
+        )));
+    }
+    Ok(Stream::new_lzma_decoder(u64::MAX)?)
+}
+
+/// LZMA-Alone 头部：属性字节、字典大小，以及表示“大小未知、以结束标记收尾”的全 1 长度
+fn alone_header(dict_size: u32) -> [u8; 13] {
+    let mut header = [0xFF; 13];
+    header[0] = LZMA_PROPERTIES;
+    header[1..5].copy_from_slice(&dict_size.to_le_bytes());
+    header
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct LzipDecoder<R> {
+    reader: BufReader<R>,
+    stream: Stream,
+    /// 需要先喂给解码器的 LZMA-Alone 头部
+    prefix: [u8; 13],
+    prefix_pos: usize,
+    /// 当前成员已解压数据的 CRC32
+    crc: crc32fast::Hasher,
+    /// 当前成员已解压的字节数
+    data_size: u64,
+    /// 当前成员已读取的字节数（含头部）
+    member_size: u64,
+    done: bool,
+}
+
+impl<R: Read> LzipDecoder<R> {
+    /// 当前成员的 LZMA 数据结束：校验尾部，再看后面是否还有成员
+    fn finish_member(&mut self) -> io::Result<()> {
+        let mut trailer = [0u8; TRAILER_LEN];
+        self.reader.read_exact(&mut trailer)?;
+        let crc = u32::from_le_bytes(trailer[0..4].try_into().unwrap_or_default());
+        let data_size = u64::from_le_bytes(trailer[4..12].try_into().unwrap_or_default());
+        let member_size = u64::from_le_bytes(trailer[12..20].try_into().unwrap_or_default());
+
+        let actual = std::mem::take(&mut self.crc).finalize();
+        if crc != actual {
+            return Err(invalid_data(format!(
+                "lzip CRC mismatch: expected {crc:08x}, got {actual:08x}"
+            )));
+        }
+        if data_size != self.data_size || member_size != self.member_size + TRAILER_LEN as u64 {
+            return Err(invalid_data("lzip member size mismatch".into()));
+        }
+
+        // 成员之后不是 `LZIP` 头部时视为尾随数据，与 lzip 的默认行为一样忽略
+        let mut header = [0u8; HEADER_LEN];
+        let mut filled = 0;
+        while filled < HEADER_LEN {
+            match self.reader.read(&mut header[filled..])? {
+                0 => break,
+                n => filled += n,
+            }
+        }
+        if filled < HEADER_LEN || !header.starts_with(MAGIC) {
+            self.done = true;
+            return Ok(());
+        }
+
+        self.stream = member_stream(&header)?;
+        self.prefix = alone_header(dict_size(header[5]));
+        self.prefix_pos = 0;
+        self.data_size = 0;
+        self.member_size = HEADER_LEN as u64;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for LzipDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while !self.done && !buf.is_empty() {
+            let (total_in, total_out) = (self.stream.total_in(), self.stream.total_out());
+            let status = if self.prefix_pos < self.prefix.len() {
+                let status =
+                    self.stream
+                        .process(&self.prefix[self.prefix_pos..], buf, Action::Run)?;
+                self.prefix_pos += (self.stream.total_in() - total_in) as usize;
+                status
+            } else {
+                let input = self.reader.fill_buf()?;
+                if input.is_empty() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Truncated lzip member",
+                    ));
+                }
+                let status = self.stream.process(input, buf, Action::Run)?;
+                let consumed = self.stream.total_in() - total_in;
+                self.reader.consume(consumed as usize);
+                self.member_size += consumed;
+                status
+            };
+
+            let produced = (self.stream.total_out() - total_out) as usize;
+            self.crc.update(&buf[..produced]);
+            self.data_size += produced as u64;
+            if matches!(status, Status::StreamEnd) {
+                self.finish_member()?;
+            }
+            if produced > 0 {
+                return Ok(produced);
+            }
+        }
+        Ok(0)
+    }
+}
+
+/// 列举 TAR.LZ 文件条目（内容不是 TAR 时返回一个虚拟条目）
+pub fn list_tar_lz_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    super::compressed::list_compressed_entries(ArchiveFormat::TarLzip, path.as_ref())
+}
+
+/// 解压 TAR.LZ 中的单个文件到目标目录
+pub fn extract_tar_lz_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarLzip,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::dict_size;
+
+    #[test]
+    fn decodes_dictionary_size() {
+        assert_eq!(dict_size(0x0C), 1 << 12);
+        assert_eq!(dict_size(0x17), 1 << 23);
+        // 2^24 - 3 × 2^20
+        assert_eq!(dict_size(0x78), (1 << 24) - 3 * (1 << 20));
+    }
+}
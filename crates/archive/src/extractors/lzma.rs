@@ -0,0 +1,23 @@
+//! 旧式 LZMA（`.lzma`、`.tlz`）压缩流，解码器与 deb/rpm 共用 [`super::tar::lzma_decoder`]
+
+use crate::{ArchiveError, ArchiveFormat, Extract};
+use std::path::{Path, PathBuf};
+
+/// 列举 TAR.LZMA 文件条目（内容不是 TAR 时返回一个虚拟条目）
+pub fn list_tar_lzma_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    super::compressed::list_compressed_entries(ArchiveFormat::TarLzma, path.as_ref())
+}
+
+/// 解压 TAR.LZMA 中的单个文件到目标目录
+pub fn extract_tar_lzma_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarLzma,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
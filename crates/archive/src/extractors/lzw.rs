@@ -0,0 +1,237 @@
+//! Unix compress（`.Z`）压缩流
+//!
+//! 头部为 `1F 9D` 和一个标志字节：低 5 位为最大码长（9–16），最高位表示分块模式，
+//! 分块模式下码字 256 用于清空字典。码字低位在前，码长从 9 位开始逐步增加。
+//! compress 以 8 个码字为一组读写，码长变化或清空字典时当前组剩余的位会被丢弃，
+//! 解码时需要同样跳过。
+
+use crate::{ArchiveError, ArchiveFormat, Extract};
+use std::{
+    io::{self, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+const MAGIC: [u8; 2] = [0x1F, 0x9D];
+const BLOCK_MODE: u8 = 0x80;
+const BITS_MASK: u8 = 0x1F;
+const INIT_BITS: u32 = 9;
+const MAX_BITS: u32 = 16;
+/// 分块模式下清空字典的码字
+const CLEAR: u32 = 256;
+
+/// 创建 compress 流式解码器，读取并检查头部
+pub(crate) fn lzw_decoder<'a, R: Read + 'a>(
+    mut reader: R,
+) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+    let mut header = [0u8; 3];
+    reader.read_exact(&mut header)?;
+    if header[..2] != MAGIC {
+        return Err(ArchiveError::Other("Not a compress (.Z) stream".into()));
+    }
+    let max_bits = u32::from(header[2] & BITS_MASK);
+    if !(INIT_BITS..=MAX_BITS).contains(&max_bits) {
+        return Err(ArchiveError::Other(format!(
+            "Unsupported compress code size: {max_bits}"
+        )));
+    }
+
+    let block_mode = header[2] & BLOCK_MODE != 0;
+    Ok(Box::new(LzwDecoder {
+        reader: BufReader::new(reader),
+        max_bits,
+        block_mode,
+        n_bits: INIT_BITS,
+        free: if block_mode { CLEAR + 1 } else { CLEAR },
+        group: 0,
+        bit_buf: 0,
+        bit_len: 0,
+        prefix: vec![0; 1 << MAX_BITS],
+        suffix: vec![0; 1 << MAX_BITS],
+        prev: None,
+        first: 0,
+        stack: Vec::new(),
+        eof: false,
+    }))
+}
+
+fn invalid_code(code: u32) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Invalid compress code: {code}"),
+    )
+}
+
+struct LzwDecoder<R> {
+    reader: BufReader<R>,
+    max_bits: u32,
+    block_mode: bool,
+    /// 当前码长
+    n_bits: u32,
+    /// 下一个新字典项的码字
+    free: u32,
+    /// 当前组内已读取的码字数
+    group: u32,
+    bit_buf: u32,
+    bit_len: u32,
+    /// 字典项的前缀码字和末尾字节
+    prefix: Vec<u16>,
+    suffix: Vec<u8>,
+    /// 上一个码字
+    prev: Option<u32>,
+    /// 上一个码字展开后的第一个字节
+    first: u8,
+    /// 待输出的字节，倒序存放
+    stack: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: Read> LzwDecoder<R> {
+    /// 读取 `bits` 位，数据不足时返回 `None`（流末尾不足一个码字的位是填充）
+    fn read_bits(&mut self, bits: u32) -> io::Result<Option<u32>> {
+        while self.bit_len < bits {
+            let mut byte = [0u8];
+            if self.reader.read(&mut byte)? == 0 {
+                return Ok(None);
+            }
+            self.bit_buf |= u32::from(byte[0]) << self.bit_len;
+            self.bit_len += 8;
+        }
+        let value = self.bit_buf & ((1 << bits) - 1);
+        self.bit_buf >>= bits;
+        self.bit_len -= bits;
+        Ok(Some(value))
+    }
+
+    /// 丢弃当前组剩余码字所占的位
+    fn skip_group(&mut self) -> io::Result<()> {
+        let remaining = (8 - self.group % 8) % 8;
+        for _ in 0..remaining {
+            if self.read_bits(self.n_bits)?.is_none() {
+                break;
+            }
+        }
+        self.group = 0;
+        Ok(())
+    }
+
+    /// 解码下一个码字，展开结果压入 `stack`；流结束时返回 `false`
+    fn decode_next(&mut self) -> io::Result<bool> {
+        loop {
+            if self.n_bits < self.max_bits && self.free >= 1 << self.n_bits {
+                self.skip_group()?;
+                self.n_bits += 1;
+            }
+
+            let Some(code) = self.read_bits(self.n_bits)? else {
+                return Ok(false);
+            };
+            self.group += 1;
+
+            let Some(prev) = self.prev else {
+                if code >= CLEAR {
+                    return Err(invalid_code(code));
+                }
+                self.first = code as u8;
+                self.stack.push(self.first);
+                self.prev = Some(code);
+                return Ok(true);
+            };
+
+            if code == CLEAR && self.block_mode {
+                // 与 compress 一致：清空后的第一个码字仍以上一个码字为前缀登记字典项 256，
+                // 该项不会被引用
+                self.skip_group()?;
+                self.n_bits = INIT_BITS;
+                self.free = CLEAR;
+                continue;
+            }
+
+            let mut current = code;
+            if current >= self.free {
+                // KwKwK：码字就是正要登记的这一项
+                if current > self.free {
+                    return Err(invalid_code(code));
+                }
+                self.stack.push(self.first);
+                current = prev;
+            }
+            while current >= 256 {
+                if self.stack.len() >= 1 << MAX_BITS {
+                    return Err(invalid_code(code));
+                }
+                self.stack.push(self.suffix[current as usize]);
+                current = u32::from(self.prefix[current as usize]);
+            }
+            self.first = current as u8;
+            self.stack.push(self.first);
+
+            if self.free < 1 << self.max_bits {
+                self.prefix[self.free as usize] = prev as u16;
+                self.suffix[self.free as usize] = self.first;
+                self.free += 1;
+            }
+            self.prev = Some(code);
+            return Ok(true);
+        }
+    }
+}
+
+impl<R: Read> Read for LzwDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut written = 0;
+        while written < buf.len() {
+            if let Some(byte) = self.stack.pop() {
+                buf[written] = byte;
+                written += 1;
+                continue;
+            }
+            if self.eof || !self.decode_next()? {
+                self.eof = true;
+                break;
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// 列举 TAR.Z 文件条目（内容不是 TAR 时返回一个虚拟条目）
+pub fn list_tar_z_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    super::compressed::list_compressed_entries(ArchiveFormat::TarZ, path.as_ref())
+}
+
+/// 解压 TAR.Z 中的单个文件到目标目录
+pub fn extract_tar_z_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarZ,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::lzw_decoder;
+    use std::io::Read;
+
+    #[test]
+    fn decodes_compress_stream() {
+        // `compress` 输出，末尾的连续 `a` 会产生引用自身的码字（KwKwK）
+        let data: &[u8] = &[
+            0x1F, 0x9D, 0x90, 0x54, 0x9E, 0x08, 0x29, 0xF2, 0x44, 0x8A, 0x93, 0x27, 0x54, 0x02,
+            0x0E, 0x2C, 0xA8, 0x90, 0xA0, 0x41, 0x84, 0x23, 0xC2, 0x48, 0x9C, 0x28, 0x11,
+        ];
+        let mut output = String::new();
+        lzw_decoder(data)
+            .unwrap()
+            .read_to_string(&mut output)
+            .unwrap();
+        assert_eq!(output, "TOBEORNOTTOBEORTOBEORNOT#aaaaaaaa");
+
+        assert!(lzw_decoder(&[0x1F, 0x9D, 0x1F][..]).is_err());
+    }
+}
@@ -1,15 +1,136 @@
+#[cfg(feature = "ar")]
 pub mod ar;
+#[cfg(feature = "brotli")]
+pub mod brotli;
+#[cfg(feature = "cab")]
+pub mod cab;
+#[cfg(feature = "tar")]
+pub mod compressed;
+#[cfg(feature = "cpio")]
 pub mod cpio;
+#[cfg(feature = "deb")]
+pub mod deb;
+#[cfg(feature = "iso")]
+pub mod iso;
+#[cfg(feature = "lz4")]
+pub mod lz4;
+#[cfg(feature = "lzip")]
+pub mod lzip;
+#[cfg(feature = "lzma")]
+pub mod lzma;
+#[cfg(feature = "compress")]
+pub mod lzw;
+#[cfg(feature = "msi")]
+pub mod msi;
+#[cfg(feature = "rar")]
 pub mod rar;
+#[cfg(feature = "rpm")]
+pub mod rpm;
+#[cfg(feature = "sevenz")]
 pub mod sevenz;
+#[cfg(feature = "squashfs")]
+pub mod squashfs;
+#[cfg(feature = "tar")]
 pub mod tar;
+#[cfg(feature = "iso")]
+mod udf;
+#[cfg(feature = "zip")]
 pub mod zip;
+#[cfg(feature = "zstd")]
 pub mod zst;
 
+#[cfg(feature = "ar")]
 pub use ar::*;
+#[cfg(feature = "brotli")]
+pub use brotli::*;
+#[cfg(feature = "cab")]
+pub use cab::*;
+#[cfg(feature = "tar")]
+pub use compressed::*;
+#[cfg(feature = "cpio")]
 pub use cpio::*;
+#[cfg(feature = "deb")]
+pub use deb::*;
+#[cfg(feature = "iso")]
+pub use iso::*;
+#[cfg(feature = "lz4")]
+pub use lz4::*;
+#[cfg(feature = "lzip")]
+pub use lzip::*;
+#[cfg(feature = "lzma")]
+pub use lzma::*;
+#[cfg(feature = "compress")]
+pub use lzw::*;
+#[cfg(feature = "msi")]
+pub use msi::*;
+#[cfg(feature = "rar")]
 pub use rar::*;
+#[cfg(feature = "rpm")]
+pub use rpm::*;
+#[cfg(feature = "sevenz")]
 pub use sevenz::*;
+#[cfg(feature = "squashfs")]
+pub use squashfs::*;
+#[cfg(feature = "tar")]
 pub use tar::*;
+#[cfg(feature = "zip")]
 pub use zip::*;
+#[cfg(feature = "zstd")]
 pub use zst::*;
+
+use crate::ArchiveError;
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+/// 计算条目解压后的输出路径，并确保目标目录存在
+///
+/// 只取条目路径的最后一段作为文件名，避免 `../` 之类的条目名写到目标目录之外。
+pub(crate) fn entry_output_path(
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    let file_name = entry_name
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()
+        .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+        .ok_or_else(|| ArchiveError::InvalidPath(entry_name.to_string()))?;
+
+    std::fs::create_dir_all(dest_dir)?;
+    Ok(dest_dir.join(file_name))
+}
+
+/// 符号链接、硬链接条目没有文件数据，读取时报 [`ArchiveError::LinkEntry`]
+#[cfg(any(feature = "tar", feature = "iso", feature = "squashfs"))]
+pub(crate) fn ensure_not_link(item: &crate::Extract) -> Result<(), ArchiveError> {
+    let target = item.link_target.as_ref().or(item.hardlink_target.as_ref());
+    if target.is_some()
+        || matches!(
+            item.kind,
+            crate::EntryKind::Symlink | crate::EntryKind::Hardlink
+        )
+    {
+        return Err(ArchiveError::LinkEntry {
+            entry: item.name.clone(),
+            target: target.cloned(),
+        });
+    }
+    Ok(())
+}
+
+/// 将单个条目的数据写入目标目录，返回写出的文件路径
+pub(crate) fn write_entry_to_dir<R: Read + ?Sized>(
+    reader: &mut R,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    let dest_path = entry_output_path(entry_name, dest_dir)?;
+    let mut writer = BufWriter::new(File::create(&dest_path)?);
+    std::io::copy(reader, &mut writer)?;
+    writer.flush()?;
+
+    Ok(dest_path)
+}
@@ -0,0 +1,739 @@
+//! Windows Installer 安装包（.msi）及合并模块（.msm）
+//!
+//! MSI 是复合文档（CFB），安装数据库的每张表保存为一个流，按列连续存放；表中的字符串统一保存在
+//! `_StringPool`/`_StringData` 两个流中，单元格只记录序号。列举结果包括 File 表登记的文件
+//! （路径由 Component、Directory 表确定，带有大小和版本号）以及复合文档中的全部流，后者位于
+//! `[Streams]/` 目录下，表的流名前加 `!`。文件数据从 Media 表指向的内嵌 CAB 流或安装包旁边的
+//! 外部 CAB 中读取。
+
+use super::cab::CabReader;
+use crate::reader::{ArchiveMetadata, ArchiveReader, EntryFn, Input, SeekInput, VisitFn};
+use crate::{ArchiveError, ArchiveFormat, Extract};
+use encoding_rs::{
+    Encoding, BIG5, EUC_KR, GBK, IBM866, SHIFT_JIS, UTF_8, WINDOWS_1250, WINDOWS_1251,
+    WINDOWS_1252, WINDOWS_1253, WINDOWS_1254, WINDOWS_1255, WINDOWS_1256, WINDOWS_1257,
+    WINDOWS_1258, WINDOWS_874,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+/// 表的流名以该字符开头
+const TABLE_PREFIX: char = '\u{4840}';
+
+/// 复合文档中的流在列举结果中所在的目录
+const STREAMS_DIR: &str = "[Streams]";
+
+/// 列类型的标志位（`_Columns` 表的 Type 列）
+const COLUMN_VALID: i32 = 0x0100;
+const COLUMN_STRING: i32 = 0x0800;
+const COLUMN_NULLABLE: i32 = 0x1000;
+const COLUMN_TEMPORARY: i32 = 0x4000;
+
+/// `_StringPool` 头部中表示字符串序号占 3 字节的标志位
+const LONG_STRING_REFS: u16 = 0x8000;
+
+/// Directory 表的父目录链最多跟随的层数，防止循环引用
+const MAX_DIRECTORY_DEPTH: usize = 64;
+
+/// 流名称中的一个 6 位编码对应的字符：`0-9A-Za-z._`
+fn name_char(value: u32) -> char {
+    let value = value as u8;
+    match value {
+        0..=9 => char::from(b'0' + value),
+        10..=35 => char::from(b'A' + value - 10),
+        36..=61 => char::from(b'a' + value - 36),
+        62 => '.',
+        _ => '_',
+    }
+}
+
+/// 解码流名称：U+3800–U+47FF 的一个码元表示两个字符，U+4800–U+483F 表示一个字符；
+/// 返回是否为表以及解码后的名称，控制字符（如 `\x05SummaryInformation`）写作 `[5]`
+fn decode_stream_name(name: &str) -> (bool, String) {
+    let (table, name) = match name.strip_prefix(TABLE_PREFIX) {
+        Some(name) => (true, name),
+        None => (false, name),
+    };
+    let mut decoded = String::new();
+    for c in name.chars() {
+        match u32::from(c) {
+            code @ 0x3800..=0x47FF => {
+                let value = code - 0x3800;
+                decoded.push(name_char(value & 0x3F));
+                decoded.push(name_char(value >> 6));
+            },
+            code @ 0x4800..=0x483F => decoded.push(name_char(code - 0x4800)),
+            code @ 0..=0x1F => decoded.push_str(&format!("[{code}]")),
+            _ => decoded.push(c),
+        }
+    }
+    (table, decoded)
+}
+
+/// 字符串池使用的 Windows 代码页，0（中性）及未列出的代码页按 Windows-1252 解码
+fn codepage_encoding(codepage: u32) -> &'static Encoding {
+    match codepage {
+        65001 => UTF_8,
+        936 => GBK,
+        950 => BIG5,
+        932 => SHIFT_JIS,
+        949 => EUC_KR,
+        866 => IBM866,
+        874 => WINDOWS_874,
+        1250 => WINDOWS_1250,
+        1251 => WINDOWS_1251,
+        1253 => WINDOWS_1253,
+        1254 => WINDOWS_1254,
+        1255 => WINDOWS_1255,
+        1256 => WINDOWS_1256,
+        1257 => WINDOWS_1257,
+        1258 => WINDOWS_1258,
+        _ => WINDOWS_1252,
+    }
+}
+
+fn u16_at(data: &[u8], index: usize) -> u16 {
+    u16::from_le_bytes([data[index * 2], data[index * 2 + 1]])
+}
+
+/// 数据库的字符串池，序号 0 表示空值
+struct StringPool {
+    strings: Vec<Option<String>>,
+    /// 表中字符串序号的字节数（2 或 3）
+    ref_size: usize,
+}
+
+impl StringPool {
+    /// `pool` 为每个字符串的 (长度, 引用计数)，`data` 为依次拼接的字符串内容
+    fn parse(pool: &[u8], data: &[u8]) -> Result<Self, ArchiveError> {
+        if pool.len() < 4 {
+            return Err(ArchiveError::Other("Invalid MSI string pool".into()));
+        }
+        let words = pool.len() / 2;
+        let high = u16_at(pool, 1);
+        let codepage = u32::from(u16_at(pool, 0)) | (u32::from(high & !LONG_STRING_REFS) << 16);
+        let encoding = codepage_encoding(codepage);
+
+        let mut strings = vec![None];
+        let mut offset = 0;
+        let mut i = 1;
+        while i * 2 + 1 < words {
+            let (len, refs) = (u16_at(pool, i * 2), u16_at(pool, i * 2 + 1));
+            if len == 0 && refs == 0 {
+                // 空位仍然占用一个序号
+                strings.push(None);
+                i += 1;
+                continue;
+            }
+            // 超过 64 KiB 的字符串：长度为 0，实际长度记录在下一项中
+            let len = if len == 0 && i * 2 + 3 < words {
+                i += 2;
+                (usize::from(u16_at(pool, i * 2 + 1)) << 16) | usize::from(u16_at(pool, i * 2))
+            } else {
+                i += 1;
+                usize::from(len)
+            };
+            let bytes = data
+                .get(offset..offset + len)
+                .ok_or_else(|| ArchiveError::Other("Truncated MSI string data".into()))?;
+            strings.push(Some(
+                encoding.decode_without_bom_handling(bytes).0.into_owned(),
+            ));
+            offset += len;
+        }
+        Ok(Self {
+            strings,
+            ref_size: if high & LONG_STRING_REFS != 0 { 3 } else { 2 },
+        })
+    }
+
+    fn get(&self, index: u32) -> Option<&str> {
+        self.strings.get(index as usize)?.as_deref()
+    }
+}
+
+/// 表中一列的名称和存储宽度
+struct Column {
+    name: String,
+    width: usize,
+    string: bool,
+}
+
+impl Column {
+    /// 由 `_Columns` 表的 Type 列确定宽度，临时列不保存在文件中
+    fn new(name: String, kind: i32, ref_size: usize) -> Option<Self> {
+        if kind & COLUMN_TEMPORARY != 0 {
+            return None;
+        }
+        // 二进制（流）列保存 2 字节的值
+        let binary = kind & !COLUMN_NULLABLE == COLUMN_STRING | COLUMN_VALID;
+        let (width, string) = if binary {
+            (2, false)
+        } else if kind & COLUMN_STRING != 0 {
+            (ref_size, true)
+        } else if kind & 0xFF <= 2 {
+            (2, false)
+        } else {
+            (4, false)
+        };
+        Some(Self { name, width, string })
+    }
+}
+
+/// 按列存放的表：先是所有行的第一列，再是所有行的第二列……
+struct Table {
+    columns: Vec<Column>,
+    rows: usize,
+    data: Vec<u8>,
+}
+
+impl Table {
+    fn new(columns: Vec<Column>, data: Vec<u8>) -> Self {
+        let row_size: usize = columns.iter().map(|column| column.width).sum();
+        let rows = data.len().checked_div(row_size).unwrap_or(0);
+        Self { columns, rows, data }
+    }
+
+    /// 单元格的原始值
+    fn raw(&self, row: usize, column: &str) -> Option<(u32, &Column)> {
+        let mut start = 0;
+        for col in &self.columns {
+            if col.name == column {
+                let offset = start + row * col.width;
+                let bytes = self.data.get(offset..offset + col.width)?;
+                let value = bytes
+                    .iter()
+                    .rev()
+                    .fold(0u32, |acc, &byte| (acc << 8) | u32::from(byte));
+                return Some((value, col));
+            }
+            start += self.rows * col.width;
+        }
+        None
+    }
+
+    fn string<'p>(&self, pool: &'p StringPool, row: usize, column: &str) -> Option<&'p str> {
+        let (value, col) = self.raw(row, column)?;
+        col.string.then(|| pool.get(value)).flatten()
+    }
+
+    /// 整数单元格：保存时加上了 0x8000（2 字节）或 0x80000000（4 字节），0 表示空值
+    fn int(&self, row: usize, column: &str) -> Option<i32> {
+        let (value, col) = self.raw(row, column)?;
+        match (value, col.width) {
+            (0, _) => None,
+            (value, 2) => Some(value as i32 - 0x8000),
+            (value, _) => Some((value ^ 0x8000_0000) as i32),
+        }
+    }
+}
+
+/// 条目数据的来源
+enum Source {
+    /// 复合文档中的流
+    Stream(PathBuf),
+    /// File 表登记的文件：CAB 中的条目名（File 表主键）以及所在的 CAB，
+    /// `#` 开头为内嵌的流，`None` 表示文件未打包在 CAB 中
+    File {
+        key: String,
+        cabinet: Option<String>,
+    },
+    Directory,
+}
+
+/// 复合文档中的一个流
+struct StreamInfo {
+    path: PathBuf,
+    table: bool,
+    name: String,
+    len: u64,
+}
+
+type Compound<'r> = cfb::CompoundFile<Box<dyn crate::reader::ReadSeek + 'r>>;
+
+fn read_stream(compound: &mut Compound<'_>, path: &Path) -> Result<Vec<u8>, ArchiveError> {
+    let mut data = Vec::new();
+    compound.open_stream(path)?.read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// 安装数据库：字符串池、列定义和表的流
+struct Database<'c, 'r> {
+    compound: &'c mut Compound<'r>,
+    streams: &'c [StreamInfo],
+    pool: StringPool,
+    columns: HashMap<String, Vec<(i32, Column)>>,
+}
+
+impl<'c, 'r> Database<'c, 'r> {
+    fn open(
+        compound: &'c mut Compound<'r>,
+        streams: &'c [StreamInfo],
+    ) -> Result<Self, ArchiveError> {
+        let mut table_data = |name: &str| -> Result<Vec<u8>, ArchiveError> {
+            match streams
+                .iter()
+                .find(|stream| stream.table && stream.name == name)
+            {
+                Some(stream) => read_stream(compound, &stream.path),
+                None => Err(ArchiveError::Other(format!("MSI table {name} not found"))),
+            }
+        };
+        let pool = StringPool::parse(&table_data("_StringPool")?, &table_data("_StringData")?)?;
+
+        // `_Columns` 自身的结构是固定的：Table、Number、Name、Type
+        let r = pool.ref_size;
+        let schema = Table::new(
+            vec![
+                Column::new("Table".into(), COLUMN_STRING, r).expect("string column"),
+                Column::new("Number".into(), 2, r).expect("integer column"),
+                Column::new("Name".into(), COLUMN_STRING, r).expect("string column"),
+                Column::new("Type".into(), 2, r).expect("integer column"),
+            ],
+            table_data("_Columns")?,
+        );
+        let mut columns: HashMap<String, Vec<(i32, Column)>> = HashMap::new();
+        for row in 0..schema.rows {
+            let (Some(table), Some(number), Some(name), Some(kind)) = (
+                schema.string(&pool, row, "Table"),
+                schema.int(row, "Number"),
+                schema.string(&pool, row, "Name"),
+                schema.int(row, "Type"),
+            ) else {
+                continue;
+            };
+            if let Some(column) = Column::new(name.to_string(), kind, r) {
+                columns
+                    .entry(table.to_string())
+                    .or_default()
+                    .push((number, column));
+            }
+        }
+        for table in columns.values_mut() {
+            table.sort_by_key(|(number, _)| *number);
+        }
+        Ok(Self { compound, streams, pool, columns })
+    }
+
+    /// 读取一张表，数据库中没有该表时为 `None`
+    fn table(&mut self, name: &str) -> Result<Option<Table>, ArchiveError> {
+        let Some(columns) = self.columns.remove(name) else {
+            return Ok(None);
+        };
+        let columns = columns.into_iter().map(|(_, column)| column).collect();
+        let data = match self
+            .streams
+            .iter()
+            .find(|stream| stream.table && stream.name == name)
+        {
+            Some(stream) => read_stream(self.compound, &stream.path)?,
+            // 没有行的表不保存流
+            None => Vec::new(),
+        };
+        Ok(Some(Table::new(columns, data)))
+    }
+}
+
+/// `short|long` 形式的文件名取长文件名
+fn long_name(name: &str) -> &str {
+    name.split_once('|').map_or(name, |(_, long)| long)
+}
+
+/// Directory 表中每个目录的安装路径（相对 TARGETDIR），由 DefaultDir 的目标部分逐级拼接
+fn directory_paths(table: &Table, pool: &StringPool) -> HashMap<String, String> {
+    let mut parents = HashMap::new();
+    for row in 0..table.rows {
+        let Some(key) = table.string(pool, row, "Directory") else {
+            continue;
+        };
+        let parent = table
+            .string(pool, row, "Directory_Parent")
+            .filter(|parent| *parent != key);
+        // `目标:源`，`.` 表示与父目录相同
+        let default_dir = table.string(pool, row, "DefaultDir").unwrap_or(".");
+        let target = default_dir
+            .split_once(':')
+            .map_or(default_dir, |(target, _)| target);
+        let name = match (parent, long_name(target)) {
+            // 根目录（TARGETDIR 的 SourceDir）不出现在路径中
+            (None, _) | (_, ".") => "",
+            (Some(_), name) => name,
+        };
+        parents.insert(
+            key.to_string(),
+            (parent.map(str::to_string), name.to_string()),
+        );
+    }
+
+    let mut paths = HashMap::new();
+    for key in parents.keys() {
+        let mut segments = Vec::new();
+        let mut current = Some(key.as_str());
+        for _ in 0..MAX_DIRECTORY_DEPTH {
+            let Some((parent, name)) = current.and_then(|key| parents.get(key)) else {
+                break;
+            };
+            if !name.is_empty() {
+                segments.push(name.as_str());
+            }
+            current = parent.as_deref();
+        }
+        segments.reverse();
+        paths.insert(key.clone(), segments.join("/"));
+    }
+    paths
+}
+
+/// 读取复合文档中的流以及 File 表登记的文件
+fn read_index(compound: &mut Compound<'_>) -> Result<Vec<(Extract, Source)>, ArchiveError> {
+    let mut streams: Vec<StreamInfo> = compound
+        .walk()
+        .filter(|entry| entry.is_stream())
+        .map(|entry| {
+            let mut table = false;
+            let name = entry
+                .path()
+                .iter()
+                .skip(1)
+                .map(|segment| {
+                    let (is_table, name) = decode_stream_name(&segment.to_string_lossy());
+                    table = is_table;
+                    name
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            StreamInfo {
+                path: entry.path().to_path_buf(),
+                table,
+                name,
+                len: entry.len(),
+            }
+        })
+        .collect();
+    streams.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut entries = Vec::new();
+    let mut db = Database::open(compound, &streams)?;
+    let directories = match db.table("Directory")? {
+        Some(table) => directory_paths(&table, &db.pool),
+        None => HashMap::new(),
+    };
+    let mut component_dirs = HashMap::new();
+    if let Some(table) = db.table("Component")? {
+        for row in 0..table.rows {
+            if let (Some(component), Some(directory)) = (
+                table.string(&db.pool, row, "Component"),
+                table.string(&db.pool, row, "Directory_"),
+            ) {
+                component_dirs.insert(component.to_string(), directory.to_string());
+            }
+        }
+    }
+    // (LastSequence, Cabinet)，按最后一个文件的序号排列
+    let mut media = Vec::new();
+    if let Some(table) = db.table("Media")? {
+        for row in 0..table.rows {
+            let last = table.int(row, "LastSequence").unwrap_or(0);
+            let cabinet = table.string(&db.pool, row, "Cabinet");
+            media.push((
+                last,
+                cabinet
+                    .filter(|cabinet| !cabinet.is_empty())
+                    .map(str::to_string),
+            ));
+        }
+    }
+    media.sort_by_key(|(last, _)| *last);
+
+    let mut dirs: Vec<&String> = directories
+        .values()
+        .filter(|path| !path.is_empty())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    dirs.sort();
+    for dir in dirs {
+        entries.push((
+            Extract::new(format!("{dir}/"), 0, None, true),
+            Source::Directory,
+        ));
+    }
+
+    if let Some(table) = db.table("File")? {
+        let mut files = Vec::new();
+        for row in 0..table.rows {
+            let Some(key) = table.string(&db.pool, row, "File") else {
+                continue;
+            };
+            let file_name = long_name(table.string(&db.pool, row, "FileName").unwrap_or(key));
+            let dir = table
+                .string(&db.pool, row, "Component_")
+                .and_then(|component| component_dirs.get(component))
+                .and_then(|directory| directories.get(directory))
+                .filter(|dir| !dir.is_empty());
+            let name = match dir {
+                Some(dir) => format!("{dir}/{file_name}"),
+                None => file_name.to_string(),
+            };
+            let size = table.int(row, "FileSize").unwrap_or(0);
+            let sequence = table.int(row, "Sequence").unwrap_or(0);
+            let mut item = Extract::new(name, u64::try_from(size).unwrap_or(0), None, false);
+            // Version 列也可能是伴随文件的 File 主键，只保留版本号
+            item.version = table
+                .string(&db.pool, row, "Version")
+                .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+                .map(str::to_string);
+            let cabinet = media
+                .iter()
+                .find(|(last, _)| *last >= sequence)
+                .and_then(|(_, cabinet)| cabinet.clone());
+            files.push((
+                sequence,
+                item,
+                Source::File { key: key.to_string(), cabinet },
+            ));
+        }
+        files.sort_by_key(|(sequence, _, _)| *sequence);
+        entries.extend(files.into_iter().map(|(_, item, source)| (item, source)));
+    }
+
+    entries.push((
+        Extract::new(format!("{STREAMS_DIR}/"), 0, None, true),
+        Source::Directory,
+    ));
+    for stream in &streams {
+        let name = if stream.table {
+            format!("{STREAMS_DIR}/!{}", stream.name)
+        } else {
+            format!("{STREAMS_DIR}/{}", stream.name)
+        };
+        entries.push((
+            Extract::new(name, stream.len, None, false),
+            Source::Stream(stream.path.clone()),
+        ));
+    }
+    Ok(entries)
+}
+
+/// 没有打包进 CAB 的文件以原样放在安装包旁边的目录中，无法从安装包读取
+fn uncompressed_file(name: &str) -> ArchiveError {
+    ArchiveError::Other(format!("MSI file is not stored in a cabinet: {name}"))
+}
+
+/// 列举 MSI 中的文件和流
+pub fn list_msi_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    MsiReader::open(Input::Path(path.as_ref().to_path_buf()), None)?.list()
+}
+
+pub struct MsiReader {
+    input: SeekInput,
+    /// 磁盘上的安装包所在目录，用于查找外部 CAB
+    dir: Option<PathBuf>,
+    /// 第一次列举或读取时建立的条目索引
+    index: Option<Vec<(Extract, Source)>>,
+}
+
+impl MsiReader {
+    fn load_index(&mut self) -> Result<&[(Extract, Source)], ArchiveError> {
+        if self.index.is_none() {
+            let (image, _) = self.input.reader()?;
+            let mut compound = cfb::CompoundFile::open(image)?;
+            self.index = Some(read_index(&mut compound)?);
+        }
+        Ok(self.index.as_deref().expect("index loaded"))
+    }
+
+    /// 打开 File 表中文件所在的 CAB：`#` 开头为内嵌的流，否则为安装包旁边的文件
+    fn open_cabinet(&self, cabinet: &str) -> Result<CabReader, ArchiveError> {
+        if let Some(stream) = cabinet.strip_prefix('#') {
+            let index = self.index.as_deref().unwrap_or_default();
+            let path = index.iter().find_map(|(item, source)| match source {
+                Source::Stream(path) if item.name == format!("{STREAMS_DIR}/{stream}") => {
+                    Some(path)
+                },
+                _ => None,
+            });
+            let path =
+                path.ok_or_else(|| ArchiveError::EntryNotFound(format!("{STREAMS_DIR}/{stream}")))?;
+            let (image, _) = self.input.reader()?;
+            let data = read_stream(&mut cfb::CompoundFile::open(image)?, path)?;
+            return CabReader::open(
+                Input::Stream {
+                    reader: Box::new(Cursor::new(data)),
+                    name: stream.to_string(),
+                },
+                None,
+            );
+        }
+        let path = self
+            .dir
+            .as_ref()
+            .map(|dir| dir.join(cabinet))
+            .filter(|path| path.is_file())
+            .ok_or_else(|| ArchiveError::Other(format!("External cabinet not found: {cabinet}")))?;
+        CabReader::open(Input::Path(path), None)
+    }
+}
+
+impl<'a> ArchiveReader<'a> for MsiReader {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        let dir = match &input {
+            Input::Path(path) => path.parent().map(Path::to_path_buf),
+            _ => None,
+        };
+        Ok(Self {
+            input: SeekInput::new(input)?,
+            dir,
+            index: None,
+        })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Msi)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        Ok(self
+            .load_index()?
+            .iter()
+            .map(|(item, _)| item.clone())
+            .collect())
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        self.load_index()?;
+        let (_, source) = self
+            .index
+            .as_deref()
+            .expect("index loaded")
+            .iter()
+            .find(|(item, _)| item.name == entry_name)
+            .ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))?;
+        match source {
+            Source::Directory => Err(ArchiveError::InvalidPath(entry_name.to_string())),
+            Source::Stream(path) => {
+                let (image, _) = self.input.reader()?;
+                let mut compound = cfb::CompoundFile::open(image)?;
+                let mut stream = compound.open_stream(path)?;
+                f(&mut stream)
+            },
+            Source::File { key, cabinet } => match cabinet {
+                Some(cabinet) => self.open_cabinet(cabinet)?.read_entry(key, f),
+                None => Err(uncompressed_file(entry_name)),
+            },
+        }
+    }
+
+    /// 先按 CAB 依次解压 File 表中的文件，再读取复合文档中的流
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        self.load_index()?;
+        let index = self.index.as_deref().expect("index loaded");
+
+        let mut by_cabinet: Vec<(Option<&str>, BTreeMap<&str, &Extract>)> = Vec::new();
+        for (item, source) in index {
+            if let Source::File { key, cabinet } = source {
+                let cabinet = cabinet.as_deref();
+                match by_cabinet.iter_mut().find(|(name, _)| *name == cabinet) {
+                    Some((_, files)) => {
+                        files.insert(key, item);
+                    },
+                    None => by_cabinet.push((cabinet, BTreeMap::from([(key.as_str(), item)]))),
+                }
+            }
+        }
+
+        for (cabinet, mut files) in by_cabinet {
+            let mut keep_going = true;
+            let Some(cabinet) = cabinet else {
+                for item in files.values() {
+                    if !f(item, Err(uncompressed_file(&item.name)))? {
+                        return Ok(());
+                    }
+                }
+                continue;
+            };
+            match self.open_cabinet(cabinet) {
+                Ok(mut cab) => cab.for_each_entry(&mut |cab_item, data| match files
+                    .remove(cab_item.name.as_str())
+                {
+                    Some(item) => {
+                        keep_going = f(item, data)?;
+                        Ok(keep_going)
+                    },
+                    None => Ok(true),
+                })?,
+                Err(err) => {
+                    // 错误不能复制，其余文件只带上错误信息
+                    let message = err.to_string();
+                    let mut err = Some(err);
+                    for item in files.values() {
+                        let err = err
+                            .take()
+                            .unwrap_or_else(|| ArchiveError::Other(message.clone()));
+                        if !f(item, Err(err))? {
+                            return Ok(());
+                        }
+                    }
+                    files.clear();
+                },
+            }
+            if !keep_going {
+                return Ok(());
+            }
+            // CAB 中缺少的文件
+            for (key, item) in files {
+                if !f(item, Err(ArchiveError::EntryNotFound(key.to_string())))? {
+                    return Ok(());
+                }
+            }
+        }
+
+        let (image, _) = self.input.reader()?;
+        let mut compound = cfb::CompoundFile::open(image)?;
+        for (item, source) in index {
+            if let Source::Stream(path) = source {
+                let keep_going = match compound.open_stream(path) {
+                    Ok(mut stream) => f(item, Ok(&mut stream))?,
+                    Err(err) => f(item, Err(err.into()))?,
+                };
+                if !keep_going {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_stream_name, StringPool};
+
+    #[test]
+    fn decodes_stream_names_and_string_pool() {
+        // "_StringPool" 表：`_S`、`tr`、`in`、`gP`、`oo` 各压缩为一个码元，末尾的 `l` 单独一个
+        assert_eq!(
+            decode_stream_name("\u{4840}\u{3f3f}\u{4577}\u{446c}\u{3e6a}\u{44b2}\u{482f}"),
+            (true, "_StringPool".to_string())
+        );
+        assert_eq!(
+            decode_stream_name("\u{5}SummaryInformation"),
+            (false, "[5]SummaryInformation".to_string())
+        );
+
+        // 代码页 936，序号 2 为空位，字符串序号占 3 字节
+        let pool = [0xA8, 0x03, 0x00, 0x80, 3, 0, 1, 0, 0, 0, 0, 0, 4, 0, 1, 0];
+        let pool = StringPool::parse(&pool, b"Foo\xCE\xC4\xB5\xB5").unwrap();
+        assert_eq!(pool.ref_size, 3);
+        assert_eq!(pool.get(1), Some("Foo"));
+        assert_eq!(pool.get(2), None);
+        assert_eq!(pool.get(3), Some("文档"));
+    }
+}
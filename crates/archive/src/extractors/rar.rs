@@ -1,13 +1,43 @@
-use crate::{ArchiveError, Extract};
-use chrono::NaiveDate;
-use std::path::Path;
+use crate::encoding::{NameDecoder, NameEncoding};
+use crate::reader::{
+    collect_entries, ArchiveMetadata, ArchiveReader, EntryFn, FileInput, Input, ListFn,
+    PasswordProtection, SpilledFile, VisitFn, MAX_MEMORY_INPUT,
+};
+use crate::{volume, ArchiveError, ArchiveFormat, EntryTime, Extract};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::{Path, PathBuf},
+};
 
-/// 列举 RAR 文件条目
+/// 列举 RAR 文件条目，`path` 可以是分卷中的任意一卷
 pub fn list_rar_entries<P: AsRef<Path>>(
     path: P,
     password: Option<&str>,
 ) -> Result<Vec<Extract>, ArchiveError> {
-    let path = path.as_ref();
+    list_rar_file(
+        &volume::first_volume(path.as_ref())?,
+        password,
+        NameEncoding::Auto,
+    )
+}
+
+/// 从 `path` 开始列举条目，分卷时 `path` 须为第一卷
+fn list_rar_file(
+    path: &Path,
+    password: Option<&str>,
+    encoding: NameEncoding,
+) -> Result<Vec<Extract>, ArchiveError> {
+    collect_entries(|f| each_rar_entry(path, password, encoding, f))
+}
+
+/// 从 `path` 开始逐条列举条目，分卷时 `path` 须为第一卷
+fn each_rar_entry(
+    path: &Path,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut ListFn<'_>,
+) -> Result<(), ArchiveError> {
     let path_str = path.to_string_lossy().to_string();
 
     let archive = match password {
@@ -16,30 +46,18 @@ pub fn list_rar_entries<P: AsRef<Path>>(
     };
     let archive = archive
         .open_for_listing()
-        .map_err(|e| ArchiveError::Other(format!("Failed to open RAR archive: {}", e)))?;
-
-    let mut entries = Vec::new();
+        .map_err(|e| rar_error("Failed to open RAR archive", e))?;
 
     for entry in archive {
         match entry {
             Ok(header) => {
-                let raw_name = header.filename.to_string_lossy().to_string();
-                // 统一路径分隔符为 '/'
-                let name = raw_name.replace('\\', "/");
-                let size = header.unpacked_size;
-                let is_dir = header.is_directory();
-
-                // 目录名称需要以 '/' 结尾
-                let name = if is_dir && !name.ends_with('/') {
-                    format!("{}/", name)
-                } else {
-                    name
-                };
-
-                // 将 RAR 文件时间转换为 yyyy-MM-dd HH:mm:ss 格式
-                let last_modified = rar_time_to_string(header.file_time);
-
-                entries.push(Extract::new(name, size, last_modified, is_dir));
+                if !f(rar_entry(&header, encoding))? {
+                    break;
+                }
+            },
+            // 头部加密时密码错误会在读取条目时才发现
+            Err(e) if is_password_error(&e) => {
+                return Err(rar_error("Failed to read RAR entry", e))
             },
             Err(e) => {
                 log::warn!("Failed to read RAR entry: {}", e);
@@ -48,12 +66,201 @@ pub fn list_rar_entries<P: AsRef<Path>>(
         }
     }
 
-    Ok(entries)
+    Ok(())
+}
+
+/// 条目名：修正按单字节保存的旧编码名称，并统一路径分隔符为 '/'
+///
+/// unrar 逐个读取文件头，无法先统计全部条目名，因此逐个名称识别编码；
+/// 修正要在替换分隔符之前，Shift_JIS 等编码的第二字节可能是 `\`。
+fn rar_entry_name(header: &unrar::FileHeader, encoding: NameEncoding) -> String {
+    let name = header.filename.to_string_lossy().into_owned();
+    let name = NameDecoder::for_unicode(encoding, [name.as_str()]).repair(name);
+    name.replace('\\', "/")
+}
+
+/// 文件头记录的条目信息
+fn rar_entry(header: &unrar::FileHeader, encoding: NameEncoding) -> Extract {
+    let name = rar_entry_name(header, encoding);
+    let size = header.unpacked_size;
+    let is_dir = header.is_directory();
+
+    // 目录名称需要以 '/' 结尾
+    let name = if is_dir && !name.ends_with('/') {
+        format!("{}/", name)
+    } else {
+        name
+    };
+
+    // unrar 把修改时间统一为 DOS 格式（本地时间，高 16 位日期，低 16 位时间），转换为 EntryTime
+    let last_modified =
+        EntryTime::from_dos((header.file_time >> 16) as u16, header.file_time as u16);
+
+    let mut item = Extract::new(name, size, last_modified, is_dir);
+    item.compression_method = Some(rar_method_name(header.method));
+    item.crc32 = Some(header.file_crc);
+    item.encrypted = header.is_encrypted();
+    item
+}
+
+fn open_rar_for_processing(
+    path: &Path,
+    password: Option<&str>,
+) -> Result<unrar::OpenArchive<unrar::Process, unrar::CursorBeforeHeader>, ArchiveError> {
+    let path_str = path.to_string_lossy().to_string();
+    let archive = match password {
+        Some(pw) => unrar::Archive::with_password(&path_str, pw.as_bytes()),
+        None => unrar::Archive::new(&path_str),
+    };
+    archive
+        .open_for_processing()
+        .map_err(|e| rar_error("Failed to open RAR archive", e))
+}
+
+/// 定位 RAR 中的指定文件，并把解压后的数据交给 `f` 处理
+///
+/// unrar 只能整块读出条目数据或解压到文件：[`MAX_MEMORY_INPUT`] 以内的条目解压到内存，
+/// 更大的条目先解压到临时文件再读取。
+pub(crate) fn with_rar_entry<P: AsRef<Path>, T>(
+    path: P,
+    entry_name: &str,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut dyn FnMut(&mut dyn std::io::Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut archive = open_rar_for_processing(path.as_ref(), password)?;
+
+    while let Some(header) = archive
+        .read_header()
+        .map_err(|e| rar_error("Failed to read RAR entry", e))?
+    {
+        if rar_entry_name(header.entry(), encoding) != entry_name {
+            archive = header
+                .skip()
+                .map_err(|e| rar_error("Failed to skip RAR entry", e))?;
+            continue;
+        }
+        if header.entry().is_directory() {
+            return Err(ArchiveError::InvalidPath(entry_name.to_string()));
+        }
+
+        let encrypted = header.entry().is_encrypted();
+        if header.entry().unpacked_size <= MAX_MEMORY_INPUT {
+            let (data, _) = header.read().map_err(|e| extract_error(e, encrypted))?;
+            return f(&mut data.as_slice());
+        }
+
+        let extension = crate::format::extension_of(Path::new(entry_name));
+        let spilled = SpilledFile::reserve(&extension)?;
+        header
+            .extract_to(spilled.path())
+            .map_err(|e| extract_error(e, encrypted))?;
+        return f(&mut BufReader::new(File::open(spilled.path())?));
+    }
+
+    Err(ArchiveError::EntryNotFound(entry_name.to_string()))
+}
+
+/// 依次解压每个文件条目，unrar 解压时校验 CRC
+///
+/// unrar 解压失败后无法继续读取后面的条目，此时先把错误交给 `f`，再返回错误。
+pub(crate) fn for_each_rar_entry(
+    path: &Path,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut VisitFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut archive = open_rar_for_processing(path, password)?;
+
+    while let Some(header) = archive
+        .read_header()
+        .map_err(|e| rar_error("Failed to read RAR entry", e))?
+    {
+        let item = rar_entry(header.entry(), encoding);
+        if item.dir {
+            archive = header
+                .skip()
+                .map_err(|e| rar_error("Failed to skip RAR entry", e))?;
+            continue;
+        }
+
+        match header.read() {
+            Ok((data, next)) => {
+                if !f(&item, Ok(&mut data.as_slice()))? {
+                    return Ok(());
+                }
+                archive = next;
+            },
+            Err(e) => {
+                f(&item, Err(extract_error(e, item.encrypted)))?;
+                return Err(ArchiveError::Other(format!(
+                    "Cannot continue after failing to extract {}",
+                    item.name
+                )));
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// 解压 RAR 中的单个文件到目标目录，`path` 可以是分卷中的任意一卷
+pub fn extract_rar_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    password: Option<&str>,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    extract_rar_file(
+        &volume::first_volume(path.as_ref())?,
+        entry_name,
+        password,
+        NameEncoding::Auto,
+        dest_dir,
+    )
+}
+
+/// 由 unrar 直接把条目解压到 `dest_dir`，不经过内存；分卷时 `path` 须为第一卷
+fn extract_rar_file(
+    path: &Path,
+    entry_name: &str,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    let mut archive = open_rar_for_processing(path, password)?;
+
+    while let Some(header) = archive
+        .read_header()
+        .map_err(|e| rar_error("Failed to read RAR entry", e))?
+    {
+        if rar_entry_name(header.entry(), encoding) != entry_name {
+            archive = header
+                .skip()
+                .map_err(|e| rar_error("Failed to skip RAR entry", e))?;
+            continue;
+        }
+        if header.entry().is_directory() {
+            return Err(ArchiveError::InvalidPath(entry_name.to_string()));
+        }
+
+        let dest_path = super::entry_output_path(entry_name, dest_dir)?;
+        let encrypted = header.entry().is_encrypted();
+        header
+            .extract_to(&dest_path)
+            .map_err(|e| extract_error(e, encrypted))?;
+        return Ok(dest_path);
+    }
+
+    Err(ArchiveError::EntryNotFound(entry_name.to_string()))
 }
 
 /// 检测 RAR 文件是否需要密码（仅头部加密时才需要密码才能列出文件）
 pub fn is_rar_password_protected<P: AsRef<Path>>(path: P) -> Result<bool, ArchiveError> {
-    let path = path.as_ref();
+    is_rar_file_encrypted(&volume::first_volume(path.as_ref())?)
+}
+
+fn is_rar_file_encrypted(path: &Path) -> Result<bool, ArchiveError> {
     let path_str = path.to_string_lossy().to_string();
 
     let archive = unrar::Archive::new(&path_str);
@@ -69,29 +276,172 @@ pub fn is_rar_password_protected<P: AsRef<Path>>(path: P) -> Result<bool, Archiv
         Ok(open_archive) => Ok(open_archive.has_encrypted_headers()),
     }
 }
-/// RAR header.file_time 是 DOS date/time 打包格式:
-///   高 16 位: 日期 (bit 15-9: 年-1980, bit 8-5: 月, bit 4-0: 日)
-///   低 16 位: 时间 (bit 15-11: 时, bit 10-5: 分, bit 4-0: 秒/2)
-fn rar_time_to_string(file_time: u32) -> String {
-    if file_time == 0 {
-        return "1970-01-01 00:00:00".to_string();
+
+/// 检查 RAR 的密码是否正确，`path` 可以是分卷中的任意一卷
+pub fn verify_rar_password<P: AsRef<Path>>(path: P, password: &str) -> Result<bool, ArchiveError> {
+    verify_rar_file(&volume::first_volume(path.as_ref())?, password)
+}
+
+/// 头部加密时列举即可确认；否则只测试解压最小的一个加密条目
+///
+/// RAR5 的文件头带有密码校验值，unrar 不用解压即可判断；RAR4 要解压后比对 CRC。
+fn verify_rar_file(path: &Path, password: &str) -> Result<bool, ArchiveError> {
+    let encoding = NameEncoding::Auto;
+    let entries = match list_rar_file(path, Some(password), encoding) {
+        Ok(entries) => entries,
+        Err(ArchiveError::PasswordRequired | ArchiveError::WrongPassword) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    let target = entries
+        .into_iter()
+        .filter(|entry| entry.encrypted && !entry.dir)
+        .min_by_key(|entry| entry.size);
+    let Some(target) = target else {
+        return Ok(true);
+    };
+
+    let mut archive = open_rar_for_processing(path, Some(password))?;
+    while let Some(header) = archive
+        .read_header()
+        .map_err(|e| rar_error("Failed to read RAR entry", e))?
+    {
+        if rar_entry_name(header.entry(), encoding) != target.name {
+            archive = header
+                .skip()
+                .map_err(|e| rar_error("Failed to skip RAR entry", e))?;
+            continue;
+        }
+        return match header.test().map_err(|e| extract_error(e, true)) {
+            Ok(_) => Ok(true),
+            Err(ArchiveError::PasswordRequired | ArchiveError::WrongPassword) => Ok(false),
+            Err(e) => Err(e),
+        };
     }
 
-    let date = (file_time >> 16) as u16;
-    let time = (file_time & 0xFFFF) as u16;
+    Err(ArchiveError::EntryNotFound(target.name))
+}
 
-    let year = ((date >> 9) + 1980) as i32;
-    let month = ((date >> 5) & 0x0F) as u32;
-    let day = (date & 0x1F) as u32;
-    let hour = (time >> 11) as u32;
-    let minute = ((time >> 5) & 0x3F) as u32;
-    let second = ((time & 0x1F) * 2) as u32;
+/// 缺少密码或密码错误
+fn is_password_error(e: &unrar::error::UnrarError) -> bool {
+    matches!(
+        e.code,
+        unrar::error::Code::MissingPassword | unrar::error::Code::BadPassword
+    )
+}
 
-    match NaiveDate::from_ymd_opt(year, month, day) {
-        Some(date) => match date.and_hms_opt(hour, minute, second) {
-            Some(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
-            None => "1970-01-01 00:00:00".to_string(),
-        },
-        None => "1970-01-01 00:00:00".to_string(),
+/// unrar 错误转换为 [`ArchiveError`]，缺少密码和密码错误单独区分
+fn rar_error(context: &str, e: unrar::error::UnrarError) -> ArchiveError {
+    match e.code {
+        unrar::error::Code::MissingPassword => ArchiveError::PasswordRequired,
+        unrar::error::Code::BadPassword => ArchiveError::WrongPassword,
+        _ => ArchiveError::Other(format!("{context}: {e}")),
+    }
+}
+
+/// 解压条目时的错误：RAR4 不校验密码，密码错误时解出的数据 CRC 不符
+fn extract_error(e: unrar::error::UnrarError, encrypted: bool) -> ArchiveError {
+    if encrypted && e.code == unrar::error::Code::BadData {
+        return ArchiveError::WrongPassword;
+    }
+    rar_error("Failed to extract RAR entry", e)
+}
+
+/// RAR 压缩方法：0x30 为仅存储，0x31~0x35 对应 m1（最快）~ m5（最好）
+fn rar_method_name(method: u32) -> String {
+    match method {
+        0x30 => "store".to_string(),
+        0x31..=0x35 => format!("rar-m{}", method - 0x30),
+        other => format!("rar-{other:#x}"),
+    }
+}
+
+/// RAR 读取器：unrar 只能从文件打开，嵌套的 RAR 会落地到临时文件；
+/// 分卷从第一卷打开，后续分卷由 unrar 自行读取
+pub struct RarReader {
+    input: FileInput,
+    password: Option<String>,
+    encoding: NameEncoding,
+}
+
+impl<'a> ArchiveReader<'a> for RarReader {
+    fn open(input: Input<'a>, password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self {
+            input: FileInput::new(input, "rar")?,
+            password: password.map(str::to_string),
+            encoding: NameEncoding::Auto,
+        })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Rar)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        list_rar_file(self.input.path(), self.password.as_deref(), self.encoding)
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        each_rar_entry(
+            self.input.path(),
+            self.password.as_deref(),
+            self.encoding,
+            f,
+        )
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        is_rar_file_encrypted(self.input.path())
+    }
+
+    fn password_protection(&mut self) -> Result<PasswordProtection, ArchiveError> {
+        if self.is_encrypted()? {
+            return Ok(PasswordProtection::Headers);
+        }
+        Ok(PasswordProtection::of_entries(&list_rar_file(
+            self.input.path(),
+            None,
+            self.encoding,
+        )?))
+    }
+
+    fn verify_password(&mut self, password: &str) -> Result<bool, ArchiveError> {
+        verify_rar_file(self.input.path(), password)
+    }
+
+    fn set_name_encoding(&mut self, encoding: NameEncoding) {
+        self.encoding = encoding;
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        with_rar_entry(
+            self.input.path(),
+            entry_name,
+            self.password.as_deref(),
+            self.encoding,
+            f,
+        )
+    }
+
+    fn extract_entry(
+        &mut self,
+        entry_name: &str,
+        dest_dir: &Path,
+    ) -> Result<PathBuf, ArchiveError> {
+        extract_rar_file(
+            self.input.path(),
+            entry_name,
+            self.password.as_deref(),
+            self.encoding,
+            dest_dir,
+        )
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        for_each_rar_entry(
+            self.input.path(),
+            self.password.as_deref(),
+            self.encoding,
+            f,
+        )
     }
 }
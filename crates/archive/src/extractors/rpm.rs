@@ -0,0 +1,479 @@
+//! RPM 软件包
+//!
+//! 文件依次为 96 字节的 lead、签名头部（按 8 字节对齐）、主头部和压缩后的 CPIO 载荷。
+//! 头部由索引项和数据区组成，名称、版本、依赖、脚本等都记录在主头部中；
+//! 载荷解压后按 CPIO 列举。
+
+use crate::package::{PackageInfo, PackageKind, PackageScript};
+use crate::reader::{ArchiveMetadata, ArchiveReader, EntryFn, Input, ListFn, StreamInput, VisitFn};
+use crate::{ArchiveError, ArchiveFormat, EntryTime, Extract};
+use std::io::{self, Read};
+
+const LEAD_SIZE: usize = 96;
+const LEAD_MAGIC: [u8; 4] = [0xED, 0xAB, 0xEE, 0xDB];
+const HEADER_MAGIC: [u8; 3] = [0x8E, 0xAD, 0xE8];
+
+/// 单个头部的索引项和数据区的上限，避免损坏的文件申请过多内存
+const MAX_HEADER_SIZE: usize = 64 * 1024 * 1024;
+
+// 头部数据类型
+const TYPE_INT8: u32 = 2;
+const TYPE_INT16: u32 = 3;
+const TYPE_INT32: u32 = 4;
+const TYPE_INT64: u32 = 5;
+const TYPE_STRING: u32 = 6;
+const TYPE_STRING_ARRAY: u32 = 8;
+const TYPE_I18NSTRING: u32 = 9;
+
+// 主头部中用到的标签
+const TAG_NAME: u32 = 1000;
+const TAG_VERSION: u32 = 1001;
+const TAG_RELEASE: u32 = 1002;
+const TAG_EPOCH: u32 = 1003;
+const TAG_SUMMARY: u32 = 1004;
+const TAG_DESCRIPTION: u32 = 1005;
+const TAG_BUILDTIME: u32 = 1006;
+const TAG_BUILDHOST: u32 = 1007;
+const TAG_SIZE: u32 = 1009;
+const TAG_DISTRIBUTION: u32 = 1010;
+const TAG_VENDOR: u32 = 1011;
+const TAG_LICENSE: u32 = 1014;
+const TAG_PACKAGER: u32 = 1015;
+const TAG_GROUP: u32 = 1016;
+const TAG_URL: u32 = 1020;
+const TAG_OS: u32 = 1021;
+const TAG_ARCH: u32 = 1022;
+const TAG_PREIN: u32 = 1023;
+const TAG_POSTIN: u32 = 1024;
+const TAG_PREUN: u32 = 1025;
+const TAG_POSTUN: u32 = 1026;
+const TAG_SOURCERPM: u32 = 1044;
+const TAG_REQUIREFLAGS: u32 = 1048;
+const TAG_REQUIRENAME: u32 = 1049;
+const TAG_REQUIREVERSION: u32 = 1050;
+const TAG_PREINPROG: u32 = 1085;
+const TAG_POSTINPROG: u32 = 1086;
+const TAG_PREUNPROG: u32 = 1087;
+const TAG_POSTUNPROG: u32 = 1088;
+const TAG_PAYLOADFORMAT: u32 = 1124;
+const TAG_PAYLOADCOMPRESSOR: u32 = 1125;
+
+// 依赖的版本比较标记
+const SENSE_LESS: u32 = 0x02;
+const SENSE_GREATER: u32 = 0x04;
+const SENSE_EQUAL: u32 = 0x08;
+
+/// 头部索引项
+struct IndexEntry {
+    tag: u32,
+    kind: u32,
+    offset: usize,
+    count: usize,
+}
+
+impl IndexEntry {
+    /// 整数类型每个值的字节数，其他类型为 `None`
+    fn int_width(&self) -> Option<usize> {
+        match self.kind {
+            TYPE_INT8 => Some(1),
+            TYPE_INT16 => Some(2),
+            TYPE_INT32 => Some(4),
+            TYPE_INT64 => Some(8),
+            _ => None,
+        }
+    }
+}
+
+/// 已读入内存的头部
+struct RpmHeader {
+    entries: Vec<IndexEntry>,
+    data: Vec<u8>,
+}
+
+impl RpmHeader {
+    /// 读取一个头部，返回头部及其占用的字节数
+    fn read<R: Read>(reader: &mut R) -> Result<(Self, usize), ArchiveError> {
+        let mut intro = [0u8; 16];
+        reader.read_exact(&mut intro)?;
+        if intro[..3] != HEADER_MAGIC {
+            return Err(malformed());
+        }
+        let count = be32(&intro[8..]) as usize;
+        let size = be32(&intro[12..]) as usize;
+        if count.saturating_mul(16).saturating_add(size) > MAX_HEADER_SIZE {
+            return Err(malformed());
+        }
+
+        let mut index = vec![0u8; count * 16];
+        reader.read_exact(&mut index)?;
+        let mut data = vec![0u8; size];
+        reader.read_exact(&mut data)?;
+
+        let entries: Vec<IndexEntry> = index
+            .chunks_exact(16)
+            .map(|entry| IndexEntry {
+                tag: be32(entry),
+                kind: be32(&entry[4..]),
+                offset: be32(&entry[8..]) as usize,
+                count: be32(&entry[12..]) as usize,
+            })
+            .collect();
+        // 索引项指向的数据须落在数据区内
+        for entry in &entries {
+            let len = entry.int_width().unwrap_or(0).saturating_mul(entry.count);
+            if entry.offset.saturating_add(len) > size {
+                return Err(malformed());
+            }
+        }
+        Ok((Self { entries, data }, 16 + count * 16 + size))
+    }
+
+    fn entry(&self, tag: u32) -> Option<&IndexEntry> {
+        self.entries.iter().find(|entry| entry.tag == tag)
+    }
+
+    /// 字符串类标签的全部值；I18NSTRING 的第一个值为默认语言
+    fn strings(&self, tag: u32) -> Vec<String> {
+        let Some(entry) = self.entry(tag) else {
+            return Vec::new();
+        };
+        if !matches!(
+            entry.kind,
+            TYPE_STRING | TYPE_STRING_ARRAY | TYPE_I18NSTRING
+        ) {
+            return Vec::new();
+        }
+        let Some(data) = self.data.get(entry.offset..) else {
+            return Vec::new();
+        };
+        data.split(|b| *b == 0)
+            .take(entry.count.max(1))
+            .map(|raw| String::from_utf8_lossy(raw).into_owned())
+            .collect()
+    }
+
+    fn string(&self, tag: u32) -> Option<String> {
+        self.strings(tag)
+            .into_iter()
+            .next()
+            .filter(|value| !value.is_empty())
+    }
+
+    /// 整数类标签的全部值
+    fn ints(&self, tag: u32) -> Vec<u64> {
+        let Some(entry) = self.entry(tag) else {
+            return Vec::new();
+        };
+        let Some(width) = entry.int_width() else {
+            return Vec::new();
+        };
+        let end = entry.offset.saturating_add(width * entry.count);
+        let Some(data) = self.data.get(entry.offset..end) else {
+            return Vec::new();
+        };
+        data.chunks_exact(width)
+            .map(|value| value.iter().fold(0u64, |acc, b| (acc << 8) | u64::from(*b)))
+            .collect()
+    }
+
+    fn int(&self, tag: u32) -> Option<u64> {
+        self.ints(tag).into_iter().next()
+    }
+}
+
+fn be32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn malformed() -> ArchiveError {
+    ArchiveError::Other("Malformed RPM header".into())
+}
+
+/// 读取 lead、签名头部和主头部，之后 `reader` 位于载荷开头
+fn read_headers<R: Read>(reader: &mut R) -> Result<RpmHeader, ArchiveError> {
+    let mut lead = [0u8; LEAD_SIZE];
+    reader.read_exact(&mut lead)?;
+    if lead[..4] != LEAD_MAGIC {
+        return Err(ArchiveError::Other("Not an RPM package".into()));
+    }
+
+    // 签名头部之后补齐到 8 字节边界
+    let (_, signature_size) = RpmHeader::read(reader)?;
+    let padding = (8 - signature_size % 8) % 8;
+    io::copy(&mut reader.take(padding as u64), &mut io::sink())?;
+
+    let (header, _) = RpmHeader::read(reader)?;
+    Ok(header)
+}
+
+/// 解压载荷，返回 CPIO 数据流
+fn payload<'a, R: Read + 'a>(
+    header: &RpmHeader,
+    reader: R,
+) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+    if let Some(format) = header.string(TAG_PAYLOADFORMAT) {
+        if format != "cpio" {
+            return Err(ArchiveError::UnsupportedFormat(format!(
+                "rpm payload {format}"
+            )));
+        }
+    }
+    // 没有记录压缩方式的旧 RPM 使用 gzip
+    let compressor = header.string(TAG_PAYLOADCOMPRESSOR);
+    let format = match compressor.as_deref().unwrap_or("gzip") {
+        "gzip" => ArchiveFormat::TarGz,
+        "bzip2" => ArchiveFormat::TarBz2,
+        "xz" => ArchiveFormat::TarXz,
+        "zstd" => ArchiveFormat::TarZst,
+        "lzma" => return super::tar::lzma_decoder(reader),
+        "identity" => ArchiveFormat::Tar,
+        other => {
+            return Err(ArchiveError::UnsupportedFormat(format!(
+                "rpm payload {other}"
+            )))
+        },
+    };
+    super::tar::tar_stream(format, reader)
+}
+
+/// 把主头部整理为软件包信息
+fn rpm_package_info(header: &RpmHeader) -> PackageInfo {
+    let mut info = PackageInfo::new(PackageKind::Rpm, "header", String::new());
+
+    let name = header.string(TAG_NAME);
+    let version = header.string(TAG_VERSION);
+    let release = header.string(TAG_RELEASE);
+    let epoch = header.int(TAG_EPOCH);
+    info.version = version.as_ref().map(|version| {
+        let mut full = match epoch {
+            Some(epoch) => format!("{epoch}:{version}"),
+            None => version.clone(),
+        };
+        if let Some(release) = &release {
+            full.push('-');
+            full.push_str(release);
+        }
+        full
+    });
+
+    let build_time = header
+        .int(TAG_BUILDTIME)
+        .and_then(|secs| EntryTime::from_unix(secs as i64))
+        .map(|time| time.to_string());
+    let fields = [
+        ("Name", name.clone()),
+        ("Epoch", epoch.map(|epoch| epoch.to_string())),
+        ("Version", version),
+        ("Release", release),
+        ("Architecture", header.string(TAG_ARCH)),
+        ("OS", header.string(TAG_OS)),
+        ("Summary", header.string(TAG_SUMMARY)),
+        ("Group", header.string(TAG_GROUP)),
+        ("License", header.string(TAG_LICENSE)),
+        ("URL", header.string(TAG_URL)),
+        ("Vendor", header.string(TAG_VENDOR)),
+        ("Packager", header.string(TAG_PACKAGER)),
+        ("Distribution", header.string(TAG_DISTRIBUTION)),
+        ("Build Host", header.string(TAG_BUILDHOST)),
+        ("Build Time", build_time),
+        ("Source RPM", header.string(TAG_SOURCERPM)),
+        ("Size", header.int(TAG_SIZE).map(|size| size.to_string())),
+        ("Description", header.string(TAG_DESCRIPTION)),
+    ];
+    for (key, value) in fields {
+        if let Some(value) = value {
+            info.push_field(key, &value);
+        }
+    }
+
+    let names = header.strings(TAG_REQUIRENAME);
+    let versions = header.strings(TAG_REQUIREVERSION);
+    let flags = header.ints(TAG_REQUIREFLAGS);
+    for (i, name) in names.into_iter().enumerate() {
+        let version = versions.get(i).filter(|version| !version.is_empty());
+        let flag = flags.get(i).copied().unwrap_or(0) as u32;
+        let operator = match flag & (SENSE_LESS | SENSE_GREATER | SENSE_EQUAL) {
+            SENSE_LESS => "<",
+            SENSE_GREATER => ">",
+            SENSE_EQUAL => "=",
+            f if f == SENSE_LESS | SENSE_EQUAL => "<=",
+            f if f == SENSE_GREATER | SENSE_EQUAL => ">=",
+            _ => "",
+        };
+        info.dependencies.push(match version {
+            Some(version) if !operator.is_empty() => format!("{name} {operator} {version}"),
+            _ => name,
+        });
+    }
+
+    // 脚本解释器（如 /bin/sh、<lua>）写成 shebang 放在脚本开头
+    for (name, script, program) in [
+        ("%pre", TAG_PREIN, TAG_PREINPROG),
+        ("%post", TAG_POSTIN, TAG_POSTINPROG),
+        ("%preun", TAG_PREUN, TAG_PREUNPROG),
+        ("%postun", TAG_POSTUN, TAG_POSTUNPROG),
+    ] {
+        let program = header.strings(program).join(" ");
+        let content = match header.string(script) {
+            Some(script) if program.is_empty() || script.starts_with("#!") => script,
+            Some(script) => format!("#!{program}\n{script}"),
+            None if !program.is_empty() => format!("#!{program}"),
+            None => continue,
+        };
+        info.scripts
+            .push(PackageScript { name: name.to_string(), content });
+    }
+
+    info.manifest_text = info
+        .fields
+        .iter()
+        .map(|field| format!("{}: {}\n", field.key, field.value))
+        .chain(
+            info.dependencies
+                .iter()
+                .map(|dependency| format!("Requires: {dependency}\n")),
+        )
+        .collect();
+    info.name = name;
+    info.description = info.field(&["Summary"]);
+    info.author = info.field(&["Packager", "Vendor"]);
+    info.architecture = info.field(&["Architecture"]);
+    info
+}
+
+/// RPM 读取器，条目为 CPIO 载荷中的文件
+pub struct RpmReader<'a> {
+    input: StreamInput<'a>,
+}
+
+impl<'a> RpmReader<'a> {
+    /// 跳过头部，返回解压后的 CPIO 载荷
+    fn payload(&mut self) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+        let mut reader = self.input.take()?;
+        let header = read_headers(&mut reader)?;
+        payload(&header, reader)
+    }
+}
+
+impl<'a> ArchiveReader<'a> for RpmReader<'a> {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self { input: StreamInput::new(input) })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Rpm)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        super::cpio::list_cpio_entries_from(self.payload()?)
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        super::cpio::each_cpio_entry(self.payload()?, f)
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        super::cpio::with_cpio_entry(self.payload()?, entry_name, f)
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        super::cpio::for_each_cpio_entry(self.payload()?, f)
+    }
+
+    fn package_info(&mut self) -> Result<Option<PackageInfo>, ArchiveError> {
+        let header = read_headers(&mut self.input.take()?)?;
+        Ok(Some(rpm_package_info(&header)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 按索引项拼出头部：`(标签, 类型, 个数, 数据)`，数据依次放入数据区
+    fn header(items: &[(u32, u32, u32, &[u8])]) -> Vec<u8> {
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for (tag, kind, count, value) in items {
+            for field in [*tag, *kind, data.len() as u32, *count] {
+                index.extend_from_slice(&field.to_be_bytes());
+            }
+            data.extend_from_slice(value);
+        }
+        let mut bytes = vec![0x8E, 0xAD, 0xE8, 0x01, 0, 0, 0, 0];
+        bytes.extend_from_slice(&(items.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        bytes.extend(index);
+        bytes.extend(data);
+        bytes
+    }
+
+    #[test]
+    fn reads_package_fields() {
+        let mut rpm = vec![0; LEAD_SIZE];
+        rpm[..4].copy_from_slice(&LEAD_MAGIC);
+        // 签名头部 16 + 16 + 5 字节，之后补 3 字节对齐
+        rpm.extend(header(&[(62, TYPE_INT8, 5, b"sig!!")]));
+        rpm.extend([0; 3]);
+        rpm.extend(header(&[
+            (TAG_NAME, TYPE_STRING, 1, b"demo\0"),
+            (TAG_VERSION, TYPE_STRING, 1, b"1.2\0"),
+            (TAG_RELEASE, TYPE_STRING, 1, b"3.el9\0"),
+            (TAG_ARCH, TYPE_STRING, 1, b"x86_64\0"),
+            (TAG_EPOCH, TYPE_INT32, 1, &2u32.to_be_bytes()),
+            (TAG_REQUIRENAME, TYPE_STRING_ARRAY, 2, b"libc\0bash\0"),
+            (
+                TAG_REQUIREFLAGS,
+                TYPE_INT32,
+                2,
+                &[0, 0, 0, 0x0C, 0, 0, 0, 0],
+            ),
+            (TAG_REQUIREVERSION, TYPE_STRING_ARRAY, 2, b"2.34\0\0"),
+        ]));
+        rpm.extend(b"payload");
+
+        let mut reader = rpm.as_slice();
+        let info = rpm_package_info(&read_headers(&mut reader).unwrap());
+        assert_eq!(reader, b"payload");
+        assert_eq!(info.name.as_deref(), Some("demo"));
+        assert_eq!(info.version.as_deref(), Some("2:1.2-3.el9"));
+        assert_eq!(info.architecture.as_deref(), Some("x86_64"));
+        assert_eq!(info.dependencies, ["libc >= 2.34", "bash"]);
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let bytes = header(&[(TAG_NAME, TYPE_STRING, 1, b"demo\0")]);
+        for len in [8, 20, bytes.len() - 1] {
+            assert!(RpmHeader::read(&mut &bytes[..len]).is_err());
+        }
+
+        let mut bytes = bytes;
+        bytes[0] = 0;
+        assert!(RpmHeader::read(&mut bytes.as_slice()).is_err());
+    }
+
+    #[test]
+    fn rejects_out_of_range_index() {
+        let mut bytes = header(&[(TAG_SIZE, TYPE_INT32, 1, &[0, 0, 0, 1])]);
+        assert!(RpmHeader::read(&mut bytes.as_slice()).is_ok());
+
+        // 个数超出数据区
+        bytes[28..32].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(RpmHeader::read(&mut bytes.as_slice()).is_err());
+
+        // 偏移超出数据区
+        bytes[28..32].copy_from_slice(&1u32.to_be_bytes());
+        bytes[24..28].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(RpmHeader::read(&mut bytes.as_slice()).is_err());
+
+        // 索引项个数和数据区大小超出上限时不申请内存
+        let mut bytes = header(&[]);
+        bytes[8..12].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(RpmHeader::read(&mut bytes.as_slice()).is_err());
+    }
+}
@@ -1,73 +1,401 @@
-use crate::{ArchiveError, Extract};
-use sevenz_rust::Password;
-use std::{fs::File, io::BufReader, path::Path};
+use crate::cancel::drain;
+use crate::encoding::{NameDecoder, NameEncoding};
+use crate::reader::{
+    ArchiveMetadata, ArchiveReader, EntryFn, Input, PasswordProtection, SeekInput, VisitFn,
+};
+use crate::{volume, ArchiveError, ArchiveFormat, CancelToken, EntryTime, Extract};
+use sevenz_rust::{BlockDecoder, Password, SevenZMethod};
+use std::{
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
 
-/// Windows FILETIME: 100-nanosecond intervals since 1601-01-01 00:00:00 UTC
-const FILETIME_UNIX_EPOCH_DIFF: u64 = 116_444_736_000_000_000;
-const FILETIMES_PER_SEC: u64 = 10_000_000;
+/// 列举 7Z 文件条目，`path` 可以是 `.7z.001` 等分卷中的任意一卷
+pub fn list_7z_entries<P: AsRef<Path>>(
+    path: P,
+    password: Option<&str>,
+) -> Result<Vec<Extract>, ArchiveError> {
+    SevenZReader::open(volume::open_input(path.as_ref())?, password)?.list()
+}
 
-fn filetime_to_string(raw: u64) -> String {
-    if raw == 0 || raw < FILETIME_UNIX_EPOCH_DIFF {
-        return "1970-01-01 00:00:00".to_string();
-    }
+/// 从任意可寻址的数据源列举 7Z 条目（用于嵌套在其他归档中的 7Z）
+pub(crate) fn list_7z_entries_from<R: Read + Seek>(
+    mut reader: R,
+    len: u64,
+    password: Option<&str>,
+    encoding: NameEncoding,
+) -> Result<Vec<Extract>, ArchiveError> {
+    let mut archive = read_archive(&mut reader, len, password)?;
+    decode_names(&mut archive, encoding);
+    Ok((0..archive.files.len())
+        .map(|index| sevenz_entry(&archive, index))
+        .collect())
+}
 
-    let secs = (raw - FILETIME_UNIX_EPOCH_DIFF) / FILETIMES_PER_SEC;
-    let dt = chrono::DateTime::from_timestamp(secs as i64, 0);
-    match dt {
-        Some(dt) => dt.format("%Y-%m-%d %H:%M:%S").to_string(),
-        None => "1970-01-01 00:00:00".to_string(),
+/// 头部中记录的第 `file_index` 个条目的信息
+fn sevenz_entry(archive: &sevenz_rust::Archive, file_index: usize) -> Extract {
+    let entry = &archive.files[file_index];
+    let size = if entry.has_stream { entry.size } else { 0 };
+    let last_modified = if entry.has_last_modified_date {
+        EntryTime::from_filetime(entry.last_modified_date.to_raw())
+    } else {
+        None
+    };
+
+    let mut item = Extract::new(entry.name.clone(), size, last_modified, entry.is_directory);
+    if entry.has_crc {
+        item.crc32 = Some(entry.crc as u32);
+    }
+    // p7zip 在属性高 16 位保存 Unix 权限位，并设置 0x8000 标记
+    if entry.has_windows_attributes && entry.windows_attributes & 0x8000 != 0 {
+        item.set_mode(entry.windows_attributes >> 16);
+    }
+    if let Some(folder_index) = archive.stream_map.file_folder_index[file_index] {
+        let (method, encrypted) = folder_method(archive, folder_index);
+        item.compression_method = Some(method);
+        item.encrypted = encrypted;
+        item.encryption_method = encrypted.then(|| "aes-256".to_string());
+        item.compressed_size = folder_packed_size(archive, folder_index);
     }
+    item
 }
 
-/// 列举 7Z 文件条目
-pub fn list_7z_entries<P: AsRef<Path>>(
-    path: P,
-    password: Option<&str>,
-) -> Result<Vec<Extract>, ArchiveError> {
-    let file = File::open(path)?;
-    let len = file.metadata()?.len();
-    let mut reader = BufReader::new(file);
-    let password_bytes = password
+/// 7Z 密码的字节形式（UTF-16LE），未提供时为空
+fn password_bytes(password: Option<&str>) -> Vec<u8> {
+    password
         .map(|p| Password::from(p).as_slice().to_vec())
-        .unwrap_or_default();
-    let archive = match sevenz_rust::Archive::read(&mut reader, len, &password_bytes) {
-        Ok(archive) => archive,
-        Err(sevenz_rust::Error::PasswordRequired)
-        | Err(sevenz_rust::Error::MaybeBadPassword(_)) => {
-            return Err(ArchiveError::Other(
-                "Password required to decrypt 7z archive".into(),
-            ));
-        },
-        Err(e) => {
-            return Err(ArchiveError::Other(format!(
-                "Failed to read 7z archive: {}",
-                e
-            )))
+        .unwrap_or_default()
+}
+
+/// 读取 7Z 头部
+///
+/// 头部加密时，未提供密码报 [`ArchiveError::PasswordRequired`]，
+/// 提供的密码无法解开头部报 [`ArchiveError::WrongPassword`]。
+fn read_archive<R: Read + Seek>(
+    reader: &mut R,
+    len: u64,
+    password: Option<&str>,
+) -> Result<sevenz_rust::Archive, ArchiveError> {
+    sevenz_rust::Archive::read(reader, len, &password_bytes(password)).map_err(|e| match e {
+        sevenz_rust::Error::PasswordRequired | sevenz_rust::Error::MaybeBadPassword(_)
+            if password.map_or(true, str::is_empty) =>
+        {
+            ArchiveError::PasswordRequired
         },
+        e => e.into(),
+    })
+}
+
+/// 修正按单字节保存的旧编码条目名，之后按条目名查找和比较都使用修正后的名称
+fn decode_names(archive: &mut sevenz_rust::Archive, encoding: NameEncoding) {
+    let decoder = NameDecoder::for_unicode(encoding, archive.files.iter().map(|f| f.name.as_str()));
+    for file in &mut archive.files {
+        file.name = decoder.repair(std::mem::take(&mut file.name));
+    }
+}
+
+/// 块的压缩方法（多个编码器以 `+` 连接，如 `bcj_x86+lzma`）以及是否加密
+fn folder_method(archive: &sevenz_rust::Archive, folder_index: usize) -> (String, bool) {
+    let mut encrypted = false;
+    let methods: Vec<String> = archive.folders[folder_index]
+        .coders
+        .iter()
+        .filter_map(|coder| {
+            let method = SevenZMethod::by_id(coder.decompression_method_id());
+            if method == Some(SevenZMethod::AES256SHA256) {
+                encrypted = true;
+                return None;
+            }
+            Some(method.map_or_else(|| "unknown".to_string(), |m| m.name().to_lowercase()))
+        })
+        .collect();
+    let method = if methods.is_empty() {
+        "copy".to_string()
+    } else {
+        methods.join("+")
     };
+    (method, encrypted)
+}
+
+/// 块的压缩后大小
+fn folder_packed_size_total(archive: &sevenz_rust::Archive, folder_index: usize) -> u64 {
+    let first = archive.stream_map.folder_first_pack_stream_index[folder_index];
+    let count = archive.folders[folder_index].packed_streams.len();
+    archive.pack_sizes[first..first + count].iter().sum()
+}
+
+/// 只包含一个文件的块，其压缩后大小即为该文件的压缩后大小；固实块中的文件无法单独计算
+fn folder_packed_size(archive: &sevenz_rust::Archive, folder_index: usize) -> Option<u64> {
+    let folder = &archive.folders[folder_index];
+    if folder.num_unpack_sub_streams != 1 {
+        return None;
+    }
+    Some(folder_packed_size_total(archive, folder_index))
+}
+
+/// 打开 7Z 中的指定文件，并把解压后的数据流交给 `f` 处理
+///
+/// 只解码目标文件所在的块；固实块内排在前面的文件仍需解码后丢弃，丢弃时检查 `cancel`。
+pub(crate) fn with_7z_entry<R: Read + Seek, T>(
+    mut reader: R,
+    len: u64,
+    entry_name: &str,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    cancel: &CancelToken,
+    f: &mut dyn FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut archive = read_archive(&mut reader, len, password)?;
+    decode_names(&mut archive, encoding);
 
-    let entries = archive
+    let file_index = archive
         .files
         .iter()
-        .map(|entry| {
-            let name = entry.name.clone();
-            let size = if entry.has_stream { entry.size } else { 0 };
-            let is_dir = entry.is_directory;
-            let last_modified = if entry.has_last_modified_date {
-                filetime_to_string(entry.last_modified_date.to_raw())
+        .position(|entry| entry.name == entry_name)
+        .ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))?;
+    if archive.files[file_index].is_directory {
+        return Err(ArchiveError::InvalidPath(entry_name.to_string()));
+    }
+
+    let folder_index = match archive.stream_map.file_folder_index[file_index] {
+        Some(index) => index,
+        // 没有数据流的空文件
+        None => return f(&mut std::io::empty()),
+    };
+
+    let (_, encrypted) = folder_method(&archive, folder_index);
+    if encrypted && password.map_or(true, str::is_empty) {
+        return Err(ArchiveError::PasswordRequired);
+    }
+
+    let password_bytes = password_bytes(password);
+    let mut result = None;
+    let mut data_failed = false;
+    let decoded = BlockDecoder::new(folder_index, &archive, &password_bytes, &mut reader)
+        .for_each_entries(&mut |entry, data| {
+            if entry.name != entry_name {
+                // 解码错误交给下面统一判断，取消直接作为结果返回
+                match drain(data, cancel) {
+                    Ok(_) => return Ok(true),
+                    Err(ArchiveError::IoError(e)) => return Err(e.into()),
+                    Err(e) => {
+                        result = Some(Err(e));
+                        return Ok(false);
+                    },
+                }
+            }
+            let mut data = DecodeGuard { inner: data, failed: false };
+            result = Some(f(&mut data));
+            data_failed = data.failed;
+            Ok(false)
+        })
+        .map_err(|e| decode_error(e, encrypted));
+
+    // 用错误的密码解出的是乱码，解压或校验失败都说明密码不对；`f` 自身的错误原样返回
+    match result {
+        Some(Err(_)) if encrypted && data_failed => Err(ArchiveError::WrongPassword),
+        Some(result) => result,
+        None => match decoded {
+            Err(e) => Err(e),
+            Ok(_) => Err(ArchiveError::EntryNotFound(entry_name.to_string())),
+        },
+    }
+}
+
+/// 转换解码块时的错误
+///
+/// 提供了密码时 sevenz-rust 把读取数据时的所有 I/O 错误包装为 `MaybeBadPassword`，
+/// 这里只把加密块的解码失败和 CRC 不符视为密码错误；不支持的压缩方法、普通 I/O 错误原样返回。
+fn decode_error(e: sevenz_rust::Error, encrypted: bool) -> ArchiveError {
+    match e {
+        sevenz_rust::Error::MaybeBadPassword(e) | sevenz_rust::Error::Io(e, _) => {
+            if encrypted && is_corrupt_data(&e) {
+                ArchiveError::WrongPassword
             } else {
-                "1970-01-01 00:00:00".to_string()
-            };
-            Extract::new(name, size, last_modified, is_dir)
+                ArchiveError::IoError(e)
+            }
+        },
+        sevenz_rust::Error::ChecksumVerificationFailed if encrypted => ArchiveError::WrongPassword,
+        e => e.into(),
+    }
+}
+
+/// 记录读取条目数据时是否出现解码错误，用于区分解码失败与 `f` 自身（如写出文件）的错误
+struct DecodeGuard<'a> {
+    inner: &'a mut dyn Read,
+    failed: bool,
+}
+
+impl Read for DecodeGuard<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf).inspect_err(|e| {
+            self.failed |= is_corrupt_data(e);
         })
-        .collect();
+    }
+}
 
-    Ok(entries)
+/// 解码器因数据损坏而失败，或解出的数据 CRC 不符（而不是读写文件、不支持的方法等错误）
+fn is_corrupt_data(e: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+
+    // sevenz-rust 以 `Other` 包装 CRC 校验失败，LZMA 解码器以 `InvalidInput` 报告数据损坏
+    let checksum = e
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<sevenz_rust::Error>())
+        .is_some_and(|inner| matches!(inner, sevenz_rust::Error::ChecksumVerificationFailed));
+    checksum
+        || matches!(
+            e.kind(),
+            ErrorKind::InvalidData | ErrorKind::InvalidInput | ErrorKind::UnexpectedEof
+        )
 }
 
-/// 检测 7Z 文件是否需要密码
+/// 依次解码每个块，数据流读取时由 sevenz-rust 校验 CRC32；没有数据的空文件以空数据流访问
+///
+/// 块无法解码（如缺少密码、不支持的压缩方法）时，块内每个文件都以该错误访问。
+pub(crate) fn for_each_7z_entry<R: Read + Seek>(
+    mut reader: R,
+    len: u64,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut VisitFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut archive = read_archive(&mut reader, len, password)?;
+    decode_names(&mut archive, encoding);
+    let password_bytes = password_bytes(password);
+
+    for (file_index, file) in archive.files.iter().enumerate() {
+        if !file.is_directory
+            && archive.stream_map.file_folder_index[file_index].is_none()
+            && !f(
+                &sevenz_entry(&archive, file_index),
+                Ok(&mut std::io::empty()),
+            )?
+        {
+            return Ok(());
+        }
+    }
+
+    for folder_index in 0..archive.folders.len() {
+        let first = archive.stream_map.folder_first_file_index[folder_index];
+        let files = first..first + archive.folders[folder_index].num_unpack_sub_streams;
+        let (_, encrypted) = folder_method(&archive, folder_index);
+
+        let mut next = files.start;
+        let mut stopped = false;
+        let mut visit_error = None;
+        let decoded = if encrypted && password.map_or(true, str::is_empty) {
+            Err(ArchiveError::PasswordRequired)
+        } else {
+            BlockDecoder::new(folder_index, &archive, &password_bytes, &mut reader)
+                .for_each_entries(&mut |_, data| {
+                    let entry = sevenz_entry(&archive, next);
+                    next += 1;
+                    match f(&entry, Ok(data)) {
+                        Ok(keep_going) => {
+                            stopped = !keep_going;
+                            Ok(keep_going)
+                        },
+                        Err(e) => {
+                            visit_error = Some(e);
+                            Ok(false)
+                        },
+                    }
+                })
+                .map_err(|e| decode_error(e, encrypted))
+        };
+        if let Some(e) = visit_error {
+            return Err(e);
+        }
+        if stopped {
+            return Ok(());
+        }
+        // 块解码失败时，尚未访问的文件都视为失败
+        if let Err(e) = decoded {
+            let message = e.to_string();
+            let mut error = Some(e);
+            for file_index in next..files.end {
+                let e = error
+                    .take()
+                    .unwrap_or_else(|| ArchiveError::Other(message.clone()));
+                if !f(&sevenz_entry(&archive, file_index), Err(e))? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 解压 7Z 中的单个文件到目标目录，`path` 可以是分卷中的任意一卷
+pub fn extract_7z_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    password: Option<&str>,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    let input = SeekInput::new(volume::open_input(path.as_ref())?)?;
+    let (reader, len) = input.reader()?;
+    with_7z_entry(
+        reader,
+        len,
+        entry_name,
+        password,
+        NameEncoding::Auto,
+        &CancelToken::new(),
+        &mut |data| super::write_entry_to_dir(data, entry_name, dest_dir),
+    )
+}
+
+/// 检测 7Z 文件是否需要密码，`path` 可以是分卷中的任意一卷
 pub fn is_7z_password_protected<P: AsRef<Path>>(path: P) -> Result<bool, ArchiveError> {
-    match sevenz_rust::Archive::open(path.as_ref()) {
+    SevenZReader::open(volume::open_input(path.as_ref())?, None)?.is_encrypted()
+}
+
+/// 检查 7Z 的密码是否正确，`path` 可以是分卷中的任意一卷
+pub fn verify_7z_password<P: AsRef<Path>>(path: P, password: &str) -> Result<bool, ArchiveError> {
+    SevenZReader::open(volume::open_input(path.as_ref())?, None)?.verify_password(password)
+}
+
+/// 头部加密时解开头部即可确认；否则只解码压缩后最小的加密块中的第一个文件，由 CRC 确认
+fn verify_7z_password_from<R: Read + Seek>(
+    mut reader: R,
+    len: u64,
+    password: &str,
+) -> Result<bool, ArchiveError> {
+    let archive = match read_archive(&mut reader, len, Some(password)) {
+        Ok(archive) => archive,
+        Err(ArchiveError::PasswordRequired | ArchiveError::WrongPassword) => return Ok(false),
+        Err(e) => return Err(e),
+    };
+
+    let folder_index = (0..archive.folders.len())
+        .filter(|&index| folder_method(&archive, index).1)
+        .min_by_key(|&index| folder_packed_size_total(&archive, index));
+    let Some(folder_index) = folder_index else {
+        return Ok(true);
+    };
+
+    let password_bytes = password_bytes(Some(password));
+    let decoded = BlockDecoder::new(folder_index, &archive, &password_bytes, &mut reader)
+        .for_each_entries(&mut |_, data| {
+            std::io::copy(data, &mut std::io::sink())?;
+            Ok(false)
+        })
+        .map_err(|e| decode_error(e, true));
+    // 只有解码失败、CRC 不符说明密码不对，不支持的压缩方法、头部损坏等错误原样返回
+    match decoded {
+        Ok(_) => Ok(true),
+        Err(ArchiveError::PasswordRequired | ArchiveError::WrongPassword) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+fn is_7z_password_protected_from<R: Read + Seek>(
+    mut reader: R,
+    len: u64,
+) -> Result<bool, ArchiveError> {
+    match sevenz_rust::Archive::read(&mut reader, len, &[]) {
         Ok(_) => Ok(false),
         Err(sevenz_rust::Error::PasswordRequired)
         | Err(sevenz_rust::Error::MaybeBadPassword(_)) => Ok(true),
@@ -80,3 +408,167 @@ pub fn is_7z_password_protected<P: AsRef<Path>>(path: P) -> Result<bool, Archive
         },
     }
 }
+
+/// 7Z 读取器，嵌套的 7Z 会读入内存
+pub struct SevenZReader {
+    input: SeekInput,
+    password: Option<String>,
+    encoding: NameEncoding,
+    cancel: CancelToken,
+}
+
+impl<'a> ArchiveReader<'a> for SevenZReader {
+    fn open(input: Input<'a>, password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self {
+            input: SeekInput::new(input)?,
+            password: password.map(str::to_string),
+            encoding: NameEncoding::Auto,
+            cancel: CancelToken::new(),
+        })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::SevenZ)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        let (reader, len) = self.input.reader()?;
+        list_7z_entries_from(reader, len, self.password.as_deref(), self.encoding)
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        let (reader, len) = self.input.reader()?;
+        is_7z_password_protected_from(reader, len)
+    }
+
+    fn password_protection(&mut self) -> Result<PasswordProtection, ArchiveError> {
+        if self.is_encrypted()? {
+            return Ok(PasswordProtection::Headers);
+        }
+        let (reader, len) = self.input.reader()?;
+        Ok(PasswordProtection::of_entries(&list_7z_entries_from(
+            reader,
+            len,
+            None,
+            self.encoding,
+        )?))
+    }
+
+    fn verify_password(&mut self, password: &str) -> Result<bool, ArchiveError> {
+        let (reader, len) = self.input.reader()?;
+        verify_7z_password_from(reader, len, password)
+    }
+
+    fn set_name_encoding(&mut self, encoding: NameEncoding) {
+        self.encoding = encoding;
+    }
+
+    fn set_cancel(&mut self, cancel: &CancelToken) {
+        self.cancel = cancel.clone();
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        let (reader, len) = self.input.reader()?;
+        with_7z_entry(
+            reader,
+            len,
+            entry_name,
+            self.password.as_deref(),
+            self.encoding,
+            &self.cancel,
+            f,
+        )
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        let (reader, len) = self.input.reader()?;
+        for_each_7z_entry(reader, len, self.password.as_deref(), self.encoding, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sevenz_rust::{
+        AesEncoderOptions, SevenZArchiveEntry, SevenZMethodConfiguration, SevenZWriter,
+    };
+    use std::io::Cursor;
+
+    /// 用 AES + LZMA 加密单个文件，头部不加密
+    fn encrypted_7z(password: &str) -> Vec<u8> {
+        let mut writer = SevenZWriter::new(Cursor::new(Vec::new())).unwrap();
+        writer.set_encrypt_header(false);
+        writer.set_content_methods(vec![
+            AesEncoderOptions::new(password.into()).into(),
+            SevenZMethodConfiguration::new(SevenZMethod::LZMA),
+        ]);
+        let mut entry = SevenZArchiveEntry::new();
+        entry.name = "a.txt".into();
+        entry.has_stream = true;
+        writer
+            .push_archive_entry(entry, Some(&b"hello 7z"[..]))
+            .unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    /// 把块的 LZMA 编码器改为 sevenz-rust 不支持的 PPMd，并重新计算头部的 CRC
+    fn with_ppmd(mut archive: Vec<u8>) -> Vec<u8> {
+        let header_start = 32 + u64::from_le_bytes(archive[12..20].try_into().unwrap()) as usize;
+        let header = &mut archive[header_start..];
+        let lzma = [0x23, 0x03, 0x01, 0x01];
+        let at = header.windows(4).position(|w| w == lzma).unwrap();
+        header[at + 2] = 0x04;
+        let crc = crc32fast::hash(header);
+        archive[28..32].copy_from_slice(&crc.to_le_bytes());
+        let crc = crc32fast::hash(&archive[12..32]);
+        archive[8..12].copy_from_slice(&crc.to_le_bytes());
+        archive
+    }
+
+    fn read(archive: &[u8], password: &str) -> Result<Vec<u8>, ArchiveError> {
+        let mut output = Vec::new();
+        with_7z_entry(
+            Cursor::new(archive),
+            archive.len() as u64,
+            "a.txt",
+            Some(password),
+            NameEncoding::Auto,
+            &CancelToken::new(),
+            &mut |data| Ok(data.read_to_end(&mut output)?),
+        )?;
+        Ok(output)
+    }
+
+    fn verify(archive: &[u8], password: &str) -> Result<bool, ArchiveError> {
+        verify_7z_password_from(Cursor::new(archive), archive.len() as u64, password)
+    }
+
+    #[test]
+    fn verifies_password_without_hiding_other_errors() {
+        let archive = encrypted_7z("secret");
+        assert!(verify(&archive, "secret").unwrap());
+        assert!(!verify(&archive, "wrong").unwrap());
+        assert!(verify(&with_ppmd(archive.clone()), "secret").is_err());
+        assert!(verify(&archive[..archive.len() - 4], "secret").is_err());
+    }
+
+    #[test]
+    fn reports_wrong_password_only_for_decoder_failures() {
+        let archive = encrypted_7z("secret");
+        assert_eq!(read(&archive, "secret").unwrap(), b"hello 7z");
+        assert!(matches!(
+            read(&archive, "wrong"),
+            Err(ArchiveError::WrongPassword)
+        ));
+
+        let archive = with_ppmd(archive);
+        let err = read(&archive, "secret").unwrap_err();
+        assert!(
+            matches!(
+                err,
+                ArchiveError::SevenZError(sevenz_rust::Error::UnsupportedCompressionMethod(_))
+            ),
+            "{err:?}"
+        );
+    }
+}
@@ -0,0 +1,851 @@
+//! SquashFS 4.0 文件系统映像（小端），固件、Live CD 的根文件系统以及 snap 软件包使用
+//!
+//! 元数据（inode 表、目录表、碎片表、ID 表）以最大 8 KiB 的块压缩保存；文件数据按块压缩，
+//! 不足一块的尾部可能与其他文件一起放在碎片块中。支持 gzip、lzma、xz、zstd 压缩，
+//! lzo、lz4 返回 [`ArchiveError::UnsupportedFormat`]。
+
+use crate::reader::{ArchiveMetadata, ArchiveReader, EntryFn, Input, ReadSeek, SeekInput, VisitFn};
+use crate::{ArchiveError, ArchiveFormat, EntryTime, Extract};
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Read, SeekFrom},
+    path::Path,
+};
+
+const MAGIC: &[u8; 4] = b"hsqs";
+const SUPERBLOCK_SIZE: usize = 96;
+
+/// 元数据块的最大解压大小，以及块头中表示未压缩的标志位
+const METADATA_BLOCK_SIZE: usize = 8192;
+const METADATA_UNCOMPRESSED: u16 = 0x8000;
+
+/// 数据块、碎片块大小字段中表示未压缩的标志位
+const DATA_UNCOMPRESSED: u32 = 1 << 24;
+
+/// 目录内容超过该大小时视为映像损坏
+const MAX_DIRECTORY_SIZE: usize = 64 << 20;
+
+/// 符号链接目标的最大长度
+const MAX_LINK_TARGET: usize = 4096;
+
+/// 没有碎片时的碎片序号
+const NO_FRAGMENT: u32 = 0xFFFF_FFFF;
+
+/// inode 类型，扩展类型为基本类型加 7
+const INODE_DIRECTORY: u16 = 1;
+const INODE_FILE: u16 = 2;
+const INODE_SYMLINK: u16 = 3;
+const INODE_BLOCK_DEVICE: u16 = 4;
+const INODE_CHAR_DEVICE: u16 = 5;
+const INODE_FIFO: u16 = 6;
+const INODE_SOCKET: u16 = 7;
+const EXTENDED_OFFSET: u16 = 7;
+
+/// 数据块和元数据块的压缩算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compressor {
+    Gzip,
+    Lzma,
+    Lzo,
+    Xz,
+    Lz4,
+    Zstd,
+}
+
+impl Compressor {
+    fn from_id(id: u16) -> Result<Self, ArchiveError> {
+        Ok(match id {
+            1 => Self::Gzip,
+            2 => Self::Lzma,
+            3 => Self::Lzo,
+            4 => Self::Xz,
+            5 => Self::Lz4,
+            6 => Self::Zstd,
+            _ => {
+                return Err(ArchiveError::UnsupportedFormat(format!(
+                    "SquashFS compressor {id}"
+                )))
+            },
+        })
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Lzma => "lzma",
+            Self::Lzo => "lzo",
+            Self::Xz => "xz",
+            Self::Lz4 => "lz4",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// 解压一个块，输出不超过 `max_len` 字节
+    fn decompress(self, data: &[u8], max_len: usize) -> Result<Vec<u8>, ArchiveError> {
+        // SquashFS 的 gzip 实际是 zlib 格式
+        let decoder: Box<dyn Read + '_> = match self {
+            Self::Gzip => Box::new(flate2::read::ZlibDecoder::new(data)),
+            Self::Lzma => {
+                let stream =
+                    xz2::stream::Stream::new_lzma_decoder(u64::MAX).map_err(io::Error::from)?;
+                Box::new(xz2::read::XzDecoder::new_stream(data, stream))
+            },
+            Self::Xz => Box::new(xz2::read::XzDecoder::new(data)),
+            Self::Zstd => Box::new(
+                ruzstd::decoding::StreamingDecoder::new(data)
+                    .map_err(|e| ArchiveError::Other(format!("Zstd 解码失败: {e}")))?,
+            ),
+            Self::Lzo | Self::Lz4 => {
+                return Err(ArchiveError::UnsupportedFormat(format!(
+                    "SquashFS {} compression",
+                    self.name()
+                )))
+            },
+        };
+        let mut output = Vec::with_capacity(max_len);
+        decoder.take(max_len as u64).read_to_end(&mut output)?;
+        Ok(output)
+    }
+}
+
+/// 超级块中用到的字段
+#[derive(Debug, Clone)]
+struct Superblock {
+    block_size: u32,
+    fragment_count: u32,
+    compressor: Compressor,
+    id_count: u16,
+    root_inode: u64,
+    id_table: u64,
+    inode_table: u64,
+    directory_table: u64,
+    fragment_table: u64,
+}
+
+impl Superblock {
+    fn parse(data: &[u8]) -> Result<Self, ArchiveError> {
+        if data.len() < SUPERBLOCK_SIZE || &data[..4] != MAGIC {
+            return Err(ArchiveError::Other("Not a SquashFS image".into()));
+        }
+        let u16_at = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let u32_at =
+            |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        let u64_at =
+            |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let (major, minor) = (u16_at(28), u16_at(30));
+        if major != 4 {
+            return Err(ArchiveError::UnsupportedFormat(format!(
+                "SquashFS version {major}.{minor}"
+            )));
+        }
+        let block_size = u32_at(12);
+        if !(4096..=1 << 20).contains(&block_size) {
+            return Err(ArchiveError::Other(format!(
+                "Invalid SquashFS block size {block_size}"
+            )));
+        }
+        Ok(Self {
+            block_size,
+            fragment_count: u32_at(16),
+            compressor: Compressor::from_id(u16_at(20))?,
+            id_count: u16_at(26),
+            root_inode: u64_at(32),
+            id_table: u64_at(48),
+            inode_table: u64_at(64),
+            directory_table: u64_at(72),
+            fragment_table: u64_at(80),
+        })
+    }
+}
+
+/// 元数据中的位置：元数据块在映像中的偏移，以及解压后块内的偏移
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct MetadataPos {
+    block: u64,
+    offset: usize,
+}
+
+impl MetadataPos {
+    /// inode 引用：高 48 位为元数据块相对表起点的偏移，低 16 位为块内偏移
+    fn from_ref(table: u64, reference: u64) -> Self {
+        Self {
+            block: table + (reference >> 16),
+            offset: (reference & 0xFFFF) as usize,
+        }
+    }
+}
+
+/// 读取并缓存解压后的元数据块
+struct MetadataReader<'r> {
+    image: &'r mut dyn ReadSeek,
+    compressor: Compressor,
+    /// 块偏移 → (解压后的数据, 下一块的偏移)
+    blocks: HashMap<u64, (Vec<u8>, u64)>,
+}
+
+impl MetadataReader<'_> {
+    fn block(&mut self, offset: u64) -> Result<&(Vec<u8>, u64), ArchiveError> {
+        if !self.blocks.contains_key(&offset) {
+            let mut header = [0; 2];
+            self.image.seek(SeekFrom::Start(offset))?;
+            self.image.read_exact(&mut header)?;
+            let header = u16::from_le_bytes(header);
+            let len = usize::from(header & !METADATA_UNCOMPRESSED);
+            let mut raw = vec![0; len];
+            self.image.read_exact(&mut raw)?;
+            let data = if header & METADATA_UNCOMPRESSED != 0 {
+                raw
+            } else {
+                self.compressor.decompress(&raw, METADATA_BLOCK_SIZE)?
+            };
+            self.blocks.insert(offset, (data, offset + 2 + len as u64));
+        }
+        Ok(&self.blocks[&offset])
+    }
+
+    /// 从 `pos` 读取 `len` 字节，可以跨越元数据块，读完后 `pos` 指向后面的数据
+    fn read(&mut self, pos: &mut MetadataPos, len: usize) -> Result<Vec<u8>, ArchiveError> {
+        let mut output = Vec::with_capacity(len);
+        while output.len() < len {
+            let (data, next) = self.block(pos.block)?;
+            if pos.offset >= data.len() {
+                if data.is_empty() {
+                    return Err(ArchiveError::Other("Empty SquashFS metadata block".into()));
+                }
+                *pos = MetadataPos {
+                    block: *next,
+                    offset: pos.offset - data.len(),
+                };
+                continue;
+            }
+            let take = (len - output.len()).min(data.len() - pos.offset);
+            output.extend_from_slice(&data[pos.offset..pos.offset + take]);
+            pos.offset += take;
+        }
+        Ok(output)
+    }
+
+    /// 读取查找表（碎片表、ID 表）：`table` 处是元数据块位置的数组，每块 8 KiB
+    fn read_table(&mut self, table: u64, len: usize) -> Result<Vec<u8>, ArchiveError> {
+        let block_count = len.div_ceil(METADATA_BLOCK_SIZE);
+        let image_len = self.image.seek(SeekFrom::End(0))?;
+        if block_count as u64 * 8 > image_len.saturating_sub(table) {
+            return Err(ArchiveError::Other(
+                "SquashFS lookup table is truncated".into(),
+            ));
+        }
+        let mut pointers = vec![0; block_count * 8];
+        self.image.seek(SeekFrom::Start(table))?;
+        self.image.read_exact(&mut pointers)?;
+        let mut output = Vec::with_capacity(len);
+        for pointer in pointers.chunks_exact(8) {
+            let block = u64::from_le_bytes(pointer.try_into().unwrap());
+            let take = (len - output.len()).min(METADATA_BLOCK_SIZE);
+            output.extend(self.read(&mut MetadataPos { block, offset: 0 }, take)?);
+        }
+        Ok(output)
+    }
+}
+
+/// 普通文件的数据位置
+#[derive(Debug, Clone)]
+struct FileData {
+    size: u64,
+    blocks_start: u64,
+    /// 每个数据块的大小字段，0 表示稀疏块
+    block_sizes: Vec<u32>,
+    /// 尾部所在的碎片序号及其在碎片块中的偏移
+    fragment: Option<(u32, u32)>,
+}
+
+/// 碎片表项：碎片块在映像中的偏移和大小字段
+#[derive(Debug, Clone, Copy)]
+struct Fragment {
+    start: u64,
+    size: u32,
+}
+
+/// 解析 inode 得到的信息
+struct Inode {
+    kind: u16,
+    mode: u32,
+    uid: Option<u64>,
+    gid: Option<u64>,
+    modified: Option<EntryTime>,
+    /// 目录内容的位置和长度
+    directory: Option<(MetadataPos, usize)>,
+    file: Option<FileData>,
+    link_target: Option<String>,
+}
+
+fn le_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn le_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn le_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// 已建立的条目索引
+pub(crate) struct SquashIndex {
+    superblock: Superblock,
+    fragments: Vec<Fragment>,
+    entries: Vec<(Extract, Option<FileData>)>,
+}
+
+/// 读取 inode 表和目录表，按目录顺序建立全部条目的索引
+fn read_index(image: &mut dyn ReadSeek) -> Result<SquashIndex, ArchiveError> {
+    let mut header = [0; SUPERBLOCK_SIZE];
+    image.seek(SeekFrom::Start(0))?;
+    image.read_exact(&mut header)?;
+    let superblock = Superblock::parse(&header)?;
+    let mut metadata = MetadataReader {
+        image,
+        compressor: superblock.compressor,
+        blocks: HashMap::new(),
+    };
+
+    let ids = metadata.read_table(superblock.id_table, usize::from(superblock.id_count) * 4)?;
+    let ids: Vec<u32> = ids.chunks_exact(4).map(|id| le_u32(id, 0)).collect();
+    let fragments = if superblock.fragment_count == 0 {
+        Vec::new()
+    } else {
+        let table = metadata.read_table(
+            superblock.fragment_table,
+            superblock.fragment_count as usize * 16,
+        )?;
+        table
+            .chunks_exact(16)
+            .map(|entry| Fragment {
+                start: le_u64(entry, 0),
+                size: le_u32(entry, 8),
+            })
+            .collect()
+    };
+
+    let block_size = u64::from(superblock.block_size);
+    // inode 表位于目录表之前，每个元数据块至少占 3 字节（块头和 1 字节数据），
+    // 由此得到 `pos` 之后 inode 表解压后的最大长度
+    let inode_table_left = |pos: &MetadataPos| {
+        let blocks = superblock.directory_table.saturating_sub(pos.block) / 3;
+        usize::try_from(blocks)
+            .unwrap_or(usize::MAX)
+            .saturating_mul(METADATA_BLOCK_SIZE)
+    };
+    let read_inode = |metadata: &mut MetadataReader<'_>, reference: u64| {
+        let mut pos = MetadataPos::from_ref(superblock.inode_table, reference);
+        let common = metadata.read(&mut pos, 16)?;
+        let kind = le_u16(&common, 0);
+        let basic_kind = (kind.max(1) - 1) % EXTENDED_OFFSET + 1;
+        let id = |index: u16| ids.get(usize::from(index)).map(|id| u64::from(*id));
+        let file_type = match basic_kind {
+            INODE_DIRECTORY => 0o040_000,
+            INODE_FILE => 0o100_000,
+            INODE_SYMLINK => 0o120_000,
+            INODE_BLOCK_DEVICE => 0o060_000,
+            INODE_CHAR_DEVICE => 0o020_000,
+            INODE_FIFO => 0o010_000,
+            INODE_SOCKET => 0o140_000,
+            _ => 0,
+        };
+        let mut inode = Inode {
+            kind: basic_kind,
+            mode: file_type | u32::from(le_u16(&common, 2)),
+            uid: id(le_u16(&common, 4)),
+            gid: id(le_u16(&common, 6)),
+            modified: EntryTime::from_unix(i64::from(le_u32(&common, 8))),
+            directory: None,
+            file: None,
+            link_target: None,
+        };
+
+        // 目录的 file_size 包含 `.`、`..` 占用的 3 个字节
+        let file_fields = match kind {
+            1 => {
+                let fields = metadata.read(&mut pos, 16)?;
+                let size = usize::from(le_u16(&fields, 8)).saturating_sub(3);
+                let start = MetadataPos {
+                    block: superblock.directory_table + u64::from(le_u32(&fields, 0)),
+                    offset: usize::from(le_u16(&fields, 10)),
+                };
+                inode.directory = Some((start, size));
+                None
+            },
+            8 => {
+                let fields = metadata.read(&mut pos, 24)?;
+                let size = (le_u32(&fields, 4) as usize).saturating_sub(3);
+                if size > MAX_DIRECTORY_SIZE {
+                    return Err(ArchiveError::Other(
+                        "SquashFS directory is too large".into(),
+                    ));
+                }
+                let start = MetadataPos {
+                    block: superblock.directory_table + u64::from(le_u32(&fields, 8)),
+                    offset: usize::from(le_u16(&fields, 18)),
+                };
+                inode.directory = Some((start, size));
+                None
+            },
+            2 => {
+                let fields = metadata.read(&mut pos, 16)?;
+                Some((
+                    u64::from(le_u32(&fields, 0)),
+                    le_u32(&fields, 4),
+                    le_u32(&fields, 8),
+                    u64::from(le_u32(&fields, 12)),
+                ))
+            },
+            9 => {
+                let fields = metadata.read(&mut pos, 40)?;
+                Some((
+                    le_u64(&fields, 0),
+                    le_u32(&fields, 28),
+                    le_u32(&fields, 32),
+                    le_u64(&fields, 8),
+                ))
+            },
+            3 | 10 => {
+                let fields = metadata.read(&mut pos, 8)?;
+                let len = le_u32(&fields, 4) as usize;
+                if len > MAX_LINK_TARGET {
+                    return Err(ArchiveError::Other(
+                        "SquashFS symlink target is too long".into(),
+                    ));
+                }
+                let target = metadata.read(&mut pos, len)?;
+                inode.link_target = Some(String::from_utf8_lossy(&target).into_owned());
+                None
+            },
+            4..=7 | 11..=14 => None,
+            _ => {
+                return Err(ArchiveError::Other(format!(
+                    "Invalid SquashFS inode type {kind}"
+                )))
+            },
+        };
+
+        if let Some((blocks_start, fragment, fragment_offset, size)) = file_fields {
+            let full_blocks = size / block_size;
+            let block_count = if fragment == NO_FRAGMENT {
+                size.div_ceil(block_size)
+            } else {
+                full_blocks
+            };
+            let list_len = usize::try_from(block_count)
+                .ok()
+                .and_then(|count| count.checked_mul(4))
+                .filter(|len| *len <= inode_table_left(&pos))
+                .ok_or_else(|| ArchiveError::Other("SquashFS block list is truncated".into()))?;
+            let sizes = metadata.read(&mut pos, list_len)?;
+            inode.file = Some(FileData {
+                size,
+                blocks_start,
+                block_sizes: sizes.chunks_exact(4).map(|word| le_u32(word, 0)).collect(),
+                fragment: (fragment != NO_FRAGMENT).then_some((fragment, fragment_offset)),
+            });
+        }
+        Ok::<_, ArchiveError>(inode)
+    };
+
+    let compression = superblock.compressor.name().to_string();
+    let root = read_inode(&mut metadata, superblock.root_inode)?;
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![(String::new(), root.directory)];
+    while let Some((prefix, directory)) = pending.pop() {
+        let Some((start, size)) = directory else {
+            continue;
+        };
+        if !visited.insert(start) {
+            continue;
+        }
+        let listing = metadata.read(&mut start.clone(), size)?;
+        let mut children = Vec::new();
+
+        // 目录头（条目数 - 1、inode 所在元数据块、起始 inode 号）后跟若干条目
+        let mut pos = 0;
+        while pos + 12 <= listing.len() {
+            let count = le_u32(&listing, pos) as usize + 1;
+            let inode_block = u64::from(le_u32(&listing, pos + 4));
+            pos += 12;
+            for _ in 0..count {
+                let Some(entry) = listing.get(pos..pos + 8) else {
+                    break;
+                };
+                let name_len = usize::from(le_u16(entry, 6)) + 1;
+                let Some(name) = listing.get(pos + 8..pos + 8 + name_len) else {
+                    break;
+                };
+                let reference = (inode_block << 16) | u64::from(le_u16(entry, 0));
+                pos += 8 + name_len;
+
+                let path = format!("{prefix}{}", String::from_utf8_lossy(name));
+                let inode = read_inode(&mut metadata, reference)?;
+                let is_dir = inode.kind == INODE_DIRECTORY;
+                let size = inode.file.as_ref().map_or(0, |file| file.size);
+                let mut item = if is_dir {
+                    Extract::new(format!("{path}/"), 0, inode.modified, true)
+                } else {
+                    Extract::new(path, size, inode.modified, false)
+                };
+                item.set_mode(inode.mode);
+                item.uid = inode.uid;
+                item.gid = inode.gid;
+                item.link_target = inode.link_target;
+                if inode.file.is_some() {
+                    item.compression_method = Some(compression.clone());
+                }
+                if is_dir {
+                    children.push((item.name.clone(), inode.directory));
+                }
+                entries.push((item, inode.file));
+            }
+        }
+        pending.extend(children.into_iter().rev());
+    }
+
+    Ok(SquashIndex { superblock, fragments, entries })
+}
+
+/// 读取数据块和碎片块，缓存最近用到的碎片块（多个小文件的尾部共用一个碎片块）
+struct BlockReader<'r> {
+    image: &'r mut dyn ReadSeek,
+    superblock: &'r Superblock,
+    fragments: &'r [Fragment],
+    fragment_cache: Option<(u32, Vec<u8>)>,
+}
+
+impl BlockReader<'_> {
+    fn read_block(&mut self, offset: u64, size_field: u32) -> Result<Vec<u8>, ArchiveError> {
+        let len = (size_field & !DATA_UNCOMPRESSED) as usize;
+        if len > self.superblock.block_size as usize {
+            return Err(ArchiveError::Other(format!(
+                "Invalid SquashFS block size field {size_field:#x}"
+            )));
+        }
+        let mut raw = vec![0; len];
+        self.image.seek(SeekFrom::Start(offset))?;
+        self.image.read_exact(&mut raw)?;
+        if size_field & DATA_UNCOMPRESSED != 0 {
+            Ok(raw)
+        } else {
+            self.superblock
+                .compressor
+                .decompress(&raw, self.superblock.block_size as usize)
+        }
+    }
+
+    fn fragment(&mut self, index: u32) -> Result<&[u8], ArchiveError> {
+        if self.fragment_cache.as_ref().map(|(cached, _)| *cached) != Some(index) {
+            let fragment = *self.fragments.get(index as usize).ok_or_else(|| {
+                ArchiveError::Other(format!("SquashFS fragment {index} does not exist"))
+            })?;
+            let data = self.read_block(fragment.start, fragment.size)?;
+            self.fragment_cache = Some((index, data));
+        }
+        Ok(self
+            .fragment_cache
+            .as_ref()
+            .map(|(_, data)| data.as_slice())
+            .unwrap_or_default())
+    }
+}
+
+/// 逐块解压一个文件的数据
+struct FileReader<'b, 'r> {
+    blocks: &'b mut BlockReader<'r>,
+    file: &'b FileData,
+    next_block: usize,
+    /// 下一个数据块在映像中的偏移
+    offset: u64,
+    buffer: Vec<u8>,
+    consumed: usize,
+    remaining: u64,
+}
+
+impl<'b, 'r> FileReader<'b, 'r> {
+    fn new(blocks: &'b mut BlockReader<'r>, file: &'b FileData) -> Self {
+        Self {
+            blocks,
+            offset: file.blocks_start,
+            file,
+            next_block: 0,
+            buffer: Vec::new(),
+            consumed: 0,
+            remaining: file.size,
+        }
+    }
+
+    /// 解压下一个数据块，或者读取碎片块中的尾部
+    fn fill(&mut self) -> Result<(), ArchiveError> {
+        let block_size = u64::from(self.blocks.superblock.block_size);
+        let want = self.remaining.min(block_size) as usize;
+        let mut data = if let Some(&size_field) = self.file.block_sizes.get(self.next_block) {
+            self.next_block += 1;
+            if size_field & !DATA_UNCOMPRESSED == 0 {
+                vec![0; want]
+            } else {
+                let data = self.blocks.read_block(self.offset, size_field)?;
+                self.offset += u64::from(size_field & !DATA_UNCOMPRESSED);
+                data
+            }
+        } else if let Some((index, offset)) = self.file.fragment {
+            let fragment = self.blocks.fragment(index)?;
+            fragment.get(offset as usize..).unwrap_or_default().to_vec()
+        } else {
+            Vec::new()
+        };
+        if data.len() < want {
+            return Err(ArchiveError::Other(
+                "SquashFS file data is truncated".into(),
+            ));
+        }
+        data.truncate(want);
+        self.remaining -= want as u64;
+        self.buffer = data;
+        self.consumed = 0;
+        Ok(())
+    }
+}
+
+impl Read for FileReader<'_, '_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.consumed == self.buffer.len() {
+            if self.remaining == 0 {
+                return Ok(0);
+            }
+            self.fill().map_err(|e| match e {
+                ArchiveError::IoError(e) => e,
+                e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+            })?;
+        }
+        let len = buf.len().min(self.buffer.len() - self.consumed);
+        buf[..len].copy_from_slice(&self.buffer[self.consumed..self.consumed + len]);
+        self.consumed += len;
+        Ok(len)
+    }
+}
+
+/// 列举 SquashFS 映像（含 snap 软件包）的条目
+pub fn list_squashfs_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
+    SquashFsReader::open(Input::Path(path.as_ref().to_path_buf()), None)?.list()
+}
+
+/// SquashFS 读取器，嵌套的映像会读入内存
+pub struct SquashFsReader {
+    input: SeekInput,
+    /// 第一次列举或读取时建立的条目索引
+    index: Option<SquashIndex>,
+}
+
+impl SquashFsReader {
+    fn load_index(&mut self) -> Result<&SquashIndex, ArchiveError> {
+        if self.index.is_none() {
+            let (mut image, _) = self.input.reader()?;
+            self.index = Some(read_index(&mut *image)?);
+        }
+        Ok(self.index.as_ref().expect("index loaded"))
+    }
+}
+
+impl<'a> ArchiveReader<'a> for SquashFsReader {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self {
+            input: SeekInput::new(input)?,
+            index: None,
+        })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Squashfs)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        Ok(self
+            .load_index()?
+            .entries
+            .iter()
+            .map(|(item, _)| item.clone())
+            .collect())
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        self.load_index()?;
+        let index = self.index.as_ref().expect("index loaded");
+        let (item, file) = index
+            .entries
+            .iter()
+            .find(|(item, _)| item.name == entry_name)
+            .ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))?;
+        if item.dir {
+            return Err(ArchiveError::InvalidPath(entry_name.to_string()));
+        }
+        super::ensure_not_link(item)?;
+        let (mut image, _) = self.input.reader()?;
+        let Some(file) = file else {
+            // 设备文件等没有数据
+            return f(&mut io::empty());
+        };
+        let mut blocks = BlockReader {
+            image: &mut *image,
+            superblock: &index.superblock,
+            fragments: &index.fragments,
+            fragment_cache: None,
+        };
+        f(&mut FileReader::new(&mut blocks, file))
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        self.load_index()?;
+        let index = self.index.as_ref().expect("index loaded");
+        let (mut image, _) = self.input.reader()?;
+        let mut blocks = BlockReader {
+            image: &mut *image,
+            superblock: &index.superblock,
+            fragments: &index.fragments,
+            fragment_cache: None,
+        };
+        for (item, file) in index.entries.iter().filter(|(item, _)| !item.dir) {
+            let keep_going = match file {
+                Some(file) => f(item, Ok(&mut FileReader::new(&mut blocks, file)))?,
+                None => f(item, Ok(&mut io::empty()))?,
+            };
+            if !keep_going {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// 不压缩的元数据块
+    fn metadata_block(data: &[u8]) -> Vec<u8> {
+        let header = data.len() as u16 | METADATA_UNCOMPRESSED;
+        [&header.to_le_bytes()[..], data].concat()
+    }
+
+    /// 根目录只有一个条目 `name`，其 inode 为 `child`；`data` 紧跟超级块，从偏移 96 开始
+    fn image(data: &[u8], name: &str, kind: u16, child: &[u8]) -> Vec<u8> {
+        let mut listing = Vec::new();
+        listing.extend_from_slice(&0u32.to_le_bytes());
+        listing.extend_from_slice(&0u32.to_le_bytes());
+        listing.extend_from_slice(&1u32.to_le_bytes());
+        listing.extend_from_slice(&32u16.to_le_bytes());
+        listing.extend_from_slice(&1i16.to_le_bytes());
+        listing.extend_from_slice(&kind.to_le_bytes());
+        listing.extend_from_slice(&(name.len() as u16 - 1).to_le_bytes());
+        listing.extend_from_slice(name.as_bytes());
+
+        // 根目录 inode：公共字段后跟目录字段，子条目的 inode 从块内偏移 32 开始
+        let mut inodes = Vec::new();
+        for value in [INODE_DIRECTORY, 0o755, 0, 0] {
+            inodes.extend_from_slice(&value.to_le_bytes());
+        }
+        inodes.extend_from_slice(&[0; 8]);
+        inodes.extend_from_slice(&0u32.to_le_bytes());
+        inodes.extend_from_slice(&2u32.to_le_bytes());
+        inodes.extend_from_slice(&(listing.len() as u16 + 3).to_le_bytes());
+        inodes.extend_from_slice(&0u16.to_le_bytes());
+        inodes.extend_from_slice(&0u32.to_le_bytes());
+        for value in [kind, 0o644, 0, 0] {
+            inodes.extend_from_slice(&value.to_le_bytes());
+        }
+        inodes.extend_from_slice(&[0; 8]);
+        inodes.extend_from_slice(child);
+
+        let inode_table = (SUPERBLOCK_SIZE + data.len()) as u64;
+        let inode_block = metadata_block(&inodes);
+        let directory_table = inode_table + inode_block.len() as u64;
+        let directory_block = metadata_block(&listing);
+        let id_block_start = directory_table + directory_block.len() as u64;
+        let id_block = metadata_block(&1000u32.to_le_bytes());
+        let id_table = id_block_start + id_block.len() as u64;
+
+        let mut superblock = vec![0; SUPERBLOCK_SIZE];
+        superblock[..4].copy_from_slice(MAGIC);
+        superblock[12..16].copy_from_slice(&4096u32.to_le_bytes());
+        superblock[20..22].copy_from_slice(&1u16.to_le_bytes());
+        superblock[26..28].copy_from_slice(&1u16.to_le_bytes());
+        superblock[28..30].copy_from_slice(&4u16.to_le_bytes());
+        superblock[48..56].copy_from_slice(&id_table.to_le_bytes());
+        superblock[64..72].copy_from_slice(&inode_table.to_le_bytes());
+        superblock[72..80].copy_from_slice(&directory_table.to_le_bytes());
+        [
+            superblock,
+            data.to_vec(),
+            inode_block,
+            directory_block,
+            id_block,
+            id_block_start.to_le_bytes().to_vec(),
+        ]
+        .concat()
+    }
+
+    fn fields(values: &[u32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    #[test]
+    fn reads_crafted_image() {
+        // 一个不压缩的数据块，没有碎片
+        let mut file = fields(&[SUPERBLOCK_SIZE as u32, NO_FRAGMENT, 0, 5]);
+        file.extend_from_slice(&(5 | DATA_UNCOMPRESSED).to_le_bytes());
+        let image = image(b"hello", "a.txt", INODE_FILE, &file);
+
+        let mut reader = Cursor::new(image);
+        let index = read_index(&mut reader).unwrap();
+        let (item, data) = &index.entries[0];
+        assert_eq!(
+            (item.name.as_str(), item.size, item.uid),
+            ("a.txt", 5, Some(1000))
+        );
+
+        let mut blocks = BlockReader {
+            image: &mut reader,
+            superblock: &index.superblock,
+            fragments: &index.fragments,
+            fragment_cache: None,
+        };
+        let mut content = String::new();
+        FileReader::new(&mut blocks, data.as_ref().unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "hello");
+    }
+
+    #[test]
+    fn rejects_oversized_lengths() {
+        // 扩展文件 inode 声明 1 TiB，块列表远超 inode 表的剩余部分
+        let mut file = Vec::new();
+        file.extend_from_slice(&(SUPERBLOCK_SIZE as u64).to_le_bytes());
+        file.extend_from_slice(&(1u64 << 40).to_le_bytes());
+        file.extend_from_slice(&fields(&[0, 0, 1, NO_FRAGMENT, 0, 0]));
+        let file = image(&[], "big", INODE_FILE + EXTENDED_OFFSET, &file);
+
+        let link = image(&[], "link", INODE_SYMLINK, &fields(&[1, u32::MAX]));
+
+        let mut directory = fields(&[2, u32::MAX, 0, 0]);
+        directory.extend_from_slice(&[0; 8]);
+        let directory = image(&[], "dir", INODE_DIRECTORY + EXTENDED_OFFSET, &directory);
+
+        for image in [file, link, directory] {
+            assert!(read_index(&mut Cursor::new(image)).is_err());
+        }
+    }
+}
@@ -1,32 +1,505 @@
-use crate::{ArchiveError, Extract};
+//! TAR 归档
+//!
+//! 除 ustar 头部外还解析 GNU 长文件名、PAX 扩展头部（含全局头部、高精度时间、
+//! `SCHILY.xattr.*` 扩展属性）以及 GNU 和 PAX 两种稀疏文件格式。
+
+use crate::reader::{
+    collect_entries, ArchiveMetadata, ArchiveReader, EntryFn, Input, ListFn, StreamInput, VisitFn,
+};
+use crate::{ArchiveError, ArchiveFormat, EntryKind, EntryTime, Extract};
+use chrono::DateTime;
 use std::{
+    collections::BTreeMap,
     fs::File,
-    io::Read,
-    path::Path,
-    time::{Duration, UNIX_EPOCH},
+    io::{self, Read},
+    path::{Path, PathBuf},
 };
 
-fn parse_tar_entries<R: Read>(reader: R) -> Result<Vec<Extract>, ArchiveError> {
+/// TAR 的块大小
+const BLOCK_SIZE: u64 = 512;
+/// PAX 1.0 稀疏映射中允许的最大数据段数量
+const MAX_SPARSE_SEGMENTS: u64 = 1 << 20;
+
+pub(crate) fn parse_tar_entries<R: Read>(reader: R) -> Result<Vec<Extract>, ArchiveError> {
+    collect_entries(|f| each_tar_entry(reader, f))
+}
+
+/// 顺序读取 TAR 流，每读到一个头部就交给 `f`
+pub(crate) fn each_tar_entry<R: Read>(reader: R, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = archive.entries()?;
+    let mut globals = PaxRecords::default();
+
+    while let Some((_, entry)) = next_entry(&mut entries, &mut globals)? {
+        if !f(entry.item)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// PAX 扩展头部中的记录，按出现顺序保存，同名记录以后出现的为准
+#[derive(Default)]
+struct PaxRecords(Vec<(String, Vec<u8>)>);
+
+impl PaxRecords {
+    /// 读取条目的 PAX 记录；条目本身是全局头部时读取其内容
+    fn read<R: Read>(entry: &mut tar::Entry<'_, R>) -> Result<Self, ArchiveError> {
+        let mut records = Vec::new();
+        if let Some(extensions) = entry.pax_extensions()? {
+            // 与 tar 读取 path 等记录时一样，跳过格式错误的记录
+            for extension in extensions.flatten() {
+                if let Ok(key) = extension.key() {
+                    records.push((key.to_string(), extension.value_bytes().to_vec()));
+                }
+            }
+        }
+        Ok(Self(records))
+    }
+
+    fn get(&self, key: &str) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .rev()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_slice())
+    }
+
+    fn str(&self, key: &str) -> Option<&str> {
+        self.get(key)
+            .and_then(|value| std::str::from_utf8(value).ok())
+    }
+
+    fn number(&self, key: &str) -> Option<u64> {
+        self.str(key)?.trim().parse().ok()
+    }
+}
+
+/// PAX 格式稀疏文件（`GNU.sparse.*`）的数据段映射
+enum SparseLayout {
+    /// 0.0、0.1 版：数据段（偏移、长度）记录在 PAX 头部中
+    Map(Vec<(u64, u64)>),
+    /// 1.0 版：映射以十进制文本行存放在条目数据开头，补齐到 512 字节
+    InData,
+}
+
+/// 解析后的 TAR 条目
+struct TarEntry {
+    item: Extract,
+    /// PAX 稀疏文件的映射，GNU 稀疏文件由 tar 展开
+    sparse: Option<SparseLayout>,
+}
+
+/// 读取下一个条目；PAX 全局扩展头部只记录到 `globals`，不作为条目返回
+fn next_entry<'a, R: Read>(
+    entries: &mut tar::Entries<'a, R>,
+    globals: &mut PaxRecords,
+) -> Result<Option<(tar::Entry<'a, R>, TarEntry)>, ArchiveError> {
+    for entry in entries.by_ref() {
+        let mut entry = entry?;
+        if entry.header().entry_type().is_pax_global_extensions() {
+            globals.0.extend(PaxRecords::read(&mut entry)?.0);
+            continue;
+        }
+        let parsed = tar_entry(&mut entry, globals)?;
+        return Ok(Some((entry, parsed)));
+    }
+    Ok(None)
+}
+
+/// TAR 头部及 PAX 扩展头部记录的条目信息，`globals` 为此前全局头部中的记录
+fn tar_entry<R: Read>(
+    entry: &mut tar::Entry<'_, R>,
+    globals: &PaxRecords,
+) -> Result<TarEntry, ArchiveError> {
+    let local = PaxRecords::read(entry)?;
+    let pax = |key: &str| local.get(key).or_else(|| globals.get(key));
+    let pax_str = |key: &str| pax(key).and_then(|value| std::str::from_utf8(value).ok());
+    let pax_number = |key: &str| pax_str(key).and_then(|value| value.trim().parse::<u64>().ok());
+
+    let header = entry.header();
+    let entry_type = header.entry_type();
+    let mut name = entry.path()?.to_string_lossy().into_owned();
+    let mut size = entry.size();
+    let sparse = pax_sparse(&local);
+    if sparse.is_some() {
+        // 稀疏文件的路径是 `GNUSparseFile.<pid>/<name>` 形式的占位名，真实名称和大小另有记录
+        if let Some(real_name) = local.str("GNU.sparse.name") {
+            name = real_name.to_string();
+        }
+        size = local
+            .number("GNU.sparse.realsize")
+            .or_else(|| local.number("GNU.sparse.size"))
+            .unwrap_or(size);
+    }
+
+    let kind = match entry_type {
+        tar::EntryType::Directory => EntryKind::Directory,
+        tar::EntryType::Symlink => EntryKind::Symlink,
+        tar::EntryType::Link => EntryKind::Hardlink,
+        tar::EntryType::Char => EntryKind::CharDevice,
+        tar::EntryType::Block => EntryKind::BlockDevice,
+        tar::EntryType::Fifo => EntryKind::Fifo,
+        // 旧式 V7 TAR 没有目录类型，以 `/` 结尾的普通文件即目录
+        _ if name.ends_with('/') => EntryKind::Directory,
+        _ => EntryKind::File,
+    };
+    let is_dir = kind == EntryKind::Directory;
+    if is_dir {
+        size = 0;
+    }
+
+    let last_modified = match pax_str("mtime") {
+        Some(mtime) => pax_time(mtime),
+        None => i64::try_from(header.mtime()?)
+            .ok()
+            .and_then(EntryTime::from_unix),
+    };
+
+    let mut item = Extract::new(name, size, last_modified, is_dir);
+    item.kind = kind;
+    item.sparse = entry_type.is_gnu_sparse() || sparse.is_some();
+    item.mode = header.mode().ok();
+    item.uid = pax_number("uid").or_else(|| header.uid().ok());
+    item.gid = pax_number("gid").or_else(|| header.gid().ok());
+    item.user_name = pax_str("uname")
+        .or_else(|| header.username().ok().flatten())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    item.group_name = pax_str("gname")
+        .or_else(|| header.groupname().ok().flatten())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+    if matches!(kind, EntryKind::CharDevice | EntryKind::BlockDevice) {
+        item.device_major = header.device_major().ok().flatten();
+        item.device_minor = header.device_minor().ok().flatten();
+    }
+
+    let mut xattrs = BTreeMap::new();
+    for (key, value) in globals.0.iter().chain(&local.0) {
+        let name = match key.strip_prefix("SCHILY.xattr.") {
+            Some(name) => name,
+            // GNU tar 的 `--selinux` 使用 Red Hat 的记录名
+            None if key == "RHT.security.selinux" => "security.selinux",
+            None => continue,
+        };
+        xattrs.insert(name.to_string(), xattr_value(value));
+    }
+    item.xattrs = (!xattrs.is_empty()).then_some(xattrs);
+
+    let link_target = entry
+        .link_name()?
+        .map(|target| target.to_string_lossy().into_owned());
+    match kind {
+        EntryKind::Symlink => item.link_target = link_target,
+        EntryKind::Hardlink => item.hardlink_target = link_target,
+        _ => {},
+    }
+    Ok(TarEntry { item, sparse })
+}
+
+/// 解析 PAX 的时间值（UNIX 秒，可带小数），0 视为未记录
+fn pax_time(value: &str) -> Option<EntryTime> {
+    let value = value.trim();
+    let (secs, fraction) = value.split_once('.').unwrap_or((value, ""));
+    let mut secs: i64 = secs.parse().ok()?;
+    if !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let digits = &fraction[..fraction.len().min(9)];
+    let mut nanos: u32 = format!("{digits:0<9}").parse().ok()?;
+    // 负数时间的小数部分同样向负方向延伸
+    if value.starts_with('-') && nanos > 0 {
+        secs -= 1;
+        nanos = 1_000_000_000 - nanos;
+    }
+    if secs == 0 && nanos == 0 {
+        return None;
+    }
+    DateTime::from_timestamp(secs, nanos).map(EntryTime::Utc)
+}
+
+/// 扩展属性的值：去掉末尾的 NUL 后是可打印文本时原样返回，否则转为十六进制
+fn xattr_value(value: &[u8]) -> String {
+    let text = value.strip_suffix(&[0]).unwrap_or(value);
+    match std::str::from_utf8(text) {
+        Ok(text) if !text.chars().any(char::is_control) => text.to_string(),
+        _ => {
+            let hex: String = value.iter().map(|b| format!("{b:02x}")).collect();
+            format!("0x{hex}")
+        },
+    }
+}
+
+/// 识别 PAX 稀疏文件记录，只看条目自己的扩展头部
+fn pax_sparse(local: &PaxRecords) -> Option<SparseLayout> {
+    if local.str("GNU.sparse.major") == Some("1") {
+        return Some(SparseLayout::InData);
+    }
+    local.get("GNU.sparse.size")?;
+    if let Some(map) = local.str("GNU.sparse.map") {
+        // 0.1 版：`偏移,长度,偏移,长度,…`
+        let numbers = map
+            .split(',')
+            .map(|number| number.trim().parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        let segments = numbers.chunks_exact(2).map(|pair| (pair[0], pair[1]));
+        return Some(SparseLayout::Map(segments.collect()));
+    }
+
+    // 0.0 版：`GNU.sparse.offset` 与 `GNU.sparse.numbytes` 成对重复出现
+    let mut segments = Vec::new();
+    let mut offset = None;
+    for (key, value) in &local.0 {
+        let number = std::str::from_utf8(value)
+            .ok()
+            .and_then(|value| value.trim().parse::<u64>().ok());
+        match key.as_str() {
+            "GNU.sparse.offset" => offset = number,
+            "GNU.sparse.numbytes" => {
+                if let (Some(offset), Some(length)) = (offset.take(), number) {
+                    segments.push((offset, length));
+                }
+            },
+            _ => {},
+        }
+    }
+    Some(SparseLayout::Map(segments))
+}
+
+/// 读取 1.0 版稀疏文件数据开头的映射：段数，随后每段的偏移和长度，各占一行
+fn read_sparse_map<R: Read>(reader: &mut R) -> io::Result<Vec<(u64, u64)>> {
+    let mut consumed = 0u64;
+    let mut read_number = |reader: &mut R| -> io::Result<u64> {
+        let mut digits = String::new();
+        loop {
+            let mut byte = [0u8];
+            reader.read_exact(&mut byte)?;
+            consumed += 1;
+            match byte[0] {
+                b'\n' => break,
+                b @ b'0'..=b'9' if digits.len() < 20 => digits.push(char::from(b)),
+                _ => return Err(invalid_sparse_map()),
+            }
+        }
+        digits.parse().map_err(|_| invalid_sparse_map())
+    };
+
+    let count = read_number(reader)?;
+    if count > MAX_SPARSE_SEGMENTS {
+        return Err(invalid_sparse_map());
+    }
+    let mut segments = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let offset = read_number(reader)?;
+        let length = read_number(reader)?;
+        segments.push((offset, length));
+    }
+
+    // 映射补齐到块边界，之后才是数据段
+    let padding = (BLOCK_SIZE - consumed % BLOCK_SIZE) % BLOCK_SIZE;
+    io::copy(&mut reader.take(padding), &mut io::sink())?;
+    Ok(segments)
+}
+
+fn invalid_sparse_map() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Invalid tar sparse map")
+}
+
+/// 把稀疏文件的各数据段展开为完整内容，空洞部分补 0
+struct SparseReader<R> {
+    inner: R,
+    /// 尚未读完的数据段（偏移、长度），按偏移升序
+    segments: std::vec::IntoIter<(u64, u64)>,
+    current: Option<(u64, u64)>,
+    pos: u64,
+    size: u64,
+}
+
+impl<R: Read> SparseReader<R> {
+    fn new(inner: R, segments: Vec<(u64, u64)>, size: u64) -> io::Result<Self> {
+        let mut end = 0;
+        for &(offset, length) in &segments {
+            let segment_end = offset.checked_add(length).ok_or_else(invalid_sparse_map)?;
+            if offset < end || segment_end > size {
+                return Err(invalid_sparse_map());
+            }
+            end = segment_end;
+        }
+        let mut segments = segments.into_iter();
+        Ok(Self {
+            inner,
+            current: segments.next(),
+            segments,
+            pos: 0,
+            size,
+        })
+    }
+}
+
+impl<R: Read> Read for SparseReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.pos >= self.size || buf.is_empty() {
+                return Ok(0);
+            }
+            let (hole_end, data_end) = match self.current {
+                Some((offset, length)) if self.pos >= offset + length => {
+                    self.current = self.segments.next();
+                    continue;
+                },
+                Some((offset, length)) => (offset, offset + length),
+                None => (self.size, self.size),
+            };
+
+            if self.pos < hole_end {
+                let len = buf.len().min((hole_end - self.pos) as usize);
+                buf[..len].fill(0);
+                self.pos += len as u64;
+                return Ok(len);
+            }
+            let len = buf.len().min((data_end - self.pos) as usize);
+            let read = self.inner.read(&mut buf[..len])?;
+            if read == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Truncated tar sparse data",
+                ));
+            }
+            self.pos += read as u64;
+            return Ok(read);
+        }
+    }
+}
+
+/// 条目的数据流，PAX 稀疏文件会被展开
+fn entry_data<'e, R: Read + 'e>(
+    entry: &'e mut tar::Entry<'_, R>,
+    parsed: &mut TarEntry,
+) -> io::Result<Box<dyn Read + 'e>> {
+    let segments = match parsed.sparse.take() {
+        None => return Ok(Box::new(entry)),
+        Some(SparseLayout::Map(segments)) => segments,
+        Some(SparseLayout::InData) => read_sparse_map(entry)?,
+    };
+    Ok(Box::new(SparseReader::new(
+        entry,
+        segments,
+        parsed.item.size,
+    )?))
+}
+
+/// 顺序读取 TAR 流中的每个条目（头部校验和由 tar 检查）
+///
+/// 结束后读完剩余的数据，让外层的 gzip/xz 等解压层校验流末尾的 CRC。
+pub(crate) fn for_each_tar_entry<R: Read>(
+    reader: R,
+    f: &mut VisitFn<'_>,
+) -> Result<(), ArchiveError> {
     let mut archive = tar::Archive::new(reader);
-    let mut entries = Vec::new();
+    let mut entries = archive.entries()?;
+    let mut globals = PaxRecords::default();
 
-    for entry_result in archive.entries()? {
-        let entry = entry_result?;
-        let header = entry.header();
-        let name = entry.path()?.to_string_lossy().into_owned();
-        let size = header.size()?;
-        let mtime = header.mtime()?;
-        let is_dir = header.entry_type().is_dir();
+    while let Some((mut entry, mut parsed)) = next_entry(&mut entries, &mut globals)? {
+        if parsed.item.dir {
+            continue;
+        }
+        let keep_going = match entry_data(&mut entry, &mut parsed) {
+            Ok(mut data) => f(&parsed.item, Ok(&mut data))?,
+            Err(e) => f(&parsed.item, Err(e.into()))?,
+        };
+        if !keep_going {
+            return Ok(());
+        }
+    }
+
+    std::io::copy(&mut archive.into_inner(), &mut std::io::sink())?;
+    Ok(())
+}
 
-        let dt = UNIX_EPOCH + Duration::from_secs(mtime);
-        let last_modified = chrono::DateTime::<chrono::Local>::from(dt)
-            .format("%Y-%m-%d %H:%M:%S")
-            .to_string();
+/// 为 TAR 系列格式包上对应的解压层，返回未压缩的 TAR 数据流
+pub(crate) fn tar_stream<'a, R: Read + 'a>(
+    format: ArchiveFormat,
+    reader: R,
+) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+    let stream: Box<dyn Read + 'a> = match format {
+        ArchiveFormat::Tar => Box::new(reader),
+        ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(reader)),
+        ArchiveFormat::TarBz2 => Box::new(bzip2::read::BzDecoder::new(reader)),
+        ArchiveFormat::TarXz => Box::new(xz2::read::XzDecoder::new(reader)),
+        #[cfg(feature = "zstd")]
+        ArchiveFormat::TarZst => super::zst::zstd_decoder(reader)?,
+        #[cfg(not(feature = "zstd"))]
+        ArchiveFormat::TarZst => return Err(ArchiveError::FormatDisabled("zstd")),
+        #[cfg(feature = "lz4")]
+        ArchiveFormat::TarLz4 => super::lz4::lz4_decoder(reader),
+        #[cfg(not(feature = "lz4"))]
+        ArchiveFormat::TarLz4 => return Err(ArchiveError::FormatDisabled("lz4")),
+        #[cfg(feature = "lzma")]
+        ArchiveFormat::TarLzma => lzma_decoder(reader)?,
+        #[cfg(not(feature = "lzma"))]
+        ArchiveFormat::TarLzma => return Err(ArchiveError::FormatDisabled("lzma")),
+        #[cfg(feature = "lzip")]
+        ArchiveFormat::TarLzip => super::lzip::lzip_decoder(reader)?,
+        #[cfg(not(feature = "lzip"))]
+        ArchiveFormat::TarLzip => return Err(ArchiveError::FormatDisabled("lzip")),
+        #[cfg(feature = "brotli")]
+        ArchiveFormat::TarBr => super::brotli::brotli_decoder(reader),
+        #[cfg(not(feature = "brotli"))]
+        ArchiveFormat::TarBr => return Err(ArchiveError::FormatDisabled("brotli")),
+        #[cfg(feature = "compress")]
+        ArchiveFormat::TarZ => super::lzw::lzw_decoder(reader)?,
+        #[cfg(not(feature = "compress"))]
+        ArchiveFormat::TarZ => return Err(ArchiveError::FormatDisabled("compress")),
+        other => return Err(ArchiveError::UnsupportedFormat(format!("{other:?}"))),
+    };
+    Ok(stream)
+}
 
-        entries.push(Extract::new(name, size, last_modified, is_dir));
+/// 旧式 LZMA（`.lzma`）解码器，deb 的 `data.tar.lzma` 和部分 RPM 载荷也使用
+#[cfg(any(feature = "deb", feature = "rpm", feature = "lzma"))]
+pub(crate) fn lzma_decoder<'a, R: Read + 'a>(
+    reader: R,
+) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+    let stream = xz2::stream::Stream::new_lzma_decoder(u64::MAX).map_err(std::io::Error::from)?;
+    Ok(Box::new(xz2::read::XzDecoder::new_stream(reader, stream)))
+}
+
+/// 顺序扫描 TAR 流，找到指定条目后把它的数据流交给 `f` 处理
+pub(crate) fn with_tar_entry<R: Read, T>(
+    reader: R,
+    entry_name: &str,
+    f: &mut dyn FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut archive = tar::Archive::new(reader);
+    let mut entries = archive.entries()?;
+    let mut globals = PaxRecords::default();
+
+    while let Some((mut entry, mut parsed)) = next_entry(&mut entries, &mut globals)? {
+        if parsed.item.name != entry_name {
+            continue;
+        }
+        if parsed.item.dir {
+            return Err(ArchiveError::InvalidPath(entry_name.to_string()));
+        }
+        super::ensure_not_link(&parsed.item)?;
+        return f(&mut entry_data(&mut entry, &mut parsed)?);
     }
 
-    Ok(entries)
+    Err(ArchiveError::EntryNotFound(entry_name.to_string()))
+}
+
+/// 顺序扫描 TAR 流，找到指定条目后写入目标目录
+pub(crate) fn extract_tar_entry_from<R: Read>(
+    reader: R,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    with_tar_entry(reader, entry_name, &mut |data| {
+        super::write_entry_to_dir(data, entry_name, dest_dir)
+    })
 }
 
 /// 列举 TAR 文件条目
@@ -34,17 +507,191 @@ pub fn list_tar_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, Archive
     parse_tar_entries(File::open(path)?)
 }
 
-/// 列举 TAR.GZ 文件条目
+/// 列举 TAR.GZ 文件条目（内容不是 TAR 时返回一个虚拟条目）
 pub fn list_tar_gz_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
-    parse_tar_entries(flate2::read::GzDecoder::new(File::open(path)?))
+    super::compressed::list_compressed_entries(ArchiveFormat::TarGz, path.as_ref())
 }
 
-/// 列举 TAR.BZ2 文件条目
+/// 列举 TAR.BZ2 文件条目（内容不是 TAR 时返回一个虚拟条目）
 pub fn list_tar_bz2_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
-    parse_tar_entries(bzip2::read::BzDecoder::new(File::open(path)?))
+    super::compressed::list_compressed_entries(ArchiveFormat::TarBz2, path.as_ref())
 }
 
-/// 列举 TAR.XZ 文件条目
+/// 列举 TAR.XZ 文件条目（内容不是 TAR 时返回一个虚拟条目）
 pub fn list_tar_xz_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
-    parse_tar_entries(xz2::read::XzDecoder::new(File::open(path)?))
+    super::compressed::list_compressed_entries(ArchiveFormat::TarXz, path.as_ref())
+}
+
+/// 解压 TAR 中的单个文件到目标目录
+pub fn extract_tar_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    extract_tar_entry_from(File::open(path)?, entry_name, dest_dir)
+}
+
+/// 解压 TAR.GZ 中的单个文件到目标目录
+pub fn extract_tar_gz_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarGz,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
+
+/// 解压 TAR.BZ2 中的单个文件到目标目录
+pub fn extract_tar_bz2_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarBz2,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
+
+/// 解压 TAR.XZ 中的单个文件到目标目录
+pub fn extract_tar_xz_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarXz,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
+}
+
+/// 未压缩的 TAR 读取器，压缩的 TAR 由 [`super::compressed::CompressedReader`] 处理
+pub struct TarReader<'a> {
+    input: StreamInput<'a>,
+}
+
+impl<'a> ArchiveReader<'a> for TarReader<'a> {
+    fn open(input: Input<'a>, _password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self { input: StreamInput::new(input) })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Tar)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        parse_tar_entries(self.input.take()?)
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        each_tar_entry(self.input.take()?, f)
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        Ok(false)
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        with_tar_entry(self.input.take()?, entry_name, f)
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        for_each_tar_entry(self.input.take()?, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_tar_entry_from, pax_time, read_sparse_map, xattr_value, SparseReader};
+    use crate::ArchiveError;
+    use std::io::Read;
+
+    /// `src/real` 文件，以及指向它的符号链接 `src/evil` 和硬链接 `src/hard`
+    fn tar_with_links() -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "src/real", &b"real"[..])
+            .unwrap();
+        for (name, kind, target) in [
+            ("src/evil", tar::EntryType::Symlink, "../../etc/passwd"),
+            ("src/hard", tar::EntryType::Link, "src/real"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(kind);
+            header.set_size(0);
+            header.set_mode(0o777);
+            builder.append_link(&mut header, name, target).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn refuses_to_extract_links() {
+        let data = tar_with_links();
+        let dest = std::env::temp_dir().join(format!("quicklook-tar-links-{}", std::process::id()));
+
+        let path = extract_tar_entry_from(&data[..], "src/real", &dest).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"real");
+        for (name, target) in [("src/evil", "../../etc/passwd"), ("src/hard", "src/real")] {
+            match extract_tar_entry_from(&data[..], name, &dest) {
+                Err(ArchiveError::LinkEntry { entry, target: Some(link) }) => {
+                    assert_eq!((entry.as_str(), link.as_str()), (name, target));
+                },
+                other => panic!("{name}: {other:?}"),
+            }
+        }
+        assert!(!dest.join("evil").exists() && !dest.join("hard").exists());
+        std::fs::remove_dir_all(&dest).unwrap();
+    }
+
+    #[test]
+    fn parses_pax_times() {
+        let time = pax_time("1700000000.5").unwrap().to_string();
+        assert_eq!(time, "2023-11-14T22:13:20.500Z");
+        assert_eq!(
+            pax_time("-1.25").unwrap().to_string(),
+            "1969-12-31T23:59:58.750Z"
+        );
+        assert!(pax_time("0").is_none());
+        assert!(pax_time("12.3x").is_none());
+    }
+
+    #[test]
+    fn formats_xattr_values() {
+        assert_eq!(
+            xattr_value(b"system_u:object_r:etc_t:s0\0"),
+            "system_u:object_r:etc_t:s0"
+        );
+        assert_eq!(xattr_value(&[1, 0, 0, 2]), "0x01000002");
+    }
+
+    #[test]
+    fn expands_sparse_data() {
+        let mut data = b"2\n1\n2\n6\n1\n".to_vec();
+        data.resize(512, 0);
+        data.extend_from_slice(b"abc");
+        let mut reader = &data[..];
+        let segments = read_sparse_map(&mut reader).unwrap();
+        assert_eq!(segments, [(1, 2), (6, 1)]);
+
+        let mut output = Vec::new();
+        SparseReader::new(reader, segments, 8)
+            .unwrap()
+            .read_to_end(&mut output)
+            .unwrap();
+        assert_eq!(output, b"\0ab\0\0\0c\0");
+
+        assert!(SparseReader::new(&b""[..], vec![(4, 2), (1, 1)], 8).is_err());
+    }
 }
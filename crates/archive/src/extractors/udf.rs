@@ -0,0 +1,551 @@
+//! UDF 文件系统（ECMA-167 / OSTA UDF），DVD、蓝光以及 Windows 安装映像使用
+//!
+//! 支持物理分区、可擦写光盘的备用分区（不处理备用表）以及 UDF 2.50 的元数据分区；
+//! CD-R 追记使用的虚拟分区（VAT）不支持。
+
+use super::iso::{
+    read_at, u16_le, u32_le, Extent, ExtentReader, ImageEntry, FIRST_DESCRIPTOR_SECTOR,
+    MAX_DIRECTORY_SIZE, SECTOR_SIZE,
+};
+use crate::reader::ReadSeek;
+use crate::{ArchiveError, EntryTime, Extract};
+use chrono::NaiveDate;
+use std::{collections::HashSet, io::Read};
+
+/// 描述符标识（ECMA-167 3/7.2.1、4/7.2.1）
+const TAG_ANCHOR: u16 = 2;
+const TAG_PARTITION: u16 = 5;
+const TAG_LOGICAL_VOLUME: u16 = 6;
+const TAG_TERMINATING: u16 = 8;
+const TAG_FILE_SET: u16 = 256;
+const TAG_FILE_IDENTIFIER: u16 = 257;
+const TAG_ALLOCATION_EXTENT: u16 = 258;
+const TAG_INDIRECT: u16 = 259;
+const TAG_FILE_ENTRY: u16 = 261;
+const TAG_EXTENDED_FILE_ENTRY: u16 = 266;
+
+/// ICB 记录的文件类型
+const FILE_TYPE_DIRECTORY: u8 = 4;
+const FILE_TYPE_SYMLINK: u8 = 12;
+
+/// 锚点描述符位于第 256 个逻辑扇区，扇区大小未知时依次尝试
+const ANCHOR_SECTOR: u64 = 256;
+const SECTOR_SIZES: [u64; 4] = [2048, 512, 1024, 4096];
+
+/// 卷描述符序列、分配描述符续接的最大数量，防止损坏的映像造成死循环
+const MAX_DESCRIPTORS: u64 = 256;
+const MAX_ALLOCATION_EXTENTS: usize = 1024;
+const MAX_INDIRECT_ENTRIES: usize = 8;
+
+/// 卷识别序列中出现 NSR02/NSR03 时表示映像含有 UDF 文件系统
+pub(crate) fn has_udf(image: &mut dyn ReadSeek) -> bool {
+    for index in 0..64 {
+        let offset = (FIRST_DESCRIPTOR_SECTOR + index) * SECTOR_SIZE;
+        let Ok(descriptor) = read_at(image, offset, 6) else {
+            return false;
+        };
+        match &descriptor[1..6] {
+            b"NSR02" | b"NSR03" => return true,
+            b"BEA01" | b"TEA01" | b"CD001" | b"BOOT2" | b"CDW02" => {},
+            _ => return false,
+        }
+    }
+    false
+}
+
+/// 检查描述符标签的校验和，返回标签标识
+fn tag_id(data: &[u8]) -> Option<u16> {
+    let header = data.get(..16)?;
+    let checksum = header
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 4)
+        .fold(0u8, |sum, (_, b)| sum.wrapping_add(*b));
+    (checksum == header[4]).then(|| u16_le(header, 0))
+}
+
+/// 分区内的逻辑块地址（lb_addr）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct BlockAddr {
+    block: u32,
+    partition: u16,
+}
+
+/// long_ad：长度（高 2 位为类型）和地址
+fn long_ad(data: &[u8]) -> (u32, BlockAddr) {
+    let address = BlockAddr {
+        block: u32_le(data, 4),
+        partition: u16_le(data, 8),
+    };
+    (u32_le(data, 0), address)
+}
+
+/// 逻辑卷的分区映射
+enum Partition {
+    /// 物理分区，值为起始扇区
+    Physical(u64),
+    /// UDF 2.50 元数据分区：元数据文件在物理分区中的各段（起始扇区、扇区数）
+    Metadata(Vec<(u64, u64)>),
+    /// 不支持的分区类型，值为类型标识
+    Unsupported(String),
+}
+
+/// 已解析的 UDF 卷
+struct Udf<'r> {
+    image: &'r mut dyn ReadSeek,
+    block_size: u64,
+    partitions: Vec<Partition>,
+}
+
+/// 文件条目（File Entry / Extended File Entry）中用到的字段
+struct FileNode {
+    file_type: u8,
+    size: u64,
+    uid: Option<u64>,
+    gid: Option<u64>,
+    permissions: u32,
+    modified: Option<EntryTime>,
+    extents: Vec<Extent>,
+}
+
+impl Udf<'_> {
+    fn read_block(&mut self, sector: u64) -> Result<Vec<u8>, ArchiveError> {
+        read_at(
+            self.image,
+            sector * self.block_size,
+            self.block_size as usize,
+        )
+    }
+
+    /// 把分区内的逻辑块换算为映像中的扇区
+    fn locate(&self, address: BlockAddr) -> Result<u64, ArchiveError> {
+        let block = u64::from(address.block);
+        match self.partitions.get(usize::from(address.partition)) {
+            Some(Partition::Physical(start)) => Ok(start + block),
+            Some(Partition::Metadata(extents)) => {
+                let mut skipped = 0;
+                for (start, blocks) in extents {
+                    if block < skipped + blocks {
+                        return Ok(start + block - skipped);
+                    }
+                    skipped += blocks;
+                }
+                Err(ArchiveError::Other(format!(
+                    "UDF metadata block {block} is outside the metadata file"
+                )))
+            },
+            Some(Partition::Unsupported(kind)) => Err(ArchiveError::UnsupportedFormat(format!(
+                "UDF partition type {kind}"
+            ))),
+            None => Err(ArchiveError::Other(format!(
+                "UDF partition reference {} does not exist",
+                address.partition
+            ))),
+        }
+    }
+
+    /// 把分区内从 `address` 开始的 `len` 字节换算为映像中的数据段，
+    /// 元数据分区中跨越元数据文件不同段的部分会拆开
+    fn data_extents(&self, address: BlockAddr, len: u64) -> Result<Vec<Extent>, ArchiveError> {
+        if let Some(Partition::Physical(start)) =
+            self.partitions.get(usize::from(address.partition))
+        {
+            let offset = (start + u64::from(address.block)) * self.block_size;
+            return Ok(vec![Extent::Data { offset, len }]);
+        }
+        let mut extents = Vec::new();
+        let mut remaining = len;
+        let mut block = address.block;
+        while remaining > 0 {
+            let sector = self.locate(BlockAddr { block, ..address })?;
+            let chunk = remaining.min(self.block_size);
+            match extents.last_mut() {
+                Some(Extent::Data { offset, len })
+                    if *offset + *len == sector * self.block_size =>
+                {
+                    *len += chunk
+                },
+                _ => extents.push(Extent::Data {
+                    offset: sector * self.block_size,
+                    len: chunk,
+                }),
+            }
+            remaining -= chunk;
+            block += 1;
+        }
+        Ok(extents)
+    }
+
+    /// 读取 ICB 指向的文件条目，跟随间接条目
+    fn read_node(&mut self, mut icb: BlockAddr) -> Result<FileNode, ArchiveError> {
+        for _ in 0..MAX_INDIRECT_ENTRIES {
+            let data = self.read_block(self.locate(icb)?)?;
+            let (ea_offset, ad_header, mtime_offset) = match tag_id(&data) {
+                Some(TAG_FILE_ENTRY) => (168, 176, 84),
+                Some(TAG_EXTENDED_FILE_ENTRY) => (208, 216, 92),
+                Some(TAG_INDIRECT) => {
+                    icb = long_ad(&data[36..52]).1;
+                    continue;
+                },
+                _ => {
+                    return Err(ArchiveError::Other(format!(
+                        "Invalid UDF file entry at block {}",
+                        icb.block
+                    )))
+                },
+            };
+
+            let ea_len = u32_le(&data, ea_offset) as usize;
+            let ad_len = u32_le(&data, ea_offset + 4) as usize;
+            let ads = data
+                .get(ad_header + ea_len..ad_header + ea_len + ad_len)
+                .ok_or_else(|| ArchiveError::Other("UDF allocation descriptors overflow".into()))?;
+            let size = u64::from_le_bytes(data[56..64].try_into().unwrap_or_default());
+            let extents = match u16_le(&data, 34) & 0x07 {
+                // 数据直接记录在文件条目中
+                3 => vec![Extent::Inline(ads.to_vec())],
+                ad_type => self.allocation_extents(ad_type, ads.to_vec(), icb.partition)?,
+            };
+            // 0xFFFFFFFF 表示没有记录
+            let id =
+                |offset| Some(u64::from(u32_le(&data, offset))).filter(|id| *id != 0xFFFF_FFFF);
+            return Ok(FileNode {
+                file_type: data[27],
+                size,
+                uid: id(36),
+                gid: id(40),
+                permissions: u32_le(&data, 44),
+                modified: timestamp(&data[mtime_offset..mtime_offset + 12]),
+                extents,
+            });
+        }
+        Err(ArchiveError::Other("Too many UDF indirect entries".into()))
+    }
+
+    /// 解析短（0）、长（1）、扩展（2）分配描述符，跟随分配扩展描述符的续接
+    fn allocation_extents(
+        &mut self,
+        ad_type: u16,
+        mut ads: Vec<u8>,
+        partition: u16,
+    ) -> Result<Vec<Extent>, ArchiveError> {
+        let ad_size = match ad_type {
+            0 => 8,
+            1 => 16,
+            2 => 20,
+            _ => {
+                return Err(ArchiveError::Other(format!(
+                    "Invalid UDF allocation type {ad_type}"
+                )))
+            },
+        };
+        let mut extents = Vec::new();
+        for _ in 0..MAX_ALLOCATION_EXTENTS {
+            let mut next = None;
+            for ad in ads.chunks_exact(ad_size) {
+                let (raw_len, address) = match ad_type {
+                    0 => (u32_le(ad, 0), BlockAddr { block: u32_le(ad, 4), partition }),
+                    1 => long_ad(ad),
+                    _ => (
+                        u32_le(ad, 0),
+                        BlockAddr {
+                            block: u32_le(ad, 12),
+                            partition: u16_le(ad, 16),
+                        },
+                    ),
+                };
+                let len = u64::from(raw_len & 0x3FFF_FFFF);
+                if len == 0 {
+                    break;
+                }
+                match raw_len >> 30 {
+                    0 => extents.extend(self.data_extents(address, len)?),
+                    // 已分配未写入、未分配的段读作零
+                    1 | 2 => extents.push(Extent::Zero(len)),
+                    // 后续描述符记录在分配扩展描述符中
+                    _ => {
+                        next = Some(address);
+                        break;
+                    },
+                }
+            }
+            let Some(address) = next else {
+                return Ok(extents);
+            };
+            let data = self.read_block(self.locate(address)?)?;
+            if tag_id(&data) != Some(TAG_ALLOCATION_EXTENT) {
+                return Err(ArchiveError::Other("Invalid UDF allocation extent".into()));
+            }
+            let len = u32_le(&data, 20) as usize;
+            ads = data.get(24..24 + len).unwrap_or_default().to_vec();
+        }
+        Err(ArchiveError::Other(
+            "Too many UDF allocation extents".into(),
+        ))
+    }
+
+    fn read_data(&mut self, node: &FileNode) -> Result<Vec<u8>, ArchiveError> {
+        if node.size > MAX_DIRECTORY_SIZE {
+            return Err(ArchiveError::Other("UDF directory is too large".into()));
+        }
+        let mut data = Vec::with_capacity(node.size as usize);
+        ExtentReader::new(self.image, &node.extents, node.size).read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// 时间戳（ECMA-167 1/7.3）：类型和时区（分钟，-2047 表示未指定）、年、月、日、时、分、秒
+/// 以及厘秒、百微秒、微秒
+fn timestamp(raw: &[u8]) -> Option<EntryTime> {
+    let type_and_zone = u16_le(raw, 0);
+    let year = i16::from_le_bytes([raw[2], raw[3]]);
+    let date = NaiveDate::from_ymd_opt(i32::from(year), u32::from(raw[4]), u32::from(raw[5]))?;
+    let micros = u32::from(raw[9]) * 10_000 + u32::from(raw[10]) * 100 + u32::from(raw[11]);
+    let time = date.and_hms_micro_opt(
+        u32::from(raw[6]),
+        u32::from(raw[7]),
+        u32::from(raw[8]),
+        micros,
+    )?;
+    // 低 12 位是有符号的时区偏移，类型 1 表示本地时间
+    let zone = i32::from(((type_and_zone & 0x0FFF) << 4) as i16 >> 4);
+    let offset = (type_and_zone >> 12 == 1 && zone != -2047).then_some(zone);
+    Some(EntryTime::from_local(time, offset))
+}
+
+/// OSTA 压缩 Unicode：首字节 8/254 表示每字符 1 字节，16/255 表示每字符 2 字节（大端）
+fn dstring(raw: &[u8]) -> String {
+    match raw.split_first() {
+        Some((8 | 254, rest)) => rest.iter().map(|&b| char::from(b)).collect(),
+        Some((16 | 255, rest)) => {
+            let units = rest
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+            char::decode_utf16(units)
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect()
+        },
+        _ => String::new(),
+    }
+}
+
+/// 符号链接的路径组件（ECMA-167 4/14.16）：类型 1 为根目录，2 为 `..`，3 为 `.`，5 为名称
+fn symlink_target(data: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let (kind, len) = (data[pos], usize::from(data[pos + 1]));
+        let identifier = data.get(pos + 4..pos + 4 + len).unwrap_or_default();
+        parts.push(match kind {
+            1 => String::new(),
+            2 => "..".to_string(),
+            3 => ".".to_string(),
+            _ => dstring(identifier),
+        });
+        pos += 4 + len;
+    }
+    match parts.as_slice() {
+        [root] if root.is_empty() => "/".to_string(),
+        _ => parts.join("/"),
+    }
+}
+
+/// UDF 权限按其他用户、组、所有者各 5 位（执行、写、读、改属性、删除）排列，
+/// 换算为 Unix 权限位并加上文件类型
+fn unix_mode(node: &FileNode) -> u32 {
+    let permissions = node.permissions;
+    let bits = ((permissions >> 10) & 7) << 6 | ((permissions >> 5) & 7) << 3 | (permissions & 7);
+    let kind = match node.file_type {
+        FILE_TYPE_DIRECTORY => 0o040_000,
+        FILE_TYPE_SYMLINK => 0o120_000,
+        _ => 0o100_000,
+    };
+    kind | bits
+}
+
+/// 找到锚点描述符，读取卷描述符序列中的分区和逻辑卷，返回卷和文件集描述符的位置
+fn open_volume(image: &mut dyn ReadSeek) -> Result<(Udf<'_>, BlockAddr), ArchiveError> {
+    let (block_size, anchor) = SECTOR_SIZES
+        .iter()
+        .find_map(|&size| {
+            let data = read_at(image, ANCHOR_SECTOR * size, 512).ok()?;
+            let valid =
+                tag_id(&data) == Some(TAG_ANCHOR) && u64::from(u32_le(&data, 12)) == ANCHOR_SECTOR;
+            valid.then_some((size, data))
+        })
+        .ok_or_else(|| ArchiveError::Other("UDF anchor volume descriptor not found".into()))?;
+
+    let sequence_start = u64::from(u32_le(&anchor, 20));
+    let sequence_len = u64::from(u32_le(&anchor, 16)) / block_size;
+    let mut physical = Vec::new();
+    let mut logical_volume = None;
+    for sector in sequence_start..sequence_start + sequence_len.min(MAX_DESCRIPTORS) {
+        let data = read_at(image, sector * block_size, block_size as usize)?;
+        match tag_id(&data) {
+            Some(TAG_PARTITION) => {
+                physical.push((u16_le(&data, 22), u64::from(u32_le(&data, 188))))
+            },
+            Some(TAG_LOGICAL_VOLUME) if logical_volume.is_none() => logical_volume = Some(data),
+            Some(TAG_TERMINATING) | None => break,
+            _ => {},
+        }
+    }
+    let volume = logical_volume
+        .ok_or_else(|| ArchiveError::Other("UDF logical volume descriptor not found".into()))?;
+    if u64::from(u32_le(&volume, 212)) != block_size {
+        return Err(ArchiveError::UnsupportedFormat(format!(
+            "UDF logical block size {}",
+            u32_le(&volume, 212)
+        )));
+    }
+    let partition_start = |number: u16| {
+        physical
+            .iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, start)| *start)
+            .ok_or_else(|| ArchiveError::Other(format!("UDF partition {number} not found")))
+    };
+
+    // 分区映射：类型 1 为物理分区，类型 2 按标识区分元数据、备用、虚拟分区
+    let map_count = u32_le(&volume, 268);
+    let mut maps = Vec::new();
+    // 元数据分区的映射序号、所在物理分区号以及元数据文件的位置
+    let mut metadata_files = Vec::new();
+    let mut pos = 440;
+    for _ in 0..map_count {
+        let Some(&[kind, len]) = volume.get(pos..pos + 2) else {
+            break;
+        };
+        let map = volume.get(pos..pos + usize::from(len)).unwrap_or_default();
+        maps.push(match (kind, map.len()) {
+            (1, 6..) => Partition::Physical(partition_start(u16_le(map, 4))?),
+            (2, 64..) => {
+                let identifier = String::from_utf8_lossy(&map[5..28]);
+                let identifier = identifier.trim_end_matches('\0');
+                match identifier {
+                    "*UDF Sparable Partition" => {
+                        Partition::Physical(partition_start(u16_le(map, 38))?)
+                    },
+                    // 映射表读完后再从元数据文件展开
+                    "*UDF Metadata Partition" => {
+                        metadata_files.push((maps.len(), u16_le(map, 38), u32_le(map, 40)));
+                        Partition::Metadata(Vec::new())
+                    },
+                    _ => Partition::Unsupported(identifier.to_string()),
+                }
+            },
+            _ => Partition::Unsupported(format!("{kind}")),
+        });
+        pos += usize::from(len.max(2));
+    }
+
+    let file_set = long_ad(&volume[248..264]).1;
+    let mut udf = Udf { image, block_size, partitions: maps };
+
+    // 元数据分区的内容由物理分区中的元数据文件描述
+    for (index, number, location) in metadata_files {
+        let start = partition_start(number)?;
+        let physical_ref = udf
+            .partitions
+            .iter()
+            .position(|map| matches!(map, Partition::Physical(s) if *s == start))
+            .ok_or_else(|| {
+                ArchiveError::Other("UDF metadata partition has no physical map".into())
+            })?;
+        let address = BlockAddr {
+            block: location,
+            partition: physical_ref as u16,
+        };
+        let extents = udf
+            .read_node(address)?
+            .extents
+            .iter()
+            .filter_map(|extent| match extent {
+                Extent::Data { offset, len } => {
+                    Some((offset / block_size, len.div_ceil(block_size)))
+                },
+                _ => None,
+            })
+            .collect();
+        udf.partitions[index] = Partition::Metadata(extents);
+    }
+
+    Ok((udf, file_set))
+}
+
+/// 遍历 UDF 目录树，按目录顺序返回全部条目
+pub(crate) fn read_udf_entries(image: &mut dyn ReadSeek) -> Result<Vec<ImageEntry>, ArchiveError> {
+    let (mut udf, file_set) = open_volume(image)?;
+    let data = udf.read_block(udf.locate(file_set)?)?;
+    if tag_id(&data) != Some(TAG_FILE_SET) {
+        return Err(ArchiveError::Other(
+            "UDF file set descriptor not found".into(),
+        ));
+    }
+    let root = long_ad(&data[400..416]).1;
+
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    let mut pending = vec![(String::new(), root)];
+    while let Some((prefix, icb)) = pending.pop() {
+        if !visited.insert(icb) {
+            continue;
+        }
+        let node = udf.read_node(icb)?;
+        let data = udf.read_data(&node)?;
+        let mut children = Vec::new();
+
+        // 文件标识描述符（ECMA-167 4/14.4），每条按 4 字节对齐
+        let mut pos = 0;
+        while pos + 38 <= data.len() {
+            let record = &data[pos..];
+            if tag_id(record) != Some(TAG_FILE_IDENTIFIER) {
+                break;
+            }
+            let characteristics = record[18];
+            let name_len = usize::from(record[19]);
+            let child = long_ad(&record[20..36]).1;
+            let name_start = 38 + usize::from(u16_le(record, 36));
+            let Some(raw_name) = record.get(name_start..name_start + name_len) else {
+                break;
+            };
+            pos += (name_start + name_len + 3) & !3;
+            // 跳过已删除（0x04）和父目录（0x08）的标识
+            if characteristics & 0x0C != 0 {
+                continue;
+            }
+
+            let path = format!("{prefix}{}", dstring(raw_name));
+            let child_node = match udf.read_node(child) {
+                Ok(node) => node,
+                Err(e) => {
+                    log::warn!("Failed to read UDF file entry {}: {}", path, e);
+                    continue;
+                },
+            };
+            let is_dir = child_node.file_type == FILE_TYPE_DIRECTORY;
+            let mut item = if is_dir {
+                Extract::new(format!("{path}/"), 0, child_node.modified, true)
+            } else {
+                Extract::new(path, child_node.size, child_node.modified, false)
+            };
+            item.set_mode(unix_mode(&child_node));
+            item.uid = child_node.uid;
+            item.gid = child_node.gid;
+
+            if is_dir {
+                children.push((item.name.clone(), child));
+                entries.push(ImageEntry { item, extents: Vec::new() });
+            } else if child_node.file_type == FILE_TYPE_SYMLINK {
+                item.link_target = Some(symlink_target(&udf.read_data(&child_node)?));
+                item.size = 0;
+                entries.push(ImageEntry { item, extents: Vec::new() });
+            } else {
+                entries.push(ImageEntry { item, extents: child_node.extents });
+            }
+        }
+        pending.extend(children.into_iter().rev());
+    }
+
+    Ok(entries)
+}
@@ -1,40 +1,316 @@
-use crate::{ArchiveError, Extract};
-use std::{fs::File, path::Path};
-use zip::ZipArchive;
+use crate::encoding::{NameDecoder, NameEncoding};
+use crate::reader::{
+    collect_entries, ArchiveMetadata, ArchiveReader, EntryFn, Input, ListFn, PasswordProtection,
+    SeekInput, VisitFn,
+};
+use crate::{volume, ArchiveError, ArchiveFormat, EntryTime, Extract};
+use std::{
+    io::{Read, Seek},
+    path::{Path, PathBuf},
+};
+use zip::{extra_fields::ExtraField, read::ZipFile, CompressionMethod, DateTime, ZipArchive};
 
-fn decode_entry_name(raw: &[u8]) -> String {
-    match std::str::from_utf8(raw) {
-        Ok(s) => s.to_owned(),
-        Err(_) => {
-            let (decoded, _, _) = encoding_rs::GBK.decode(raw);
-            decoded.into_owned()
-        },
+/// 已确定为 UTF-8 的条目名：设置了 UTF-8 标志位，或带有校验通过的 0x7075 扩展字段
+///
+/// zip 读取中央目录时会用 0x7075 中的 UTF-8 名称替换原始字节并视为 UTF-8；
+/// 其余条目名按 CP437 解码，解码结果与原始字节相同只可能是纯 ASCII。
+fn unicode_name<'a>(file: &'a ZipFile<'_>) -> Option<&'a str> {
+    let name = file.name();
+    (name.as_bytes() == file.name_raw()).then_some(name)
+}
+
+fn decode_entry_name(file: &ZipFile<'_>, decoder: &NameDecoder) -> String {
+    match unicode_name(file) {
+        Some(name) => name.to_string(),
+        None => decoder.decode(file.name_raw()),
     }
 }
 
-/// 列举 ZIP 文件条目
+/// 根据全部需要猜测编码的条目名确定解码规则，`encoding` 不为 `Auto` 时直接使用
+fn name_decoder<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    encoding: NameEncoding,
+) -> Result<NameDecoder, ArchiveError> {
+    // 条目名全是 ASCII 时（最常见）不必逐个读取条目
+    if encoding != NameEncoding::Auto || archive.file_names().all(str::is_ascii) {
+        return Ok(NameDecoder::for_raw(encoding, []));
+    }
+    let mut raw_names = Vec::new();
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        if unicode_name(&file).is_none() {
+            raw_names.push(file.name_raw().to_vec());
+        }
+    }
+    Ok(NameDecoder::for_raw(
+        encoding,
+        raw_names.iter().map(Vec::as_slice),
+    ))
+}
+
+/// 压缩方法名称，与其他格式统一为小写（`Stored` → `store`，`Deflated` → `deflate`）
+fn compression_method_name(method: CompressionMethod) -> String {
+    match method {
+        CompressionMethod::Stored => "store".to_string(),
+        CompressionMethod::Deflated => "deflate".to_string(),
+        other => format!("{other:?}").to_lowercase(),
+    }
+}
+
+/// 条目修改时间：优先使用扩展字段中的 UTC 时间（0x5455 扩展时间戳、0x000a NTFS），
+/// 否则使用只有本地时间的 DOS 时间
+fn zip_entry_time<'a>(
+    mut extra_fields: impl Iterator<Item = &'a ExtraField>,
+    dos: Option<DateTime>,
+) -> Option<EntryTime> {
+    let extra = extra_fields.find_map(|field| match field {
+        ExtraField::ExtendedTimestamp(ts) => ts
+            .mod_time()
+            .and_then(|secs| EntryTime::from_unix(i64::from(secs))),
+        ExtraField::Ntfs(ntfs) => EntryTime::from_filetime(ntfs.mtime()),
+    });
+    extra.or_else(|| dos.and_then(|dos| EntryTime::from_dos(dos.datepart(), dos.timepart())))
+}
+
+/// 加密方式：通用标志位第 0 位表示加密；AES 扩展字段（0x9901）记录了密钥长度，
+/// 强加密扩展字段（0x0017）表示 PKWARE 强加密，都没有时为传统的 ZipCrypto
+fn zip_encryption_method(encrypted: bool, extra: Option<&[u8]>) -> Option<&'static str> {
+    if !encrypted {
+        return None;
+    }
+    let mut extra = extra.unwrap_or_default();
+    while extra.len() >= 4 {
+        let id = u16::from_le_bytes([extra[0], extra[1]]);
+        let len = usize::from(u16::from_le_bytes([extra[2], extra[3]]));
+        let data = extra.get(4..4 + len).unwrap_or_default();
+        match id {
+            // 厂商版本(2) 厂商 ID "AE"(2) 密钥长度(1) 实际压缩方法(2)
+            0x9901 => {
+                return Some(match data.get(4) {
+                    Some(1) => "aes-128",
+                    Some(2) => "aes-192",
+                    _ => "aes-256",
+                })
+            },
+            0x0017 => return Some("pkware-strong"),
+            _ => {},
+        }
+        extra = extra.get(4 + len..).unwrap_or_default();
+    }
+    Some("zipcrypto")
+}
+
+/// 中央目录中记录的条目信息
+fn zip_entry(file: &ZipFile<'_>, decoder: &NameDecoder) -> Extract {
+    let last_modified = zip_entry_time(file.extra_data_fields(), file.last_modified());
+    let mut entry = Extract::new(
+        decode_entry_name(file, decoder),
+        file.size(),
+        last_modified,
+        file.is_dir(),
+    );
+    entry.compressed_size = Some(file.compressed_size());
+    entry.compression_method = Some(compression_method_name(file.compression()));
+    entry.crc32 = Some(file.crc32());
+    if let Some(mode) = file.unix_mode() {
+        entry.set_mode(mode);
+    }
+    entry.encrypted = file.encrypted();
+    entry.encryption_method =
+        zip_encryption_method(file.encrypted(), file.extra_data()).map(str::to_string);
+    entry
+}
+
+/// 列举 ZIP 文件条目，`path` 可以是 `.z01`/`.zip` 分卷中的任意一卷
 pub fn list_zip_entries<P: AsRef<Path>>(
     path: P,
     password: Option<&str>,
 ) -> Result<Vec<Extract>, ArchiveError> {
-    let file = File::open(path)?;
-    let mut archive = ZipArchive::new(file)?;
-    let mut entries = Vec::with_capacity(archive.len());
+    ZipReader::open(volume::open_input(path.as_ref())?, password)?.list()
+}
+
+/// 从任意可寻址的数据源列举 ZIP 条目（用于嵌套在其他归档中的 ZIP）
+pub(crate) fn list_zip_entries_from<R: Read + Seek>(
+    reader: R,
+    password: Option<&str>,
+    encoding: NameEncoding,
+) -> Result<Vec<Extract>, ArchiveError> {
+    collect_entries(|f| each_zip_entry(reader, password, encoding, f))
+}
 
+/// 按中央目录的顺序逐条列举 ZIP 条目
+pub(crate) fn each_zip_entry<R: Read + Seek>(
+    reader: R,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut ListFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let decoder = name_decoder(&mut archive, encoding)?;
+
+    // 中央目录是明文的，列举时不需要解密条目；密码只用于读取加密的符号链接目标
     for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        let mut entry = zip_entry(&file, &decoder);
+        let is_symlink = file.is_symlink();
+        drop(file);
+
+        // 符号链接的目标存放在条目数据中，需要解压读取
+        if is_symlink {
+            let file = match password {
+                Some(pw) => archive.by_index_decrypt(i, pw.as_bytes()),
+                None => archive.by_index(i),
+            };
+            let mut target = String::new();
+            // 读取失败（如加密条目未提供密码）时不记录目标
+            if let Ok(mut file) = file {
+                if file.read_to_string(&mut target).is_ok() {
+                    entry.link_target = Some(target);
+                }
+            }
+        }
+
+        if !f(entry)? {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// 按条目名查找 ZIP 条目的索引（与列举时使用相同的文件名解码规则）
+pub(crate) fn find_zip_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    entry_name: &str,
+    encoding: NameEncoding,
+) -> Result<Option<usize>, ArchiveError> {
+    let decoder = name_decoder(archive, encoding)?;
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        if decode_entry_name(&file, &decoder) == entry_name {
+            return Ok(Some(i));
+        }
+    }
+    Ok(None)
+}
+
+/// 打开 ZIP 中的指定文件，并把解压后的数据流交给 `f` 处理
+pub(crate) fn with_zip_entry<R: Read + Seek, T>(
+    reader: R,
+    entry_name: &str,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut dyn FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let index = find_zip_entry(&mut archive, entry_name, encoding)?
+        .ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))?;
+
+    let mut file = match password {
+        Some(pw) => archive.by_index_decrypt(index, pw.as_bytes())?,
+        None => archive.by_index(index)?,
+    };
+    if file.is_dir() {
+        return Err(ArchiveError::InvalidPath(entry_name.to_string()));
+    }
+    // 符号链接的数据是链接目标，不是文件内容
+    if file.is_symlink() {
+        let mut target = Vec::new();
+        (&mut file).take(4096).read_to_end(&mut target)?;
+        return Err(ArchiveError::LinkEntry {
+            entry: entry_name.to_string(),
+            target: Some(String::from_utf8_lossy(&target).into_owned()),
+        });
+    }
+
+    f(&mut file)
+}
+
+/// 依次解压每个文件条目，数据流读取时由 zip 校验 CRC32
+pub(crate) fn for_each_zip_entry<R: Read + Seek>(
+    reader: R,
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut VisitFn<'_>,
+) -> Result<(), ArchiveError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let decoder = name_decoder(&mut archive, encoding)?;
+    for i in 0..archive.len() {
+        let entry = zip_entry(&archive.by_index_raw(i)?, &decoder);
+        if entry.dir {
+            continue;
+        }
         let file = match password {
-            Some(pw) => archive.by_index_decrypt(i, pw.as_bytes())?,
-            None => archive.by_index_raw(i)?,
+            Some(pw) => archive.by_index_decrypt(i, pw.as_bytes()),
+            None => archive.by_index(i),
         };
-        let is_dir = file.is_dir();
-        let name = decode_entry_name(file.name_raw());
-        let size = file.size();
-        let last_modified = file.last_modified().unwrap_or_default().to_string();
+        let keep_going = match file {
+            Ok(mut file) => f(&entry, Ok(&mut file))?,
+            Err(e) => f(&entry, Err(e.into()))?,
+        };
+        if !keep_going {
+            break;
+        }
+    }
+    Ok(())
+}
 
-        entries.push(Extract::new(name, size, last_modified, is_dir));
+/// 解压 ZIP 中的单个文件到目标目录，返回写出的文件路径；`path` 可以是分卷中的任意一卷
+pub fn extract_zip_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    password: Option<&str>,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    let input = SeekInput::new(volume::open_input(path.as_ref())?)?;
+    let (reader, _) = input.reader()?;
+    with_zip_entry(
+        reader,
+        entry_name,
+        password,
+        NameEncoding::Auto,
+        &mut |data| super::write_entry_to_dir(data, entry_name, dest_dir),
+    )
+}
+
+/// 检查 ZIP 的密码是否正确，`path` 可以是分卷中的任意一卷
+pub fn verify_zip_password<P: AsRef<Path>>(path: P, password: &str) -> Result<bool, ArchiveError> {
+    ZipReader::open(volume::open_input(path.as_ref())?, None)?.verify_password(password)
+}
+
+/// 只解压压缩后最小的一个加密条目
+///
+/// ZipCrypto 的头部校验字节只有 1/256 的区分度，因此读完整个条目，由 CRC 确认密码。
+fn verify_zip_password_from<R: Read + Seek>(
+    reader: R,
+    password: &str,
+) -> Result<bool, ArchiveError> {
+    let mut archive = ZipArchive::new(reader)?;
+    let mut smallest: Option<(usize, u64)> = None;
+    for i in 0..archive.len() {
+        let file = archive.by_index_raw(i)?;
+        if file.encrypted()
+            && !file.is_dir()
+            && smallest.map_or(true, |(_, size)| file.compressed_size() < size)
+        {
+            smallest = Some((i, file.compressed_size()));
+        }
     }
+    let Some((index, _)) = smallest else {
+        return Ok(true);
+    };
 
-    Ok(entries)
+    let mut file = match archive.by_index_decrypt(index, password.as_bytes()) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::InvalidPassword) => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    match std::io::copy(&mut file, &mut std::io::sink()) {
+        Ok(_) => Ok(true),
+        // 校验字节碰巧一致时，解出的数据无法解压或 CRC 不符
+        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => Ok(false),
+        Err(e) => Err(e.into()),
+    }
 }
 
 /// 处理 zip 格式的压缩文件（兼容旧接口）
@@ -42,9 +318,99 @@ pub fn zip_extract(zip_path: &str, password: Option<&str>) -> Result<Vec<Extract
     list_zip_entries(zip_path, password)
 }
 
-/// 检测 ZIP 文件是否需要密码
-/// ZIP 的元数据（文件名、大小、时间）始终是明文的，不需要密码即可列出
-/// 此方法始终返回 false
-pub fn is_zip_password_protected<P: AsRef<Path>>(_path: P) -> Result<bool, ArchiveError> {
-    Ok(false)
+/// 检测 ZIP 文件是否有加密的条目
+///
+/// ZIP 的元数据（文件名、大小、时间）始终是明文的，不需要密码即可列出；
+/// 加密的只是条目数据，需要逐个检查条目。
+pub fn is_zip_password_protected<P: AsRef<Path>>(path: P) -> Result<bool, ArchiveError> {
+    let protection =
+        ZipReader::open(volume::open_input(path.as_ref())?, None)?.password_protection()?;
+    Ok(protection != PasswordProtection::None)
+}
+
+/// ZIP 读取器，嵌套的 ZIP 会读入内存
+pub struct ZipReader {
+    input: SeekInput,
+    password: Option<String>,
+    encoding: NameEncoding,
+}
+
+impl<'a> ArchiveReader<'a> for ZipReader {
+    fn open(input: Input<'a>, password: Option<&str>) -> Result<Self, ArchiveError> {
+        Ok(Self {
+            input: SeekInput::new(input)?,
+            password: password.map(str::to_string),
+            encoding: NameEncoding::Auto,
+        })
+    }
+
+    fn metadata(&self) -> ArchiveMetadata {
+        self.input.metadata(ArchiveFormat::Zip)
+    }
+
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError> {
+        let (reader, _) = self.input.reader()?;
+        list_zip_entries_from(reader, self.password.as_deref(), self.encoding)
+    }
+
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        let (reader, _) = self.input.reader()?;
+        each_zip_entry(reader, self.password.as_deref(), self.encoding, f)
+    }
+
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError> {
+        // 中央目录始终是明文的
+        Ok(false)
+    }
+
+    fn password_protection(&mut self) -> Result<PasswordProtection, ArchiveError> {
+        let (reader, _) = self.input.reader()?;
+        Ok(PasswordProtection::of_entries(&list_zip_entries_from(
+            reader,
+            None,
+            self.encoding,
+        )?))
+    }
+
+    fn verify_password(&mut self, password: &str) -> Result<bool, ArchiveError> {
+        let (reader, _) = self.input.reader()?;
+        verify_zip_password_from(reader, password)
+    }
+
+    fn set_name_encoding(&mut self, encoding: NameEncoding) {
+        self.encoding = encoding;
+    }
+
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError> {
+        let (reader, _) = self.input.reader()?;
+        with_zip_entry(
+            reader,
+            entry_name,
+            self.password.as_deref(),
+            self.encoding,
+            f,
+        )
+    }
+
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError> {
+        let (reader, _) = self.input.reader()?;
+        for_each_zip_entry(reader, self.password.as_deref(), self.encoding, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::zip_encryption_method;
+
+    #[test]
+    fn detects_encryption_scheme_from_extra_fields() {
+        // 0x5455 扩展时间戳在前，0x9901 AES-192 在后
+        let aes = [
+            0x55, 0x54, 0x05, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // 0x5455
+            0x01, 0x99, 0x07, 0x00, 0x02, 0x00, b'A', b'E', 0x02, 0x08, 0x00, // 0x9901
+        ];
+        assert_eq!(zip_encryption_method(true, Some(&aes)), Some("aes-192"));
+        assert_eq!(zip_encryption_method(true, None), Some("zipcrypto"));
+        assert_eq!(zip_encryption_method(false, Some(&aes)), None);
+    }
 }
@@ -1,35 +1,34 @@
-use crate::{ArchiveError, Extract};
+use crate::{ArchiveError, ArchiveFormat, Extract};
 use ruzstd::decoding::StreamingDecoder;
 use std::{
-    fs::File,
-    path::Path,
-    time::{Duration, UNIX_EPOCH},
+    io::Read,
+    path::{Path, PathBuf},
 };
 
-fn parse_tar_zst_entries<R: std::io::Read>(reader: R) -> Result<Vec<Extract>, ArchiveError> {
+/// 创建 Zstd 流式解码器
+pub(crate) fn zstd_decoder<'a, R: Read + 'a>(
+    reader: R,
+) -> Result<Box<dyn Read + 'a>, ArchiveError> {
     let decoder = StreamingDecoder::new(reader)
         .map_err(|e| ArchiveError::Other(format!("Zstd 解码失败: {e}")))?;
-    let mut archive = tar::Archive::new(decoder);
-    let mut entries = Vec::new();
-
-    for entry_result in archive.entries()? {
-        let entry = entry_result?;
-        let header = entry.header();
-        let name = entry.path()?.to_string_lossy().into_owned();
-        let size = header.size()?;
-        let mtime = header.mtime()?;
-        let is_dir = header.entry_type().is_dir();
-
-        let dt = UNIX_EPOCH + Duration::from_secs(mtime);
-        let last_modified = chrono::DateTime::<chrono::Local>::from(dt).to_rfc3339();
-
-        entries.push(Extract::new(name, size, last_modified, is_dir));
-    }
-
-    Ok(entries)
+    Ok(Box::new(decoder))
 }
 
-/// 列举 TAR.ZST / TZST 文件条目
+/// 列举 TAR.ZST / TZST 文件条目（内容不是 TAR 时返回一个虚拟条目）
 pub fn list_tar_zst_entries<P: AsRef<Path>>(path: P) -> Result<Vec<Extract>, ArchiveError> {
-    parse_tar_zst_entries(File::open(path)?)
+    super::compressed::list_compressed_entries(ArchiveFormat::TarZst, path.as_ref())
+}
+
+/// 解压 TAR.ZST 中的单个文件到目标目录
+pub fn extract_tar_zst_entry<P: AsRef<Path>>(
+    path: P,
+    entry_name: &str,
+    dest_dir: &Path,
+) -> Result<PathBuf, ArchiveError> {
+    super::compressed::extract_compressed_entry(
+        ArchiveFormat::TarZst,
+        path.as_ref(),
+        entry_name,
+        dest_dir,
+    )
 }
@@ -0,0 +1,261 @@
+use crate::{registry, ArchiveError};
+use serde::Serialize;
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// 识别格式时读取的文件头长度，覆盖 TAR 头部的 `ustar` 标识（偏移 257）
+const SNIFF_LEN: usize = 512;
+
+/// 支持的归档格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    Zip,
+    Tar,
+    TarGz,
+    TarBz2,
+    TarXz,
+    TarZst,
+    /// LZ4 帧格式
+    TarLz4,
+    /// 旧式 LZMA（LZMA-Alone）
+    TarLzma,
+    TarLzip,
+    /// Brotli 没有魔数，只按扩展名识别
+    TarBr,
+    /// Unix compress（LZW）
+    TarZ,
+    SevenZ,
+    Rar,
+    Cpio,
+    Ar,
+    /// Debian 软件包，外层为 AR
+    Deb,
+    Rpm,
+    /// ISO 9660 光盘映像
+    Iso,
+    /// 带 UDF 文件系统的光盘映像，ISO 9660/UDF 混合映像也按 UDF 读取
+    Udf,
+    /// SquashFS 文件系统映像，含 snap 软件包
+    Squashfs,
+    /// Microsoft Cabinet
+    Cab,
+    /// Windows Installer 安装包（复合文档）
+    Msi,
+}
+
+impl ArchiveFormat {
+    /// 根据扩展名（不含 `.`，不区分大小写）判断格式
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        registry::by_extension(extension).map(|registration| registration.format)
+    }
+
+    /// 根据文件头的魔数判断格式
+    ///
+    /// 压缩流（gzip/bzip2/xz/zstd/lz4/lzma/lzip/compress）按其内部为 TAR 处理。
+    pub fn from_magic(header: &[u8]) -> Option<Self> {
+        registry::by_magic(header).map(|registration| registration.format)
+    }
+
+    /// 根据文件路径（或归档内的条目名）的扩展名判断格式
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<Self> {
+        path.as_ref()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+}
+
+/// 判断是否为 TAR 头部：POSIX/GNU 的 `ustar` 标识，或校验和正确的旧式 V7 头部
+pub(crate) fn is_tar_header(header: &[u8]) -> bool {
+    if header.len() < SNIFF_LEN {
+        return false;
+    }
+    if &header[257..262] == b"ustar" {
+        return true;
+    }
+
+    // 校验和字段（148..156）为八进制数字，计算时该字段按空格处理
+    let field = &header[148..156];
+    let digits = field
+        .iter()
+        .skip_while(|b| **b == b' ')
+        .take_while(|b| (b'0'..=b'7').contains(*b));
+    let Some(expected) = digits.fold(None, |acc: Option<u32>, b| {
+        Some(acc.unwrap_or(0) * 8 + u32::from(b - b'0'))
+    }) else {
+        return false;
+    };
+    let actual: u32 = header[..SNIFF_LEN]
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                u32::from(b' ')
+            } else {
+                u32::from(*b)
+            }
+        })
+        .sum();
+    // 全零块的校验和为 256，不视为 TAR
+    header[0] != 0 && expected == actual
+}
+
+/// 判断是否为旧式 LZMA 头部
+///
+/// LZMA-Alone 没有魔数，只能检查 13 字节头部的取值：属性字节为 xz 等工具默认的 `0x5D`，
+/// 字典大小为 2^n 或 2^n + 2^(n-1)（不小于 4 KiB），解压后大小未知或不超过 2^48。
+pub(crate) fn is_lzma_header(header: &[u8]) -> bool {
+    if header.len() < 13 || header[0] != 0x5D {
+        return false;
+    }
+    let dict_size = u32::from_le_bytes([header[1], header[2], header[3], header[4]]);
+    let size = u64::from_le_bytes(header[5..13].try_into().unwrap_or_default());
+    let dict_ok = dict_size >= 1 << 12
+        && (dict_size.is_power_of_two()
+            || (dict_size % 3 == 0 && (dict_size / 3).is_power_of_two()));
+    dict_ok && (size == u64::MAX || size < 1 << 48)
+}
+
+/// 读取数据流开头的若干字节，根据魔数识别归档格式
+///
+/// 无法识别时返回 `Ok(None)`；读取位置会前移，需要继续使用时请重新打开或回退。
+pub fn detect_format<R: Read>(mut reader: R) -> io::Result<Option<ArchiveFormat>> {
+    let header = read_header(&mut reader)?;
+    Ok(ArchiveFormat::from_magic(&header))
+}
+
+/// 识别文件的归档格式：优先根据文件头魔数，无法识别时再根据扩展名判断
+pub fn detect_path_format(path: &Path) -> Result<ArchiveFormat, ArchiveError> {
+    if let Some(format) = detect_format(File::open(path)?)? {
+        return Ok(format);
+    }
+    ArchiveFormat::from_path(path)
+        .ok_or_else(|| ArchiveError::UnsupportedFormat(extension_of(path)))
+}
+
+/// 读取最多 [`SNIFF_LEN`] 字节的文件头
+pub(crate) fn read_header<R: Read + ?Sized>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    reader.take(SNIFF_LEN as u64).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// 取路径的小写扩展名，用于错误信息
+pub(crate) fn extension_of(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArchiveFormat;
+
+    fn tar_header(magic: &[u8]) -> Vec<u8> {
+        let mut header = vec![0u8; 512];
+        header[..8].copy_from_slice(b"file.txt");
+        header[257..257 + magic.len()].copy_from_slice(magic);
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|b| u32::from(*b)).sum();
+        header[148..155].copy_from_slice(format!("{sum:06o}\0").as_bytes());
+        header
+    }
+
+    #[test]
+    fn detects_signatures() {
+        assert_eq!(
+            ArchiveFormat::from_magic(b"PK\x03\x04rest"),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"7z\xBC\xAF\x27\x1C\x00"),
+            Some(ArchiveFormat::SevenZ)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"Rar!\x1A\x07\x01\x00"),
+            Some(ArchiveFormat::Rar)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"!<arch>\ndebian"),
+            Some(ArchiveFormat::Ar)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(
+                b"!<arch>\ndebian-binary   1342943816  0     0     100644  4         `\n"
+            ),
+            Some(ArchiveFormat::Deb)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"\xED\xAB\xEE\xDB\x03\x00"),
+            Some(ArchiveFormat::Rpm)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"070701000"),
+            Some(ArchiveFormat::Cpio)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0x1F, 0x8B, 0x08]),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"BZh91AY"),
+            Some(ArchiveFormat::TarBz2)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"\xFD7zXZ\x00"),
+            Some(ArchiveFormat::TarXz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0x28, 0xB5, 0x2F, 0xFD]),
+            Some(ArchiveFormat::TarZst)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0x04, 0x22, 0x4D, 0x18]),
+            Some(ArchiveFormat::TarLz4)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"\x5D\x00\x00\x80\x00\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFF"),
+            Some(ArchiveFormat::TarLzma)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"LZIP\x01\x0C"),
+            Some(ArchiveFormat::TarLzip)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(&[0x1F, 0x9D, 0x90]),
+            Some(ArchiveFormat::TarZ)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"hsqs\x10\x00\x00\x00"),
+            Some(ArchiveFormat::Squashfs)
+        );
+        assert_eq!(
+            ArchiveFormat::from_magic(b"MSCF\x00\x00\x00\x00"),
+            Some(ArchiveFormat::Cab)
+        );
+        assert_eq!(ArchiveFormat::from_magic(b"plain text"), None);
+    }
+
+    #[test]
+    fn detects_tar_by_magic_or_checksum() {
+        assert_eq!(
+            ArchiveFormat::from_magic(&tar_header(b"ustar\x0000")),
+            Some(ArchiveFormat::Tar)
+        );
+        // 旧式 V7 TAR 没有 ustar 标识，只能依靠校验和
+        assert_eq!(
+            ArchiveFormat::from_magic(&tar_header(b"")),
+            Some(ArchiveFormat::Tar)
+        );
+
+        let mut corrupted = tar_header(b"");
+        corrupted[0] = b'g';
+        assert_eq!(ArchiveFormat::from_magic(&corrupted), None);
+        assert_eq!(ArchiveFormat::from_magic(&[0u8; 512]), None);
+    }
+}
@@ -0,0 +1,574 @@
+//! 容器镜像：`docker save` 导出的 TAR 和 OCI 镜像布局（image layout）
+//!
+//! | 布局 | 标识 | 清单 |
+//! | --- | --- | --- |
+//! | Docker | 根目录的 `manifest.json`（数组） | `Config` 为镜像配置的条目名，`Layers` 为各层的条目名，`RepoTags` 为标签 |
+//! | OCI | `oci-layout` 和 `index.json` | 描述符按摘要指向 `blobs/<算法>/<摘要>`，索引可以嵌套（多平台镜像） |
+//!
+//! Docker 25 起 `docker save` 同时写入两种清单，此时按 `manifest.json` 读取。
+//! 每层是（可能经过 gzip、zstd 压缩的）TAR，由 [`registry::open_stream`] 按文件头打开。
+//! 合并视图按从下到上的顺序叠加各层并处理 whiteout：`.wh.<name>` 删除下层的同名文件或目录，
+//! `.wh..wh..opq` 清空下层中所在目录的内容。
+
+use crate::reader::ArchiveReader;
+use crate::{registry, ArchiveError, Extract};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+
+/// 清单、配置等 JSON 文档的大小上限，更大的条目不会是 JSON 文档
+const MAX_DOCUMENT_SIZE: u64 = 4 * 1024 * 1024;
+/// OCI 索引允许的最大嵌套层数
+const MAX_INDEX_DEPTH: usize = 4;
+/// 符号链接最多跟随的次数
+const MAX_LINK_DEPTH: usize = 8;
+const WHITEOUT_PREFIX: &str = ".wh.";
+const OPAQUE_WHITEOUT: &str = ".wh..wh..opq";
+
+/// 镜像归档的布局
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageLayout {
+    /// `docker save` 的 `manifest.json`
+    Docker,
+    /// OCI 镜像布局的 `index.json`
+    Oci,
+}
+
+/// 归档中的容器镜像
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageInfo {
+    pub layout: ImageLayout,
+    /// 各个镜像，多平台镜像中归档里包含的每个平台各占一项
+    pub images: Vec<ImageManifest>,
+}
+
+/// 一个镜像的清单和配置
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageManifest {
+    /// 镜像名和标签，如 `alpine:3.20`
+    pub tags: Vec<String>,
+    /// 镜像清单的摘要，Docker 布局没有单独的清单，为 `None`
+    pub digest: Option<String>,
+    /// 镜像配置的条目名
+    pub config: String,
+    /// 平台，如 `linux/amd64`、`linux/arm64/v8`
+    pub platform: Option<String>,
+    /// 创建时间（RFC 3339）
+    pub created: Option<String>,
+    pub author: Option<String>,
+    pub entrypoint: Vec<String>,
+    pub cmd: Vec<String>,
+    pub working_dir: Option<String>,
+    pub user: Option<String>,
+    /// `KEY=value` 形式的环境变量
+    pub env: Vec<String>,
+    /// 暴露的端口，如 `80/tcp`
+    pub exposed_ports: Vec<String>,
+    pub labels: BTreeMap<String, String>,
+    /// 构建历史，从早到晚
+    pub history: Vec<ImageHistory>,
+    /// 文件系统层，从下到上
+    pub layers: Vec<ImageLayer>,
+}
+
+/// 构建历史中的一步
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageHistory {
+    pub created: Option<String>,
+    /// 产生这一步的指令，如 `/bin/sh -c apk add curl`
+    pub created_by: Option<String>,
+    pub comment: Option<String>,
+    /// 这一步没有产生文件系统层（如 `ENV`、`CMD`）
+    pub empty_layer: bool,
+}
+
+/// 镜像的一个文件系统层
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageLayer {
+    /// 层数据在归档中的条目名
+    pub entry: String,
+    /// 摘要，如 `sha256:…`
+    pub digest: Option<String>,
+    /// 媒体类型，Docker 布局不记录
+    pub media_type: Option<String>,
+    /// 层数据的大小，压缩的层为压缩后大小
+    pub size: u64,
+}
+
+/// 镜像各层叠加后的文件系统
+#[derive(Debug, Clone, Serialize)]
+pub struct ImageFilesystem {
+    /// 条目目录树，条目名为其所在层中的名称
+    pub entries: Vec<Extract>,
+    /// 文件条目名 → 提供该文件的层在 [`ImageManifest::layers`] 中的下标
+    pub origins: HashMap<String, usize>,
+}
+
+/// 顺序读取一遍归档得到的内容
+#[derive(Default)]
+struct ImageArchive {
+    /// 可能是清单或配置的 JSON 文档，以规范化的条目名为键
+    documents: HashMap<String, Value>,
+    /// 规范化的条目名 → 原始条目名和大小
+    entries: HashMap<String, (String, u64)>,
+    /// 符号链接 → 规范化的目标条目名；旧版 `docker save` 用符号链接复用相同的层
+    links: HashMap<String, String>,
+}
+
+/// 读取归档中的镜像清单和配置，不是容器镜像时返回 `None`
+pub(crate) fn inspect(
+    archive: &mut dyn ArchiveReader<'_>,
+) -> Result<Option<ImageInfo>, ArchiveError> {
+    Ok(ImageArchive::scan(archive)?.image_info())
+}
+
+/// 读取 `manifest` 的各层并叠加，层数据在归档中找不到时返回 [`ArchiveError::EntryNotFound`]
+pub(crate) fn merge_layers(
+    archive: &mut dyn ArchiveReader<'_>,
+    manifest: &ImageManifest,
+) -> Result<ImageFilesystem, ArchiveError> {
+    // 同一层数据可能被引用多次
+    let mut wanted: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, layer) in manifest.layers.iter().enumerate() {
+        wanted
+            .entry(normalize(&layer.entry))
+            .or_default()
+            .push(index);
+    }
+
+    let mut layers: Vec<Option<Vec<Extract>>> = vec![None; manifest.layers.len()];
+    archive.for_each_entry(&mut |entry, data| {
+        let Some(indices) = wanted.remove(normalize(&entry.name)) else {
+            return Ok(true);
+        };
+        let mut layer = registry::open_stream(Box::new(data?), &entry.name, None)?;
+        let entries = layer.list()?;
+        for index in indices {
+            layers[index] = Some(entries.clone());
+        }
+        Ok(!wanted.is_empty())
+    })?;
+
+    let mut merged = BTreeMap::new();
+    for (index, entries) in layers.into_iter().enumerate() {
+        let entries = entries
+            .ok_or_else(|| ArchiveError::EntryNotFound(manifest.layers[index].entry.clone()))?;
+        apply_layer(&mut merged, index, entries);
+    }
+
+    let origins = merged
+        .values()
+        .filter(|(_, entry)| !entry.dir)
+        .map(|(layer, entry)| (entry.name.clone(), *layer))
+        .collect();
+    let entries = merged.into_values().map(|(_, entry)| entry).collect();
+    Ok(ImageFilesystem {
+        entries: Extract::build_tree(entries),
+        origins,
+    })
+}
+
+/// 把一层叠加到 `merged` 上：先按本层的 whiteout 删除下层的条目，再写入本层的条目
+fn apply_layer(
+    merged: &mut BTreeMap<String, (usize, Extract)>,
+    layer: usize,
+    entries: Vec<Extract>,
+) {
+    let mut added = Vec::new();
+    for entry in entries {
+        let path = normalize(&entry.name).to_string();
+        if path.is_empty() {
+            continue;
+        }
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", &path));
+        if name == OPAQUE_WHITEOUT {
+            remove_children(merged, parent);
+        } else if let Some(hidden) = name.strip_prefix(WHITEOUT_PREFIX) {
+            let hidden = if parent.is_empty() {
+                hidden.to_string()
+            } else {
+                format!("{parent}/{hidden}")
+            };
+            merged.remove(&hidden);
+            remove_children(merged, &hidden);
+        } else {
+            added.push((path, entry));
+        }
+    }
+
+    for (path, entry) in added {
+        // 文件替换下层的目录时，目录中原有的内容随之消失
+        if !entry.dir {
+            remove_children(merged, &path);
+        }
+        merged.insert(path, (layer, entry));
+    }
+}
+
+/// 删除目录 `dir` 下的所有条目，`dir` 为空表示根目录
+fn remove_children(merged: &mut BTreeMap<String, (usize, Extract)>, dir: &str) {
+    if dir.is_empty() {
+        merged.clear();
+        return;
+    }
+    let prefix = format!("{dir}/");
+    let children: Vec<String> = merged
+        .range(prefix.clone()..)
+        .map(|(path, _)| path)
+        .take_while(|path| path.starts_with(&prefix))
+        .cloned()
+        .collect();
+    for path in children {
+        merged.remove(&path);
+    }
+}
+
+impl ImageArchive {
+    fn scan(archive: &mut dyn ArchiveReader<'_>) -> Result<Self, ArchiveError> {
+        let mut scan = Self::default();
+        archive.for_each_entry(&mut |entry, data| {
+            let key = normalize(&entry.name).to_string();
+            if let Some(target) = &entry.link_target {
+                scan.links.insert(key.clone(), resolve_link(&key, target));
+            }
+            scan.entries
+                .insert(key.clone(), (entry.name.clone(), entry.size));
+
+            let is_document = key == "manifest.json"
+                || key == "index.json"
+                || key.starts_with("blobs/")
+                || key.ends_with(".json");
+            // 数据读取失败的条目不可能是清单，不影响其他条目
+            if let (true, Ok(data)) = (is_document && entry.size <= MAX_DOCUMENT_SIZE, data) {
+                let mut buf = Vec::new();
+                data.take(MAX_DOCUMENT_SIZE).read_to_end(&mut buf)?;
+                if let Ok(document) = serde_json::from_slice::<Value>(&buf) {
+                    scan.documents.insert(key, document);
+                }
+            }
+            Ok(true)
+        })?;
+        Ok(scan)
+    }
+
+    fn image_info(&self) -> Option<ImageInfo> {
+        if let Some(Value::Array(manifests)) = self.documents.get("manifest.json") {
+            let images: Vec<_> = manifests
+                .iter()
+                .filter_map(|manifest| self.docker_image(manifest))
+                .collect();
+            if !images.is_empty() {
+                return Some(ImageInfo { layout: ImageLayout::Docker, images });
+            }
+        }
+
+        if !self.entries.contains_key("oci-layout") {
+            return None;
+        }
+        let index = self.documents.get("index.json")?;
+        let mut images = Vec::new();
+        self.oci_images(index, &[], 0, &mut images);
+        Some(ImageInfo { layout: ImageLayout::Oci, images })
+    }
+
+    /// `manifest.json` 中的一项
+    fn docker_image(&self, manifest: &Value) -> Option<ImageManifest> {
+        let config_key = self.resolve(normalize(manifest["Config"].as_str()?));
+        let config = self.documents.get(&config_key)?;
+        let diff_ids = config["rootfs"]["diff_ids"].as_array();
+
+        let mut image = image_config(config, self.entry_name(&config_key));
+        image.tags = strings(&manifest["RepoTags"]);
+        image.layers = strings(&manifest["Layers"])
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let key = self.resolve(normalize(name));
+                // 旧版的 `<id>/layer.tar` 是未压缩的 TAR，摘要即 rootfs 中的 diff_id
+                let digest = blob_digest(&key).or_else(|| {
+                    diff_ids
+                        .and_then(|ids| ids.get(index))
+                        .and_then(Value::as_str)
+                        .map(str::to_string)
+                });
+                ImageLayer {
+                    entry: self.entry_name(&key),
+                    digest,
+                    media_type: None,
+                    size: self.entries.get(&key).map_or(0, |(_, size)| *size),
+                }
+            })
+            .collect();
+        Some(image)
+    }
+
+    /// 遍历 OCI 索引中的描述符，嵌套的索引继承外层的标签
+    fn oci_images(
+        &self,
+        index: &Value,
+        tags: &[String],
+        depth: usize,
+        images: &mut Vec<ImageManifest>,
+    ) {
+        for descriptor in index["manifests"].as_array().into_iter().flatten() {
+            // BuildKit 的证明清单（SBOM、来源证明）不是镜像
+            if descriptor["annotations"]["vnd.docker.reference.type"].is_string() {
+                continue;
+            }
+            let Some(digest) = descriptor["digest"].as_str() else {
+                continue;
+            };
+            // 只导出了部分平台时，其他平台的清单不在归档中
+            let Some(document) = self.documents.get(&blob_path(digest)) else {
+                continue;
+            };
+
+            let annotations = &descriptor["annotations"];
+            let tags = match annotations["io.containerd.image.name"]
+                .as_str()
+                .or(annotations["org.opencontainers.image.ref.name"].as_str())
+            {
+                Some(tag) => vec![tag.to_string()],
+                None => tags.to_vec(),
+            };
+            if document["manifests"].is_array() {
+                if depth < MAX_INDEX_DEPTH {
+                    self.oci_images(document, &tags, depth + 1, images);
+                }
+                continue;
+            }
+            if let Some(mut image) = self.oci_image(document, &descriptor["platform"]) {
+                image.tags = tags;
+                image.digest = Some(digest.to_string());
+                images.push(image);
+            }
+        }
+    }
+
+    /// OCI 镜像清单，`platform` 为索引中描述符的平台，配置中没有记录平台时使用
+    fn oci_image(&self, manifest: &Value, platform: &Value) -> Option<ImageManifest> {
+        let config_key = blob_path(manifest["config"]["digest"].as_str()?);
+        let config = self.documents.get(&config_key)?;
+
+        let mut image = image_config(config, self.entry_name(&config_key));
+        if image.platform.is_none() {
+            image.platform = platform_name(platform);
+        }
+        image.layers = manifest["layers"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|layer| {
+                let digest = layer["digest"].as_str()?;
+                let key = blob_path(digest);
+                Some(ImageLayer {
+                    entry: self.entry_name(&key),
+                    digest: Some(digest.to_string()),
+                    media_type: layer["mediaType"].as_str().map(str::to_string),
+                    size: layer["size"]
+                        .as_u64()
+                        .or_else(|| self.entries.get(&key).map(|(_, size)| *size))
+                        .unwrap_or(0),
+                })
+            })
+            .collect();
+        Some(image)
+    }
+
+    /// 跟随符号链接，返回最终的条目名
+    fn resolve(&self, key: &str) -> String {
+        let mut key = key.to_string();
+        for _ in 0..MAX_LINK_DEPTH {
+            match self.links.get(&key) {
+                Some(target) => key = target.clone(),
+                None => break,
+            }
+        }
+        key
+    }
+
+    /// 规范化条目名对应的原始条目名，条目不存在时原样返回
+    fn entry_name(&self, key: &str) -> String {
+        self.entries
+            .get(key)
+            .map_or_else(|| key.to_string(), |(name, _)| name.clone())
+    }
+}
+
+/// 镜像配置中的平台、运行参数和构建历史
+fn image_config(config: &Value, config_entry: String) -> ImageManifest {
+    let text = |value: &Value| {
+        value
+            .as_str()
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+    let run = &config["config"];
+    let history = config["history"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|step| ImageHistory {
+            created: text(&step["created"]),
+            created_by: text(&step["created_by"]),
+            comment: text(&step["comment"]),
+            empty_layer: step["empty_layer"].as_bool().unwrap_or(false),
+        })
+        .collect();
+    let labels = run["Labels"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+        .collect();
+
+    ImageManifest {
+        tags: Vec::new(),
+        digest: None,
+        config: config_entry,
+        platform: platform_name(config),
+        created: text(&config["created"]),
+        author: text(&config["author"]),
+        entrypoint: strings(&run["Entrypoint"]),
+        cmd: strings(&run["Cmd"]),
+        working_dir: text(&run["WorkingDir"]),
+        user: text(&run["User"]),
+        env: strings(&run["Env"]),
+        exposed_ports: run["ExposedPorts"]
+            .as_object()
+            .map(|ports| ports.keys().cloned().collect())
+            .unwrap_or_default(),
+        labels,
+        history,
+        layers: Vec::new(),
+    }
+}
+
+/// `os/architecture/variant` 形式的平台名，镜像配置和索引中的描述符字段名相同
+fn platform_name(value: &Value) -> Option<String> {
+    let os = value["os"].as_str()?;
+    let architecture = value["architecture"].as_str()?;
+    Some(match value["variant"].as_str() {
+        Some(variant) => format!("{os}/{architecture}/{variant}"),
+        None => format!("{os}/{architecture}"),
+    })
+}
+
+/// 字符串数组；单个字符串（如 shell 形式的 `Cmd`）视为只有一项
+fn strings(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(value) => vec![value.clone()],
+        Value::Array(values) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// 摘要对应的条目名：`sha256:abc…` → `blobs/sha256/abc…`
+fn blob_path(digest: &str) -> String {
+    let (algorithm, hex) = digest.split_once(':').unwrap_or(("sha256", digest));
+    format!("blobs/{algorithm}/{hex}")
+}
+
+/// [`blob_path`] 的逆过程，不是 `blobs/` 下的条目时返回 `None`
+fn blob_digest(key: &str) -> Option<String> {
+    let (algorithm, hex) = key.strip_prefix("blobs/")?.split_once('/')?;
+    Some(format!("{algorithm}:{hex}"))
+}
+
+/// 去掉条目名开头的 `./` 和首尾的 `/`
+fn normalize(name: &str) -> &str {
+    name.trim_start_matches("./").trim_matches('/')
+}
+
+/// 符号链接 `name` 指向 `target` 时，目标的规范化条目名
+fn resolve_link(name: &str, target: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    if !target.starts_with('/') {
+        parts.extend(name.split('/'));
+        parts.pop();
+    }
+    for part in target.split('/') {
+        match part {
+            "" | "." => {},
+            ".." => {
+                parts.pop();
+            },
+            part => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply_layer, resolve_link};
+    use crate::Extract;
+    use std::collections::BTreeMap;
+
+    fn layer(names: &[&str]) -> Vec<Extract> {
+        names
+            .iter()
+            .map(|name| Extract::new(name.to_string(), 1, None, name.ends_with('/')))
+            .collect()
+    }
+
+    #[test]
+    fn applies_whiteouts() {
+        let mut merged = BTreeMap::new();
+        apply_layer(
+            &mut merged,
+            0,
+            layer(&[
+                "etc/",
+                "etc/a",
+                "etc/b",
+                "var/",
+                "var/cache/x",
+                "opt/app/bin",
+            ]),
+        );
+        apply_layer(
+            &mut merged,
+            1,
+            layer(&[
+                "./etc/.wh.a",
+                "./var/cache/.wh..wh..opq",
+                "./var/cache/y",
+                "./opt/app",
+            ]),
+        );
+
+        let paths: Vec<(&str, usize)> = merged
+            .iter()
+            .map(|(path, (layer, _))| (path.as_str(), *layer))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("etc", 0),
+                ("etc/b", 0),
+                ("opt/app", 1),
+                ("var", 0),
+                ("var/cache/y", 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_layer_links() {
+        assert_eq!(
+            resolve_link("abc/layer.tar", "../def/layer.tar"),
+            "def/layer.tar"
+        );
+        assert_eq!(
+            resolve_link("abc/layer.tar", "/def/layer.tar"),
+            "def/layer.tar"
+        );
+    }
+}
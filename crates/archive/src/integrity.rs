@@ -0,0 +1,229 @@
+//! 完整性测试：解压每个条目并校验 CRC32 或格式自带的校验和，类似 `7z t`
+//!
+//! 条目数据只读到内存缓冲区后丢弃，不写磁盘。ZIP、7Z、RAR 由各自的解压库在读取时校验 CRC32，
+//! 这里再对照头部记录的值计算一遍；TAR 系列依靠 gzip/xz 等压缩层在流末尾的校验，
+//! CPIO 的 `070702` 格式校验头部记录的字节和。
+
+use crate::reader::ArchiveReader;
+use crate::{ArchiveError, CancelToken, Extract};
+use serde::Serialize;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+/// 两次进度回调的最小间隔
+pub(crate) const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 测试进度
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestProgress {
+    /// 已测试的条目数
+    pub entries_tested: u64,
+    /// 已解压的字节数
+    pub bytes_tested: u64,
+    /// 条目总数，只有 ZIP、7Z、RAR 这类有目录索引的格式事先知道
+    pub total_entries: Option<u64>,
+    /// 解压后总大小，与 `total_entries` 同时可用
+    pub total_bytes: Option<u64>,
+    /// 正在测试的条目
+    pub current: String,
+}
+
+/// 单个条目的测试结果
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryTestResult {
+    pub name: String,
+    /// 实际解压出的字节数
+    pub size: u64,
+    pub passed: bool,
+    /// 头部记录的 CRC32，格式不记录时为 `None`
+    pub expected_crc32: Option<u32>,
+    /// 解压数据的 CRC32，解压失败或未启用 ZIP、7Z、RAR 时为 `None`
+    pub actual_crc32: Option<u32>,
+    /// 失败原因
+    pub error: Option<String>,
+}
+
+/// 整个归档的测试结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TestReport {
+    /// 每个文件条目的结果（不含目录），按归档中的顺序
+    pub entries: Vec<EntryTestResult>,
+    pub passed: u64,
+    pub failed: u64,
+    pub bytes_tested: u64,
+    /// 归档结构损坏、无法继续读取时的错误，之后的条目未测试
+    pub error: Option<String>,
+}
+
+impl TestReport {
+    /// 所有条目都通过，且完整读完了归档
+    pub fn is_ok(&self) -> bool {
+        self.failed == 0 && self.error.is_none()
+    }
+}
+
+/// 依次测试 `archive` 的每个条目
+///
+/// 缺少密码、密码错误和取消直接返回错误；其他错误记录在报告中。
+pub(crate) fn run(
+    archive: &mut dyn ArchiveReader<'_>,
+    totals: Option<(u64, u64)>,
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(&TestProgress),
+) -> Result<TestReport, ArchiveError> {
+    let mut report = TestReport::default();
+    let mut progress = TestProgress {
+        total_entries: totals.map(|(entries, _)| entries),
+        total_bytes: totals.map(|(_, bytes)| bytes),
+        ..TestProgress::default()
+    };
+    let mut last_report = Instant::now();
+
+    let result = archive.for_each_entry(&mut |entry, data| {
+        cancel.check()?;
+        progress.current.clone_from(&entry.name);
+
+        let result = match data {
+            Ok(data) => test_entry(entry, data, &mut |bytes| {
+                cancel.check()?;
+                progress.bytes_tested += bytes;
+                if last_report.elapsed() >= PROGRESS_INTERVAL {
+                    on_progress(&progress);
+                    last_report = Instant::now();
+                }
+                Ok(())
+            })?,
+            Err(e @ (ArchiveError::PasswordRequired | ArchiveError::WrongPassword)) => {
+                return Err(e)
+            },
+            Err(e) => failed(entry, 0, None, e.to_string()),
+        };
+
+        if result.passed {
+            report.passed += 1;
+        } else {
+            report.failed += 1;
+        }
+        report.bytes_tested += result.size;
+        report.entries.push(result);
+        progress.entries_tested += 1;
+        Ok(true)
+    });
+
+    match result {
+        Ok(()) => {},
+        Err(
+            e @ (ArchiveError::Cancelled
+            | ArchiveError::PasswordRequired
+            | ArchiveError::WrongPassword),
+        ) => return Err(e),
+        Err(e) => report.error = Some(e.to_string()),
+    }
+    on_progress(&progress);
+    Ok(report)
+}
+
+/// 读完条目的数据流并校验 CRC32；`on_read` 在每读一块数据后调用，返回错误时中止
+fn test_entry(
+    entry: &Extract,
+    data: &mut dyn Read,
+    on_read: &mut dyn FnMut(u64) -> Result<(), ArchiveError>,
+) -> Result<EntryTestResult, ArchiveError> {
+    let mut hasher = Crc32::new();
+    let mut buf = vec![0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let n = match data.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Ok(failed(entry, size, None, e.to_string())),
+        };
+        hasher.update(&buf[..n]);
+        size += n as u64;
+        on_read(n as u64)?;
+    }
+
+    let actual = hasher.finalize();
+    match (entry.crc32, actual) {
+        (Some(expected), Some(actual)) if expected != actual => Ok(failed(
+            entry,
+            size,
+            Some(actual),
+            format!("CRC32 不符: 应为 {expected:08x}，实际为 {actual:08x}"),
+        )),
+        (expected, actual) => Ok(EntryTestResult {
+            name: entry.name.clone(),
+            size,
+            passed: true,
+            expected_crc32: expected,
+            actual_crc32: actual,
+            error: None,
+        }),
+    }
+}
+
+/// 条目数据的 CRC32
+#[cfg(any(feature = "zip", feature = "sevenz", feature = "rar"))]
+struct Crc32(crc32fast::Hasher);
+
+/// 只有 ZIP、7Z、RAR 的头部记录 CRC32，都未启用时不计算
+#[cfg(not(any(feature = "zip", feature = "sevenz", feature = "rar")))]
+struct Crc32;
+
+impl Crc32 {
+    fn new() -> Self {
+        #[cfg(any(feature = "zip", feature = "sevenz", feature = "rar"))]
+        return Self(crc32fast::Hasher::new());
+        #[cfg(not(any(feature = "zip", feature = "sevenz", feature = "rar")))]
+        return Self;
+    }
+
+    fn update(&mut self, _data: &[u8]) {
+        #[cfg(any(feature = "zip", feature = "sevenz", feature = "rar"))]
+        self.0.update(_data);
+    }
+
+    fn finalize(self) -> Option<u32> {
+        #[cfg(any(feature = "zip", feature = "sevenz", feature = "rar"))]
+        return Some(self.0.finalize());
+        #[cfg(not(any(feature = "zip", feature = "sevenz", feature = "rar")))]
+        return None;
+    }
+}
+
+fn failed(entry: &Extract, size: u64, actual: Option<u32>, error: String) -> EntryTestResult {
+    EntryTestResult {
+        name: entry.name.clone(),
+        size,
+        passed: false,
+        expected_crc32: entry.crc32,
+        actual_crc32: actual,
+        error: Some(error),
+    }
+}
+
+#[cfg(all(test, any(feature = "zip", feature = "sevenz", feature = "rar")))]
+mod tests {
+    use super::test_entry;
+    use crate::{ArchiveError, Extract};
+
+    #[test]
+    fn compares_crc32_with_header() {
+        let mut entry = Extract::new("a.txt".to_string(), 5, None, false);
+        // crc32("hello")
+        entry.crc32 = Some(0x3610_a686);
+        let result = test_entry(&entry, &mut &b"hello"[..], &mut |_| Ok(())).unwrap();
+        assert!(result.passed);
+        assert_eq!(result.size, 5);
+
+        let result = test_entry(&entry, &mut &b"hellp"[..], &mut |_| Ok(())).unwrap();
+        assert!(!result.passed);
+        assert!(result.actual_crc32.is_some());
+
+        let cancelled = test_entry(&entry, &mut &b"hello"[..], &mut |_| {
+            Err(ArchiveError::Cancelled)
+        });
+        assert!(matches!(cancelled, Err(ArchiveError::Cancelled)));
+    }
+}
@@ -1,137 +1,662 @@
-use serde::Serialize;
-use std::collections::HashMap;
-use std::path::Path;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
+mod axml;
+pub mod cache;
+pub mod cancel;
+pub mod encoding;
 pub mod error;
 pub mod extractors;
+pub mod format;
+pub mod image;
+pub mod integrity;
+pub mod listing;
+mod nested;
+pub mod package;
+pub mod reader;
+pub mod registry;
+pub mod safety;
+pub mod timestamp;
+pub mod volume;
 
+pub use cache::ListingCache;
+pub use cancel::CancelToken;
+pub use encoding::NameEncoding;
 pub use error::ArchiveError;
-pub use extractors::ar::list_ar_entries;
-pub use extractors::cpio::list_cpio_entries;
-pub use extractors::rar::{is_rar_password_protected, list_rar_entries};
-pub use extractors::sevenz::{is_7z_password_protected, list_7z_entries};
+#[cfg(feature = "ar")]
+pub use extractors::ar::{extract_ar_entry, list_ar_entries};
+#[cfg(feature = "brotli")]
+pub use extractors::brotli::{extract_tar_br_entry, list_tar_br_entries};
+#[cfg(feature = "cab")]
+pub use extractors::cab::list_cab_entries;
+#[cfg(feature = "cpio")]
+pub use extractors::cpio::{extract_cpio_entry, list_cpio_entries};
+#[cfg(feature = "iso")]
+pub use extractors::iso::list_iso_entries;
+#[cfg(feature = "lz4")]
+pub use extractors::lz4::{extract_tar_lz4_entry, list_tar_lz4_entries};
+#[cfg(feature = "lzip")]
+pub use extractors::lzip::{extract_tar_lz_entry, list_tar_lz_entries};
+#[cfg(feature = "lzma")]
+pub use extractors::lzma::{extract_tar_lzma_entry, list_tar_lzma_entries};
+#[cfg(feature = "compress")]
+pub use extractors::lzw::{extract_tar_z_entry, list_tar_z_entries};
+#[cfg(feature = "msi")]
+pub use extractors::msi::list_msi_entries;
+#[cfg(feature = "rar")]
+pub use extractors::rar::{
+    extract_rar_entry, is_rar_password_protected, list_rar_entries, verify_rar_password,
+};
+#[cfg(feature = "sevenz")]
+pub use extractors::sevenz::{
+    extract_7z_entry, is_7z_password_protected, list_7z_entries, verify_7z_password,
+};
+#[cfg(feature = "squashfs")]
+pub use extractors::squashfs::list_squashfs_entries;
+#[cfg(feature = "tar")]
 pub use extractors::tar::{
+    extract_tar_bz2_entry, extract_tar_entry, extract_tar_gz_entry, extract_tar_xz_entry,
     list_tar_bz2_entries, list_tar_entries, list_tar_gz_entries, list_tar_xz_entries,
 };
-pub use extractors::zip::{is_zip_password_protected, list_zip_entries, zip_extract};
-pub use extractors::zst::list_tar_zst_entries;
+#[cfg(feature = "zip")]
+pub use extractors::zip::{
+    extract_zip_entry, is_zip_password_protected, list_zip_entries, verify_zip_password,
+    zip_extract,
+};
+#[cfg(feature = "zstd")]
+pub use extractors::zst::{extract_tar_zst_entry, list_tar_zst_entries};
+pub use format::{detect_format, detect_path_format, ArchiveFormat};
+pub use image::{ImageFilesystem, ImageHistory, ImageInfo, ImageLayer, ImageLayout, ImageManifest};
+pub use integrity::{EntryTestResult, TestProgress, TestReport};
+pub use listing::{EntryIter, ListProgress, ListSummary};
+pub use package::{PackageField, PackageInfo, PackageKind};
+pub use reader::{ArchiveMetadata, ArchiveReader, PasswordProtection};
+pub use safety::{SafetyAnalyzer, SafetyIssue, SafetyIssueKind, SafetyLimits, SafetyReport};
+pub use timestamp::EntryTime;
+pub use volume::{discover_volumes, VolumeScheme, VolumeSet};
 
 /// 压缩文件条目信息
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Extract {
     /// 文件名
     pub name: String,
     /// 文件大小
     pub size: u64,
-    /// 最后修改时间
-    pub last_modified: String,
+    /// 最后修改时间，格式未记录时为 `None`
+    pub last_modified: Option<EntryTime>,
     /// 是否是目录
     pub dir: bool,
+    /// 条目类型，格式不区分时只有文件和目录两种
+    pub kind: EntryKind,
+    /// 压缩后大小，格式不提供时为 `None`
+    pub compressed_size: Option<u64>,
+    /// 符号链接指向的目标
+    pub link_target: Option<String>,
+    /// 硬链接指向的条目
+    pub hardlink_target: Option<String>,
+    /// 压缩方法（如 `store`、`deflate`、`lzma2`、`zstd`），格式不提供时为 `None`
+    pub compression_method: Option<String>,
+    /// CRC32 校验值
+    pub crc32: Option<u32>,
+    /// Unix 权限位（含文件类型位）
+    pub mode: Option<u32>,
+    /// 所有者用户 ID
+    pub uid: Option<u64>,
+    /// 所有者组 ID
+    pub gid: Option<u64>,
+    /// 所有者用户名
+    pub user_name: Option<String>,
+    /// 所有者组名
+    pub group_name: Option<String>,
+    /// 设备文件的主设备号
+    pub device_major: Option<u32>,
+    /// 设备文件的次设备号
+    pub device_minor: Option<u32>,
+    /// 是否为稀疏文件，`size` 为展开后的大小
+    pub sparse: bool,
+    /// 扩展属性（如 `security.selinux`、`user.*`），值不是 UTF-8 文本时为 `0x` 开头的十六进制
+    pub xattrs: Option<BTreeMap<String, String>>,
+    /// 条目数据是否加密
+    pub encrypted: bool,
+    /// 加密方式（`zipcrypto`、`aes-128`、`aes-192`、`aes-256`、`pkware-strong`），
+    /// 未加密或格式不提供时为 `None`
+    pub encryption_method: Option<String>,
+    /// 文件版本号，目前只有 MSI 的 File 表提供
+    pub version: Option<String>,
+    /// 目录下（含子目录）的文件总数，仅 [`Extract::build_tree`] 生成的目录节点有值
+    pub file_count: Option<u64>,
     /// 子目录/文件（用于构建树状结构）
     pub children: Option<Vec<Extract>>,
 }
 
+/// 条目类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryKind {
+    File,
+    Directory,
+    Symlink,
+    /// 硬链接，指向的条目见 [`Extract::hardlink_target`]
+    Hardlink,
+    CharDevice,
+    BlockDevice,
+    Fifo,
+    Socket,
+}
+
+impl EntryKind {
+    /// 根据 Unix 权限位中的文件类型位（`S_IFMT`）判断，没有类型位时返回 `None`
+    pub fn from_mode(mode: u32) -> Option<Self> {
+        match mode & 0o170_000 {
+            0o100_000 => Some(Self::File),
+            0o040_000 => Some(Self::Directory),
+            0o120_000 => Some(Self::Symlink),
+            0o020_000 => Some(Self::CharDevice),
+            0o060_000 => Some(Self::BlockDevice),
+            0o010_000 => Some(Self::Fifo),
+            0o140_000 => Some(Self::Socket),
+            _ => None,
+        }
+    }
+}
+
+/// 列举结果及其安全检查报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveListing {
+    /// 条目目录树
+    pub entries: Vec<Extract>,
+    /// 安全检查报告
+    pub safety: SafetyReport,
+    /// 分卷数量，非分卷归档和嵌套归档为 1
+    pub volumes: usize,
+    /// 扩展名为 jar、apk 等软件包时的清单信息
+    pub package: Option<PackageInfo>,
+}
+
 impl Extract {
     /// 创建新的条目
-    pub fn new(name: String, size: u64, last_modified: String, dir: bool) -> Self {
+    pub fn new(name: String, size: u64, last_modified: Option<EntryTime>, dir: bool) -> Self {
         Self {
             name,
             size,
             last_modified,
             dir,
+            kind: if dir {
+                EntryKind::Directory
+            } else {
+                EntryKind::File
+            },
+            compressed_size: None,
+            link_target: None,
+            hardlink_target: None,
+            compression_method: None,
+            crc32: None,
+            mode: None,
+            uid: None,
+            gid: None,
+            user_name: None,
+            group_name: None,
+            device_major: None,
+            device_minor: None,
+            sparse: false,
+            xattrs: None,
+            encrypted: false,
+            encryption_method: None,
+            version: None,
+            file_count: None,
             children: None,
         }
     }
 
-    /// 检测归档文件是否需要密码
-    pub fn is_password_protected<P: AsRef<Path>>(archive_path: P) -> Result<bool, ArchiveError> {
+    /// 记录 Unix 权限位；带有文件类型位时同时设置非目录条目的 [`Extract::kind`]
+    ///
+    /// 目录与否以格式自身的标记为准，权限位与之矛盾时不覆盖。
+    pub fn set_mode(&mut self, mode: u32) {
+        self.mode = Some(mode);
+        if let Some(kind) =
+            EntryKind::from_mode(mode).filter(|kind| !self.dir && *kind != EntryKind::Directory)
+        {
+            self.kind = kind;
+        }
+    }
+
+    /// 检测归档文件的加密情况：未加密、部分条目加密、全部条目加密或头部加密
+    pub fn is_password_protected<P: AsRef<Path>>(
+        archive_path: P,
+    ) -> Result<PasswordProtection, ArchiveError> {
+        match registry::open(archive_path, None) {
+            Ok(mut archive) => archive.password_protection(),
+            // 无法识别的格式不视为加密
+            Err(ArchiveError::UnsupportedFormat(_)) => Ok(PasswordProtection::None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 检查密码是否正确，只解开头部或一个加密条目；归档没有加密内容时任何密码都返回 `true`
+    pub fn verify_password<P: AsRef<Path>>(
+        archive_path: P,
+        password: &str,
+    ) -> Result<bool, ArchiveError> {
+        registry::open(archive_path, None)?.verify_password(password)
+    }
+
+    /// 完整性测试：解压每个条目并校验 CRC32 或格式自带的校验和，类似 `7z t`
+    ///
+    /// 有加密内容时先校验密码，缺少密码或密码错误直接返回错误；`cancel` 被取消时返回
+    /// [`ArchiveError::Cancelled`]。`on_progress` 约每 100ms 调用一次，结束时再调用一次。
+    pub fn test_archive<P: AsRef<Path>>(
+        archive_path: P,
+        password: Option<&str>,
+        cancel: &CancelToken,
+        on_progress: &mut dyn FnMut(&TestProgress),
+    ) -> Result<TestReport, ArchiveError> {
         let path = archive_path.as_ref();
-        let extension = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-
-        match extension.as_str() {
-            "zip" => extractors::zip::is_zip_password_protected(path),
-            "7z" => extractors::sevenz::is_7z_password_protected(path),
-            "rar" => extractors::rar::is_rar_password_protected(path),
-            "jar" | "war" | "ear" | "apk" | "aar" | "whl" | "vsix" | "nupkg" | "crx" | "xpi"
-            | "egg" | "kra" | "xps" | "oxps" => extractors::zip::is_zip_password_protected(path),
-            // TAR/CPIO/AR 等格式不支持加密
-            _ => Ok(false),
+        let mut archive = registry::open(path, password)?;
+        if archive.password_protection()? != PasswordProtection::None {
+            match password.filter(|password| !password.is_empty()) {
+                None => return Err(ArchiveError::PasswordRequired),
+                Some(password) if !archive.verify_password(password)? => {
+                    return Err(ArchiveError::WrongPassword)
+                },
+                Some(_) => {},
+            }
         }
+
+        // 有目录索引的格式列举很快，先统计总量以便显示百分比
+        let format = archive.metadata().format;
+        let totals = if matches!(
+            format,
+            ArchiveFormat::Zip | ArchiveFormat::SevenZ | ArchiveFormat::Rar
+        ) {
+            let entries = archive.list()?;
+            let files = entries.iter().filter(|entry| !entry.dir);
+            Some(files.fold((0, 0), |(count, size), entry| {
+                (count + 1, size + entry.size)
+            }))
+        } else {
+            None
+        };
+
+        integrity::run(archive.as_mut(), totals, cancel, on_progress)
+    }
+
+    /// 逐条列举归档条目（含目录），不构建完整的条目列表和目录树，同时累积安全检查
+    ///
+    /// 适合数十万条目的大归档。`nested` 含义同 [`Extract::list_nested_archive_tree`]，
+    /// 为空时列举归档本身。`encoding` 指定 ZIP 等格式条目名的编码，通常为
+    /// [`NameEncoding::Auto`]。`on_entry` 按归档中的顺序收到每个条目，返回 `false` 时提前结束；
+    /// `cancel` 被取消时返回 [`ArchiveError::Cancelled`]。`on_progress` 约每 100ms 调用一次，
+    /// 结束时再调用一次。
+    pub fn list_archive_each<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+        encoding: NameEncoding,
+        cancel: &CancelToken,
+        on_entry: &mut dyn FnMut(Extract) -> Result<bool, ArchiveError>,
+        on_progress: &mut dyn FnMut(&ListProgress),
+    ) -> Result<ListSummary, ArchiveError> {
+        listing::run(
+            archive_path.as_ref(),
+            nested,
+            password,
+            encoding,
+            None,
+            cancel,
+            on_entry,
+            on_progress,
+        )
+    }
+
+    /// 同 [`Extract::list_archive_each`]，但先查找 `cache` 中的列举结果，未命中时边列举边写入
+    ///
+    /// 归档（含全部分卷）的大小或修改时间变化后缓存失效；使用密码或指定条目名编码时不读写缓存。
+    #[allow(clippy::too_many_arguments)]
+    pub fn list_archive_each_cached<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+        encoding: NameEncoding,
+        cache: &ListingCache,
+        cancel: &CancelToken,
+        on_entry: &mut dyn FnMut(Extract) -> Result<bool, ArchiveError>,
+        on_progress: &mut dyn FnMut(&ListProgress),
+    ) -> Result<ListSummary, ArchiveError> {
+        listing::run(
+            archive_path.as_ref(),
+            nested,
+            password,
+            encoding,
+            Some(cache),
+            cancel,
+            on_entry,
+            on_progress,
+        )
+    }
+
+    /// 在后台线程中逐条列举归档条目（含目录），返回迭代器
+    ///
+    /// 打开归档失败等错误作为迭代器的最后一项返回。
+    pub fn iter_archive<P: Into<PathBuf>>(
+        archive_path: P,
+        nested: Vec<String>,
+        password: Option<String>,
+    ) -> EntryIter {
+        EntryIter::spawn(archive_path.into(), nested, password)
     }
 
     /// 列举归档文件（不解压内容），并构建树结构
+    ///
+    /// 格式优先根据文件头魔数识别，无法识别时再根据扩展名判断。
     pub fn list_archive_tree<P: AsRef<Path>>(
         archive_path: P,
         password: Option<&str>,
     ) -> Result<Vec<Extract>, ArchiveError> {
-        let path = archive_path.as_ref();
-        let extension = path
+        Self::list_nested_archive_tree(archive_path, &[], password)
+    }
+
+    /// 压缩比（解压后大小 / 压缩后大小），压缩后大小未知或为 0 时为 `None`
+    pub fn compression_ratio(&self) -> Option<f64> {
+        self.compressed_size
+            .filter(|compressed| *compressed > 0)
+            .map(|compressed| self.size as f64 / compressed as f64)
+    }
+
+    /// 列举归档文件并构建树结构，同时生成安全检查报告
+    pub fn inspect_archive<P: AsRef<Path>>(
+        archive_path: P,
+        password: Option<&str>,
+    ) -> Result<ArchiveListing, ArchiveError> {
+        Self::inspect_nested_archive(archive_path, &[], password, NameEncoding::Auto)
+    }
+
+    /// 列举嵌套在归档内部的归档并构建树结构，同时生成安全检查报告
+    ///
+    /// 嵌套归档的自身大小未知，不做整体压缩比检查。`encoding` 同
+    /// [`Extract::list_archive_each`]，`nested` 中的条目名须按同样的编码列举得到。
+    pub fn inspect_nested_archive<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+        encoding: NameEncoding,
+    ) -> Result<ArchiveListing, ArchiveError> {
+        let archive_path = archive_path.as_ref();
+        let mut archive = registry::open(archive_path, password)?;
+        archive.set_name_encoding(encoding);
+        let metadata = archive.metadata();
+        let (archive_size, volumes) = if nested.is_empty() {
+            (metadata.archive_size.unwrap_or(0), metadata.volumes)
+        } else {
+            (0, 1)
+        };
+        let entries = nested::list_nested(archive.as_mut(), nested, password, encoding)?;
+
+        let safety = SafetyReport::analyze(&entries, archive_size, &SafetyLimits::default());
+
+        // 只有扩展名表明是软件包时才重新打开读取清单，读取失败不影响列举结果
+        let name = match nested.last() {
+            Some(name) => name.clone(),
+            None => archive_path.to_string_lossy().into_owned(),
+        };
+        let is_package = Path::new(&name)
             .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("")
-            .to_lowercase();
-
-        let entries = match extension.as_str() {
-            "zip" => extractors::zip::list_zip_entries(path, password)?,
-            "tar" => extractors::tar::list_tar_entries(path)?,
-            "gz" | "tgz" => extractors::tar::list_tar_gz_entries(path)?,
-            "bz2" | "tbz2" => extractors::tar::list_tar_bz2_entries(path)?,
-            "xz" | "txz" => extractors::tar::list_tar_xz_entries(path)?,
-            "zst" | "tzst" => extractors::zst::list_tar_zst_entries(path)?,
-            "7z" => extractors::sevenz::list_7z_entries(path, password)?,
-            "rar" => extractors::rar::list_rar_entries(path, password)?,
-            "cpio" => extractors::cpio::list_cpio_entries(path)?,
-            "ar" | "deb" | "a" => extractors::ar::list_ar_entries(path)?,
-            // ZIP 本质但带特殊扩展名的格式
-            "jar" | "war" | "ear" | "apk" | "aar" | "whl" | "vsix" | "nupkg" | "crx" | "xpi"
-            | "egg" | "kra" | "xps" | "oxps" => extractors::zip::list_zip_entries(path, password)?,
-            // 对于其他格式，返回错误
-            _ => return Err(ArchiveError::UnsupportedFormat(extension)),
+            .and_then(|extension| extension.to_str())
+            .and_then(PackageKind::from_extension)
+            .is_some();
+        let package = if is_package {
+            registry::open(archive_path, password)
+                .and_then(|mut archive| {
+                    archive.set_name_encoding(encoding);
+                    nested::with_nested_archive(
+                        archive.as_mut(),
+                        nested,
+                        password,
+                        encoding,
+                        &mut |archive| package::inspect(archive, &name, false),
+                    )
+                })
+                .unwrap_or_else(|e| {
+                    log::warn!("Failed to read package manifest: {}", e);
+                    None
+                })
+        } else {
+            None
+        };
+
+        Ok(ArchiveListing {
+            entries: Self::build_tree(entries),
+            safety,
+            volumes,
+            package,
+        })
+    }
+
+    /// 识别软件包类型并读取清单中的名称、版本、依赖、权限等信息，不是软件包时返回 `None`
+    ///
+    /// 先按扩展名（jar、whl、apk、nupkg、vsix、crx 等）判断类型，扩展名无法判断时
+    /// 再根据包内的清单文件判断。`nested` 同 [`Extract::list_nested_archive`]。
+    pub fn package_info<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+    ) -> Result<Option<PackageInfo>, ArchiveError> {
+        let name = match nested.last() {
+            Some(name) => name.clone(),
+            None => archive_path.as_ref().to_string_lossy().into_owned(),
         };
+        let mut archive = registry::open(archive_path, password)?;
+        let encoding = NameEncoding::Auto;
+        nested::with_nested_archive(
+            archive.as_mut(),
+            nested,
+            password,
+            encoding,
+            &mut |archive| package::inspect(archive, &name, true),
+        )
+    }
+
+    /// 读取 `docker save` 导出的 TAR 或 OCI 镜像布局中的镜像清单和配置，不是容器镜像时返回 `None`
+    ///
+    /// 需要顺序读完整个归档。`nested` 同 [`Extract::list_nested_archive`]。
+    pub fn image_info<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+    ) -> Result<Option<ImageInfo>, ArchiveError> {
+        let mut archive = registry::open(archive_path, password)?;
+        nested::with_nested_archive(
+            archive.as_mut(),
+            nested,
+            password,
+            NameEncoding::Auto,
+            &mut |archive| image::inspect(archive),
+        )
+    }
+
+    /// 按从下到上的顺序叠加容器镜像的各层并处理 whiteout，返回合并后的文件系统
+    ///
+    /// `image` 为 [`ImageInfo::images`] 中的下标。先读取镜像清单，再重新打开归档读取各层。
+    pub fn image_filesystem<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+        image: usize,
+    ) -> Result<ImageFilesystem, ArchiveError> {
+        let archive_path = archive_path.as_ref();
+        let manifest = Self::image_info(archive_path, nested, password)?
+            .and_then(|info| info.images.into_iter().nth(image))
+            .ok_or_else(|| ArchiveError::Other(format!("Container image not found: {image}")))?;
+        let mut archive = registry::open(archive_path, password)?;
+        nested::with_nested_archive(
+            archive.as_mut(),
+            nested,
+            password,
+            NameEncoding::Auto,
+            &mut |archive| image::merge_layers(archive, &manifest),
+        )
+    }
+
+    /// 列举归档文件（不解压内容），返回扁平的条目列表
+    pub fn list_archive<P: AsRef<Path>>(
+        archive_path: P,
+        password: Option<&str>,
+    ) -> Result<Vec<Extract>, ArchiveError> {
+        Self::list_nested_archive(archive_path, &[], password)
+    }
+
+    /// 列举嵌套在归档内部的归档，并构建树结构
+    ///
+    /// `nested` 为从外到内的条目名，例如 `["dist/app.tar.gz", "lib/core.jar"]`，
+    /// 为空时等同于 [`Extract::list_archive_tree`]。各层使用同一个密码。
+    pub fn list_nested_archive_tree<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+    ) -> Result<Vec<Extract>, ArchiveError> {
+        let entries = Self::list_nested_archive(archive_path, nested, password)?;
 
         let tree = Self::build_tree(entries);
         Ok(tree)
     }
 
-    /// 将扁平的条目列表构建为嵌套的目录树
-    pub fn build_tree(mut entries: Vec<Extract>) -> Vec<Extract> {
-        // 按路径排序，确保父目录在子项之前
-        entries.sort_by(|a, b| a.name.cmp(&b.name));
+    /// 列举嵌套在归档内部的归档，返回扁平的条目列表
+    ///
+    /// 中间层归档以流的方式读取；需要随机访问的大归档（超过 64 MiB）和 RAR 会写入临时文件。
+    pub fn list_nested_archive<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        password: Option<&str>,
+    ) -> Result<Vec<Extract>, ArchiveError> {
+        let mut archive = registry::open(archive_path, password)?;
+        nested::list_nested(archive.as_mut(), nested, password, NameEncoding::Auto)
+    }
 
-        let mut root_items = Vec::new();
-        let mut dirs_map: HashMap<String, Vec<Extract>> = HashMap::new();
+    /// 解压归档中的单个文件到 `dest_dir`，返回写出的文件路径
+    ///
+    /// `entry_name` 为列举结果中的完整条目名；输出文件只保留条目的文件名部分，
+    /// 不同目录下的同名条目解压到同一个 `dest_dir` 会互相覆盖。
+    pub fn extract_entry<P: AsRef<Path>>(
+        archive_path: P,
+        entry_name: &str,
+        password: Option<&str>,
+        dest_dir: &Path,
+    ) -> Result<PathBuf, ArchiveError> {
+        Self::extract_nested_entry(
+            archive_path,
+            &[],
+            entry_name,
+            password,
+            NameEncoding::Auto,
+            dest_dir,
+        )
+    }
+
+    /// 解压嵌套归档中的单个文件到 `dest_dir`，返回写出的文件路径
+    ///
+    /// 中间层归档以流的方式读取，需要随机访问的大归档和 RAR 会写入临时文件；`nested` 为空时等同于
+    /// [`Extract::extract_entry`]。`encoding` 须与列举时使用的相同，否则可能找不到条目。
+    pub fn extract_nested_entry<P: AsRef<Path>>(
+        archive_path: P,
+        nested: &[String],
+        entry_name: &str,
+        password: Option<&str>,
+        encoding: NameEncoding,
+        dest_dir: &Path,
+    ) -> Result<PathBuf, ArchiveError> {
+        let mut archive = registry::open(archive_path, password)?;
+        archive.set_name_encoding(encoding);
+        nested::with_nested_archive(
+            archive.as_mut(),
+            nested,
+            password,
+            encoding,
+            &mut |archive| archive.extract_entry(entry_name, dest_dir),
+        )
+    }
+
+    /// 将扁平的条目列表构建为嵌套的目录树
+    ///
+    /// 归档中没有单独条目的父目录（如只有 `src/main.rs` 而没有 `src/`）会自动补出；
+    /// 文件条目下还有子条目时，文件保持原样，同名目录另行补出；
+    /// 目录的 `size` 为其下所有文件大小之和，`file_count` 为文件总数；
+    /// 同级条目目录在前，再按名称排序。
+    pub fn build_tree(entries: Vec<Extract>) -> Vec<Extract> {
+        let mut root = TreeNode::default();
 
         for entry in entries {
-            let path_parts: Vec<&str> = entry.name.trim_end_matches('/').split('/').collect();
+            let key = entry
+                .name
+                .trim_start_matches("./")
+                .trim_matches('/')
+                .to_string();
+            // 跳过 `./`、`/` 这类指向归档根目录的条目
+            if key.is_empty() {
+                continue;
+            }
 
-            if path_parts.len() == 1 {
-                root_items.push(entry);
-            } else {
-                let parent_path = path_parts[..path_parts.len() - 1].join("/");
-                dirs_map.entry(parent_path).or_default().push(entry);
+            let mut node = &mut root;
+            for part in key.split('/').filter(|part| !part.is_empty()) {
+                node = node.children.entry(part.to_string()).or_default();
+            }
+            // 同名条目中，显式的目录条目不覆盖已有的文件条目
+            if !(entry.dir && node.entry.as_ref().is_some_and(|existing| !existing.dir)) {
+                node.entry = Some(entry);
             }
         }
 
-        // 递归构建目录树
-        Self::build_tree_recursive(&mut root_items, &dirs_map);
-        root_items
+        TreeNode::into_extracts(root.children, "")
     }
+}
+
+/// 构建目录树时的中间节点，`entry` 为空表示需要补出的隐式目录
+#[derive(Default)]
+struct TreeNode {
+    entry: Option<Extract>,
+    children: BTreeMap<String, TreeNode>,
+}
 
-    fn build_tree_recursive(items: &mut Vec<Extract>, dirs_map: &HashMap<String, Vec<Extract>>) {
-        for item in items.iter_mut() {
-            if item.dir {
-                let key = item.name.trim_end_matches('/');
-                if let Some(children) = dirs_map.get(key) {
-                    let mut child_items = children.clone();
-                    Self::build_tree_recursive(&mut child_items, dirs_map);
-                    item.children = Some(child_items);
-                }
+impl TreeNode {
+    fn into_extracts(children: BTreeMap<String, TreeNode>, prefix: &str) -> Vec<Extract> {
+        let mut items = Vec::with_capacity(children.len());
+        for (name, mut node) in children {
+            // 文件条目下还有子条目（如同时存在 `a` 和 `a/b`）时保留文件本身，目录另行补出
+            if !node.children.is_empty() && node.entry.as_ref().is_some_and(|e| !e.dir) {
+                items.extend(node.entry.take());
             }
+            items.push(node.into_extract(format!("{prefix}{name}/")));
         }
+
+        items.sort_by(|a, b| {
+            b.dir
+                .cmp(&a.dir)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        items
+    }
+
+    fn into_extract(self, dir_name: String) -> Extract {
+        let is_dir = !self.children.is_empty() || self.entry.as_ref().map_or(true, |e| e.dir);
+        let mut item = self
+            .entry
+            .unwrap_or_else(|| Extract::new(dir_name.clone(), 0, None, true));
+        if !is_dir {
+            return item;
+        }
+
+        let children = Self::into_extracts(self.children, &dir_name);
+        item.dir = true;
+        item.kind = EntryKind::Directory;
+        item.size = children.iter().map(|child| child.size).sum();
+        item.file_count = Some(
+            children
+                .iter()
+                .map(|child| child.file_count.unwrap_or(u64::from(!child.dir)))
+                .sum(),
+        );
+        item.children = Some(children);
+        item
     }
 }
 
@@ -193,7 +718,7 @@ pub extern "C" fn archive_free_string(s: *mut std::os::raw::c_char) {
 }
 
 /// 检测归档文件是否需要密码（C ABI）
-/// 返回值: 1 = 需要密码, 0 = 不需要密码, -1 = 出错
+/// 返回值: 1 = 需要密码（头部或全部条目加密）, 2 = 部分条目加密, 0 = 未加密, -1 = 出错
 #[cfg(feature = "capi")]
 #[no_mangle]
 pub extern "C" fn archive_is_password_protected(path: *const std::os::raw::c_char) -> i32 {
@@ -211,8 +736,209 @@ pub extern "C" fn archive_is_password_protected(path: *const std::os::raw::c_cha
     };
 
     match Extract::is_password_protected(path_str) {
-        Ok(true) => 1,
-        Ok(false) => 0,
+        Ok(PasswordProtection::Headers | PasswordProtection::All) => 1,
+        Ok(PasswordProtection::Partial) => 2,
+        Ok(PasswordProtection::None) => 0,
         Err(_) => -1,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{EntryTime, Extract};
+    #[cfg(feature = "zip")]
+    use std::{io::Write, path::PathBuf};
+
+    fn file(name: &str, size: u64) -> Extract {
+        Extract::new(name.to_string(), size, None, false)
+    }
+
+    #[cfg(feature = "zip")]
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("quicklook_lib_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[cfg(feature = "zip")]
+    fn zip(files: &[(&str, &[u8])], method: zip::CompressionMethod) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default().compression_method(method);
+        for (name, data) in files {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(data).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[cfg(all(feature = "zip", feature = "tar"))]
+    fn tar_gz(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, data) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            builder.append_data(&mut header, name, *data).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// `outer.zip` 中有 `README` 和 `dist/app.tar.gz`，后者又包含 `lib/readme.txt` 和
+    /// `lib/core.zip`（其中为 `core.txt`）
+    #[cfg(all(feature = "zip", feature = "tar"))]
+    fn nested_fixture(dir: &std::path::Path) -> PathBuf {
+        let deflated = zip::CompressionMethod::Deflated;
+        let core = zip(&[("core.txt", b"core")], deflated);
+        let app = tar_gz(&[("lib/readme.txt", b"inner readme"), ("lib/core.zip", &core)]);
+        let outer = zip(
+            &[("README", b"outer readme"), ("dist/app.tar.gz", &app)],
+            deflated,
+        );
+        let path = dir.join("outer.zip");
+        std::fs::write(&path, outer).unwrap();
+        path
+    }
+
+    #[test]
+    #[cfg(all(feature = "zip", feature = "tar"))]
+    fn extracts_entries_through_nested_archives() {
+        let dir = scratch_dir("extract");
+        let path = nested_fixture(&dir);
+        let dest = dir.join("out");
+        let app = "dist/app.tar.gz".to_string();
+
+        let readme = Extract::extract_entry(&path, "README", None, &dest).unwrap();
+        assert_eq!(std::fs::read(readme).unwrap(), b"outer readme");
+
+        let extract = |nested: &[String], entry: &str| {
+            let encoding = super::NameEncoding::Auto;
+            Extract::extract_nested_entry(&path, nested, entry, None, encoding, &dest)
+        };
+        let inner = extract(std::slice::from_ref(&app), "lib/readme.txt").unwrap();
+        assert_eq!(std::fs::read(inner).unwrap(), b"inner readme");
+        let core = extract(&[app.clone(), "lib/core.zip".to_string()], "core.txt").unwrap();
+        assert_eq!(std::fs::read(core).unwrap(), b"core");
+
+        assert!(matches!(
+            extract(&[app], "lib/missing.txt"),
+            Err(super::ArchiveError::EntryNotFound(_))
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(all(feature = "zip", feature = "tar"))]
+    fn lists_archives_nested_two_levels_deep() {
+        let dir = scratch_dir("list");
+        let path = nested_fixture(&dir);
+        let names = |nested: &[&str]| {
+            let nested: Vec<String> = nested.iter().map(|name| name.to_string()).collect();
+            let entries = Extract::list_nested_archive(&path, &nested, None).unwrap();
+            entries
+                .into_iter()
+                .map(|entry| entry.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&[]), ["README", "dist/app.tar.gz"]);
+        assert_eq!(
+            names(&["dist/app.tar.gz"]),
+            ["lib/readme.txt", "lib/core.zip"]
+        );
+        assert_eq!(names(&["dist/app.tar.gz", "lib/core.zip"]), ["core.txt"]);
+        assert!(Extract::list_nested_archive(&path, &["README".to_string()], None).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(feature = "zip")]
+    fn test_archive_detects_corrupted_crc() {
+        let dir = scratch_dir("crc");
+        let stored = zip::CompressionMethod::Stored;
+        let mut data = zip(
+            &[("good.txt", b"intact"), ("bad.txt", b"hello world")],
+            stored,
+        );
+        let offset = data.windows(11).position(|w| w == b"hello world").unwrap();
+        data[offset] = b'j';
+        let path = dir.join("corrupt.zip");
+        std::fs::write(&path, data).unwrap();
+
+        let cancel = super::CancelToken::new();
+        let report = Extract::test_archive(&path, None, &cancel, &mut |_| {}).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!((report.passed, report.failed), (1, 1));
+        let bad = &report.entries[1];
+        assert_eq!(bad.name, "bad.txt");
+        assert!(!bad.passed && bad.error.is_some());
+        assert_eq!(bad.expected_crc32, Some(crc32fast::hash(b"hello world")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn build_tree_synthesizes_missing_parents() {
+        let tree = Extract::build_tree(vec![
+            file("src/main.rs", 10),
+            file("README", 3),
+            file("src/sub/a.txt", 5),
+        ]);
+
+        let names: Vec<&str> = tree.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["src/", "README"]);
+
+        let src = &tree[0];
+        assert!(src.dir);
+        assert_eq!(src.size, 15);
+        assert_eq!(src.file_count, Some(2));
+        let children: Vec<&str> = src
+            .children
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|e| e.name.as_str())
+            .collect();
+        assert_eq!(children, ["src/sub/", "src/main.rs"]);
+    }
+
+    #[test]
+    fn build_tree_merges_explicit_directories() {
+        let tree = Extract::build_tree(vec![
+            file("./docs/guide.md", 4),
+            Extract::new(
+                "./docs/".to_string(),
+                0,
+                EntryTime::from_unix(1_704_067_200),
+                true,
+            ),
+        ]);
+
+        assert_eq!(tree.len(), 1);
+        assert_eq!(tree[0].name, "./docs/");
+        assert_eq!(
+            tree[0]
+                .last_modified
+                .map(|time| time.to_string())
+                .as_deref(),
+            Some("2024-01-01T00:00:00Z")
+        );
+        assert_eq!(tree[0].file_count, Some(1));
+    }
+
+    #[test]
+    fn build_tree_keeps_files_with_children() {
+        let mut entry = file("a", 7);
+        entry.crc32 = Some(0x1234_5678);
+        let tree = Extract::build_tree(vec![entry, file("a/b", 2)]);
+
+        let names: Vec<&str> = tree.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["a/", "a"]);
+        assert!(tree[0].dir);
+        assert_eq!(tree[0].size, 2);
+        assert_eq!(tree[0].file_count, Some(1));
+        assert!(!tree[1].dir);
+        assert_eq!(tree[1].size, 7);
+        assert_eq!(tree[1].crc32, Some(0x1234_5678));
+    }
+}
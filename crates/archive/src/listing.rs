@@ -0,0 +1,204 @@
+//! 流式列举：边读边交出条目，不构建完整的条目列表和目录树
+//!
+//! 数十万条目的归档（node_modules 打包、Android 系统镜像）一次性列举会长时间占用内存，
+//! 调用方可以逐条处理，随时取消。安全检查用 [`SafetyAnalyzer`] 逐条累积。
+
+use crate::cache::ListingCache;
+use crate::integrity::PROGRESS_INTERVAL;
+use crate::reader::ListFn;
+use crate::{
+    nested, registry, ArchiveError, CancelToken, Extract, NameEncoding, SafetyAnalyzer,
+    SafetyLimits, SafetyReport,
+};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Instant;
+
+/// 迭代器与后台列举线程之间最多缓冲的条目数
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// 列举进度
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListProgress {
+    /// 已列举的条目数（含目录）
+    pub entries: u64,
+    /// 已列举条目的解压后大小之和
+    pub total_size: u64,
+}
+
+/// 列举结束后的汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct ListSummary {
+    /// 已列举的条目数；`on_entry` 提前结束时只计到结束为止
+    pub entries: u64,
+    /// 安全检查报告
+    pub safety: SafetyReport,
+    /// 分卷数量，非分卷归档和嵌套归档为 1
+    pub volumes: usize,
+    /// 结果是否来自列举缓存
+    pub from_cache: bool,
+}
+
+/// 逐条列举 `archive_path`（`nested` 为空时）或其中的嵌套归档
+///
+/// 参数与 [`crate::Extract::list_archive_each_cached`] 相同。提供 `cache` 时先查缓存，
+/// 未命中则边列举边写入；使用密码时不读写缓存，避免头部加密的归档不输入密码也能看到条目。
+/// 缓存的是自动识别编码的结果，指定条目名编码时同样不读写缓存。
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run(
+    archive_path: &Path,
+    nested: &[String],
+    password: Option<&str>,
+    encoding: NameEncoding,
+    cache: Option<&ListingCache>,
+    cancel: &CancelToken,
+    on_entry: &mut dyn FnMut(Extract) -> Result<bool, ArchiveError>,
+    on_progress: &mut dyn FnMut(&ListProgress),
+) -> Result<ListSummary, ArchiveError> {
+    let cache = cache.filter(|_| password.is_none() && encoding == NameEncoding::Auto);
+    if let Some(reader) = cache.and_then(|cache| cache.lookup(archive_path, nested)) {
+        let (archive_size, volumes) = (reader.archive_size, reader.volumes);
+        let mut summary = emit(archive_size, cancel, on_entry, on_progress, |f| {
+            for entry in reader {
+                if !f(entry?)? {
+                    break;
+                }
+            }
+            Ok(())
+        })?;
+        summary.volumes = volumes;
+        summary.from_cache = true;
+        return Ok(summary);
+    }
+
+    let mut archive = registry::open(archive_path, password)?;
+    archive.set_name_encoding(encoding);
+    let metadata = archive.metadata();
+    // 嵌套归档的自身大小未知，不做整体压缩比检查
+    let (archive_size, volumes) = if nested.is_empty() {
+        (metadata.archive_size.unwrap_or(0), metadata.volumes)
+    } else {
+        (0, 1)
+    };
+    let mut writer =
+        cache.and_then(|cache| cache.writer(archive_path, nested, archive_size, volumes));
+    // `on_entry` 提前结束时列表不完整，不写入缓存
+    let mut complete = true;
+
+    let mut summary = emit(archive_size, cancel, on_entry, on_progress, |f| {
+        nested::each_nested(
+            archive.as_mut(),
+            nested,
+            password,
+            encoding,
+            cancel,
+            &mut |entry| {
+                if let Some(writer) = &mut writer {
+                    writer.push(&entry);
+                }
+                let more = f(entry)?;
+                complete &= more;
+                Ok(more)
+            },
+        )
+    })?;
+    if let Some(writer) = writer.filter(|_| complete) {
+        writer.commit();
+    }
+    summary.volumes = volumes;
+    Ok(summary)
+}
+
+/// 把 `source` 交出的条目依次做安全检查、统计进度后交给 `on_entry`
+fn emit(
+    archive_size: u64,
+    cancel: &CancelToken,
+    on_entry: &mut dyn FnMut(Extract) -> Result<bool, ArchiveError>,
+    on_progress: &mut dyn FnMut(&ListProgress),
+    source: impl FnOnce(&mut ListFn<'_>) -> Result<(), ArchiveError>,
+) -> Result<ListSummary, ArchiveError> {
+    let mut analyzer = SafetyAnalyzer::new(archive_size, SafetyLimits::default());
+    let mut progress = ListProgress::default();
+    let mut last_report = Instant::now();
+
+    source(&mut |entry| {
+        cancel.check()?;
+        analyzer.push(&entry);
+        progress.entries += 1;
+        progress.total_size += entry.size;
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            on_progress(&progress);
+            last_report = Instant::now();
+        }
+        on_entry(entry)
+    })?;
+    on_progress(&progress);
+
+    Ok(ListSummary {
+        entries: progress.entries,
+        safety: analyzer.finish(),
+        volumes: 1,
+        from_cache: false,
+    })
+}
+
+/// 在后台线程中列举归档的迭代器，由 [`crate::Extract::iter_archive`] 创建
+///
+/// 后台线程最多领先 [`CHANNEL_CAPACITY`] 个条目；出错时最后产出一个 `Err`。
+/// 迭代器被丢弃时自动取消列举。
+pub struct EntryIter {
+    receiver: Receiver<Result<Extract, ArchiveError>>,
+    cancel: CancelToken,
+}
+
+impl EntryIter {
+    pub(crate) fn spawn(path: PathBuf, nested: Vec<String>, password: Option<String>) -> Self {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let cancel = CancelToken::new();
+        let token = cancel.clone();
+
+        std::thread::spawn(move || {
+            let password = password.as_deref();
+            let result = registry::open(&path, password).and_then(|mut archive| {
+                let encoding = NameEncoding::Auto;
+                nested::each_nested(
+                    archive.as_mut(),
+                    &nested,
+                    password,
+                    encoding,
+                    &token,
+                    &mut |entry| {
+                        token.check()?;
+                        // 接收端已丢弃
+                        Ok(sender.send(Ok(entry)).is_ok())
+                    },
+                )
+            });
+            if let Err(e) = result {
+                let _ = sender.send(Err(e));
+            }
+        });
+
+        Self { receiver, cancel }
+    }
+
+    /// 可在其他线程中取消列举的标记
+    pub fn cancel_token(&self) -> &CancelToken {
+        &self.cancel
+    }
+}
+
+impl Iterator for EntryIter {
+    type Item = Result<Extract, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for EntryIter {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
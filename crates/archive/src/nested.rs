@@ -0,0 +1,69 @@
+//! 嵌套归档（归档中的归档）的浏览与提取
+//!
+//! 外层条目的数据以流的形式交给内层读取器，一般不会把中间归档解压到磁盘：
+//! TAR 系列、CPIO、AR 直接顺序读取；ZIP、7Z、ISO 等需要随机访问，64 MiB 以内读入内存，
+//! 超过时写入系统临时目录；RAR 只能从文件打开，总是写入临时目录。
+//! 临时文件在读取结束后删除。
+
+use crate::reader::{ArchiveReader, ListFn};
+use crate::{registry, ArchiveError, CancelToken, Extract, NameEncoding};
+
+/// 沿 `chain` 逐层打开嵌套归档，列举最内层归档的条目
+///
+/// `chain` 为从外到内的条目名，例如 `["dist/app.tar.gz", "lib/core.jar"]`；
+/// `encoding` 应用于每一层归档的条目名。
+pub(crate) fn list_nested(
+    archive: &mut dyn ArchiveReader<'_>,
+    chain: &[String],
+    password: Option<&str>,
+    encoding: NameEncoding,
+) -> Result<Vec<Extract>, ArchiveError> {
+    match chain.split_first() {
+        None => archive.list(),
+        Some((outer, rest)) => archive.with_entry(outer, |data| {
+            let mut inner = registry::open_stream(Box::new(data), outer, password)?;
+            inner.set_name_encoding(encoding);
+            list_nested(inner.as_mut(), rest, password, encoding)
+        }),
+    }
+}
+
+/// 沿 `chain` 逐层打开嵌套归档，逐条列举最内层归档的条目
+///
+/// `cancel` 交给每一层归档，定位中间层条目和列举时都会检查。
+pub(crate) fn each_nested(
+    archive: &mut dyn ArchiveReader<'_>,
+    chain: &[String],
+    password: Option<&str>,
+    encoding: NameEncoding,
+    cancel: &CancelToken,
+    f: &mut ListFn<'_>,
+) -> Result<(), ArchiveError> {
+    archive.set_cancel(cancel);
+    match chain.split_first() {
+        None => archive.list_each(f),
+        Some((outer, rest)) => archive.read_entry(outer, &mut |data| {
+            let mut inner = registry::open_stream(Box::new(data), outer, password)?;
+            inner.set_name_encoding(encoding);
+            each_nested(inner.as_mut(), rest, password, encoding, cancel, f)
+        }),
+    }
+}
+
+/// 沿 `chain` 逐层打开嵌套归档，把最内层归档的读取器交给 `f` 处理
+pub(crate) fn with_nested_archive<T>(
+    archive: &mut dyn ArchiveReader<'_>,
+    chain: &[String],
+    password: Option<&str>,
+    encoding: NameEncoding,
+    f: &mut dyn FnMut(&mut dyn ArchiveReader<'_>) -> Result<T, ArchiveError>,
+) -> Result<T, ArchiveError> {
+    match chain.split_first() {
+        None => f(archive),
+        Some((outer, rest)) => archive.with_entry(outer, |data| {
+            let mut inner = registry::open_stream(Box::new(data), outer, password)?;
+            inner.set_name_encoding(encoding);
+            with_nested_archive(inner.as_mut(), rest, password, encoding, f)
+        }),
+    }
+}
@@ -0,0 +1,858 @@
+//! 软件包清单：识别软件包类型，读取其中的清单文件
+//!
+//! | 类型 | 扩展名 | 清单 |
+//! | --- | --- | --- |
+//! | Java | jar、war、ear | `META-INF/MANIFEST.MF`，缺少名称时参考 `META-INF/maven/**/pom.properties` |
+//! | Python wheel | whl | `*.dist-info/METADATA`、`WHEEL`，并对照 `RECORD` 检查缺失的文件 |
+//! | Python egg | egg | `EGG-INFO/PKG-INFO`、`requires.txt` |
+//! | Android | apk、aar | `AndroidManifest.xml`，APK 中为二进制 XML，解码后读取 |
+//! | NuGet | nupkg | 根目录的 `*.nuspec` |
+//! | VSIX | vsix | `extension.vsixmanifest` |
+//! | 浏览器扩展 | crx、xpi | `manifest.json`，`__MSG_*__` 按 `default_locale` 替换为本地化文本 |
+//! | Debian | deb、udeb | `control.tar.*` 中的 `control` 和维护脚本，见 [`crate::extractors::deb`] |
+//! | RPM | rpm | 主头部，见 [`crate::extractors::rpm`] |
+//!
+//! deb 和 rpm 不是 ZIP，由各自的读取器通过 [`ArchiveReader::package_info`] 提供。
+
+use crate::reader::ArchiveReader;
+use crate::{axml, ArchiveError, ArchiveFormat};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::Read;
+
+/// 清单文件读取的上限，超出部分忽略
+const MAX_MANIFEST_SIZE: u64 = 4 * 1024 * 1024;
+
+/// 软件包类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageKind {
+    /// jar、war、ear
+    Jar,
+    Wheel,
+    Egg,
+    Apk,
+    Aar,
+    Nupkg,
+    Vsix,
+    /// Chrome 的 crx 和 Firefox 的 xpi，清单格式相同
+    WebExtension,
+    Deb,
+    Rpm,
+}
+
+impl PackageKind {
+    /// 根据扩展名（不含 `.`，不区分大小写）判断软件包类型
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        Some(match extension.to_lowercase().as_str() {
+            "jar" | "war" | "ear" => Self::Jar,
+            "whl" => Self::Wheel,
+            "egg" => Self::Egg,
+            "apk" => Self::Apk,
+            "aar" => Self::Aar,
+            "nupkg" => Self::Nupkg,
+            "vsix" => Self::Vsix,
+            "crx" | "xpi" => Self::WebExtension,
+            "deb" | "udeb" => Self::Deb,
+            "rpm" => Self::Rpm,
+            _ => return None,
+        })
+    }
+
+    /// 根据包内的清单文件判断软件包类型，`names` 为全部文件条目名
+    fn detect(names: &HashSet<&str>) -> Option<Self> {
+        if names.contains("AndroidManifest.xml") {
+            let is_apk = names.contains("classes.dex") || names.contains("resources.arsc");
+            return Some(if is_apk { Self::Apk } else { Self::Aar });
+        }
+        let kind = if names.iter().any(|name| is_root_nuspec(name)) {
+            Self::Nupkg
+        } else if names.contains("extension.vsixmanifest") {
+            Self::Vsix
+        } else if names.iter().any(|name| is_wheel_metadata(name)) {
+            Self::Wheel
+        } else if names.contains("EGG-INFO/PKG-INFO") {
+            Self::Egg
+        } else if names.contains("META-INF/MANIFEST.MF") {
+            Self::Jar
+        } else if names.contains("manifest.json") {
+            Self::WebExtension
+        } else {
+            return None;
+        };
+        Some(kind)
+    }
+}
+
+/// 清单中的一个字段
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageField {
+    pub key: String,
+    pub value: String,
+}
+
+/// 安装、卸载时执行的脚本
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageScript {
+    /// 脚本名，如 deb 的 `postinst`、rpm 的 `%post`
+    pub name: String,
+    pub content: String,
+}
+
+/// 软件包的身份信息
+#[derive(Debug, Clone, Serialize)]
+pub struct PackageInfo {
+    pub kind: PackageKind,
+    /// 清单文件在包内的条目名
+    pub manifest: String,
+    /// 清单文本；APK 中的二进制 `AndroidManifest.xml` 为解码后的 XML
+    pub manifest_text: String,
+    /// 包名或标识符（Java 为 Bundle-SymbolicName 等，Android 为 package，NuGet 为 id）
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    /// 作者、发布者或供应商
+    pub author: Option<String>,
+    /// 目标架构（deb 的 Architecture、rpm 的 Arch）
+    pub architecture: Option<String>,
+    /// 依赖，保留清单中的写法（如 `requests>=2.0`、`Newtonsoft.Json 13.0.1`）
+    pub dependencies: Vec<String>,
+    /// 申请的权限（Android 的 uses-permission、浏览器扩展的 permissions）
+    pub permissions: Vec<String>,
+    /// 清单中的字段，按出现顺序
+    pub fields: Vec<PackageField>,
+    /// wheel 的 RECORD 中登记、但包内不存在的文件
+    pub missing_files: Vec<String>,
+    /// deb 的维护脚本、rpm 的安装脚本
+    pub scripts: Vec<PackageScript>,
+}
+
+impl PackageInfo {
+    pub(crate) fn new(kind: PackageKind, manifest: &str, manifest_text: String) -> Self {
+        Self {
+            kind,
+            manifest: manifest.to_string(),
+            manifest_text,
+            name: None,
+            version: None,
+            description: None,
+            author: None,
+            architecture: None,
+            dependencies: Vec::new(),
+            permissions: Vec::new(),
+            fields: Vec::new(),
+            missing_files: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+
+    /// 第一个非空的同名字段
+    pub(crate) fn field(&self, keys: &[&str]) -> Option<String> {
+        keys.iter().find_map(|key| {
+            self.fields
+                .iter()
+                .find(|field| field.key.eq_ignore_ascii_case(key) && !field.value.is_empty())
+                .map(|field| field.value.clone())
+        })
+    }
+
+    pub(crate) fn push_field(&mut self, key: &str, value: &str) {
+        self.fields.push(PackageField {
+            key: key.to_string(),
+            value: value.to_string(),
+        });
+    }
+}
+
+/// 识别 `archive` 的软件包类型并读取清单，不是软件包时返回 `None`
+///
+/// `name` 为归档的文件名或条目名，先按其扩展名判断类型；`detect` 为 `true` 时，
+/// 扩展名无法判断（如 `.zip`）的归档再根据包内的清单文件判断。
+pub(crate) fn inspect(
+    archive: &mut dyn ArchiveReader<'_>,
+    name: &str,
+    detect: bool,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    let by_extension = name
+        .rsplit_once('.')
+        .and_then(|(_, extension)| PackageKind::from_extension(extension));
+    if by_extension.is_none() && !detect {
+        return Ok(None);
+    }
+    if archive.metadata().format != ArchiveFormat::Zip {
+        return archive.package_info();
+    }
+
+    let entries = archive.list()?;
+    let names: HashSet<&str> = entries
+        .iter()
+        .filter(|entry| !entry.dir)
+        .map(|entry| entry.name.as_str())
+        .collect();
+    let Some(kind) = by_extension.or_else(|| PackageKind::detect(&names)) else {
+        return Ok(None);
+    };
+
+    match kind {
+        PackageKind::Jar => jar(archive, &names),
+        PackageKind::Wheel => wheel(archive, &names),
+        PackageKind::Egg => egg(archive, &names),
+        PackageKind::Apk | PackageKind::Aar => android(archive, kind, &names),
+        PackageKind::Nupkg => nupkg(archive, &names),
+        PackageKind::Vsix => vsix(archive, &names),
+        PackageKind::WebExtension => web_extension(archive, &names),
+        // 按扩展名判断为 deb、rpm 但实际是 ZIP
+        PackageKind::Deb | PackageKind::Rpm => Ok(None),
+    }
+}
+
+fn jar(
+    archive: &mut dyn ArchiveReader<'_>,
+    names: &HashSet<&str>,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    const MANIFEST: &str = "META-INF/MANIFEST.MF";
+    if !names.contains(MANIFEST) {
+        return Ok(None);
+    }
+    let text = read_text(archive, MANIFEST)?;
+    let mut info = PackageInfo::new(PackageKind::Jar, MANIFEST, String::new());
+    for (key, value) in parse_jar_manifest(&text) {
+        info.push_field(&key, &value);
+    }
+    info.manifest_text = text;
+
+    // OSGi 的 Bundle-SymbolicName 可能带有 `;singleton:=true` 等指令
+    info.name = info
+        .field(&[
+            "Bundle-SymbolicName",
+            "Automatic-Module-Name",
+            "Implementation-Title",
+            "Specification-Title",
+            "Bundle-Name",
+        ])
+        .map(|name| {
+            name.split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        });
+    info.version = info.field(&[
+        "Bundle-Version",
+        "Implementation-Version",
+        "Specification-Version",
+    ]);
+    info.author = info.field(&[
+        "Implementation-Vendor",
+        "Bundle-Vendor",
+        "Specification-Vendor",
+    ]);
+    info.description = info.field(&["Bundle-Description"]);
+    if let Some(class_path) = info.field(&["Class-Path"]) {
+        info.dependencies = class_path.split_whitespace().map(str::to_string).collect();
+    }
+
+    // Maven 打包的 jar 记录了坐标，只有一个模块时用来补充名称和版本
+    let mut poms = names
+        .iter()
+        .filter(|name| name.starts_with("META-INF/maven/") && name.ends_with("/pom.properties"));
+    if let (Some(pom), None) = (poms.next(), poms.next()) {
+        let pom = read_text(archive, pom)?;
+        let properties = parse_properties(&pom);
+        let get = |key: &str| {
+            properties
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, value)| value.clone())
+        };
+        if info.name.is_none() {
+            if let (Some(group), Some(artifact)) = (get("groupId"), get("artifactId")) {
+                info.name = Some(format!("{group}:{artifact}"));
+            }
+        }
+        if info.version.is_none() {
+            info.version = get("version");
+        }
+        for (key, value) in &properties {
+            info.push_field(key, value);
+        }
+    }
+
+    Ok(Some(info))
+}
+
+fn wheel(
+    archive: &mut dyn ArchiveReader<'_>,
+    names: &HashSet<&str>,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    let Some(manifest) = names.iter().find(|name| is_wheel_metadata(name)) else {
+        return Ok(None);
+    };
+    let dist_info = manifest.trim_end_matches("METADATA");
+    let text = read_text(archive, manifest)?;
+    let mut info = python_metadata(PackageKind::Wheel, manifest, text);
+
+    let wheel = format!("{dist_info}WHEEL");
+    if names.contains(wheel.as_str()) {
+        let (headers, _) = parse_email_headers(&read_text(archive, &wheel)?);
+        for (key, value) in headers {
+            info.push_field(&key, &value);
+        }
+    }
+
+    let record = format!("{dist_info}RECORD");
+    if names.contains(record.as_str()) {
+        let text = read_text(archive, &record)?;
+        info.missing_files = text
+            .lines()
+            .filter_map(record_path)
+            .filter(|path| !names.contains(path.as_str()))
+            .collect();
+    }
+
+    Ok(Some(info))
+}
+
+fn egg(
+    archive: &mut dyn ArchiveReader<'_>,
+    names: &HashSet<&str>,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    const MANIFEST: &str = "EGG-INFO/PKG-INFO";
+    if !names.contains(MANIFEST) {
+        return Ok(None);
+    }
+    let text = read_text(archive, MANIFEST)?;
+    let mut info = python_metadata(PackageKind::Egg, MANIFEST, text);
+
+    const REQUIRES: &str = "EGG-INFO/requires.txt";
+    if info.dependencies.is_empty() && names.contains(REQUIRES) {
+        // `[extra]` 之后是可选依赖
+        info.dependencies = read_text(archive, REQUIRES)?
+            .lines()
+            .map(str::trim)
+            .take_while(|line| !line.starts_with('['))
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect();
+    }
+
+    Ok(Some(info))
+}
+
+/// 解析 Python 核心元数据（METADATA、PKG-INFO）
+fn python_metadata(kind: PackageKind, manifest: &str, text: String) -> PackageInfo {
+    let (headers, _) = parse_email_headers(&text);
+    let mut info = PackageInfo::new(kind, manifest, String::new());
+    for (key, value) in headers {
+        if key.eq_ignore_ascii_case("Requires-Dist") {
+            info.dependencies.push(value.clone());
+        }
+        info.push_field(&key, &value);
+    }
+    info.manifest_text = text;
+
+    info.name = info.field(&["Name"]);
+    info.version = info.field(&["Version"]);
+    info.description = info.field(&["Summary"]);
+    info.author = info.field(&["Author", "Author-email", "Maintainer", "Maintainer-email"]);
+    info
+}
+
+/// 整理 deb 的 `control` 文件，`scripts` 为 `control.tar.*` 中的维护脚本
+#[cfg(feature = "deb")]
+pub(crate) fn debian(control: String, scripts: Vec<PackageScript>) -> PackageInfo {
+    let (headers, _) = parse_email_headers(&control);
+    let mut info = PackageInfo::new(PackageKind::Deb, "control", String::new());
+    for (key, value) in headers {
+        // 多行字段中单独的 `.` 表示空行
+        let value = value
+            .lines()
+            .map(|line| if line == "." { "" } else { line })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if key.eq_ignore_ascii_case("Pre-Depends") || key.eq_ignore_ascii_case("Depends") {
+            info.dependencies.extend(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|dependency| !dependency.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        info.push_field(&key, &value);
+    }
+    info.manifest_text = control;
+    info.scripts = scripts;
+
+    info.name = info.field(&["Package"]);
+    info.version = info.field(&["Version"]);
+    info.author = info.field(&["Maintainer"]);
+    info.architecture = info.field(&["Architecture"]);
+    // Description 的第一行是简述
+    info.description = info
+        .field(&["Description"])
+        .and_then(|description| description.lines().next().map(str::to_string));
+    info
+}
+
+fn android(
+    archive: &mut dyn ArchiveReader<'_>,
+    kind: PackageKind,
+    names: &HashSet<&str>,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    const MANIFEST: &str = "AndroidManifest.xml";
+    if !names.contains(MANIFEST) {
+        return Ok(None);
+    }
+    // AAR 中的清单是文本 XML，APK 中的是二进制 XML
+    let data = read_bytes(archive, MANIFEST)?;
+    let (elements, text) = if axml::is_axml(&data) {
+        axml::decode(&data)?
+    } else {
+        let text = decode_text(&data);
+        (parse_xml(&text)?, text)
+    };
+
+    let mut info = PackageInfo::new(kind, MANIFEST, text);
+    for element in &elements {
+        match element.path.as_str() {
+            "manifest" | "manifest/uses-sdk" => {
+                for (key, value) in &element.attrs {
+                    info.push_field(key, value);
+                }
+            },
+            "manifest/application" => {
+                if let Some(label) = element.attr("label") {
+                    info.push_field("label", label);
+                }
+            },
+            "manifest/uses-permission" | "manifest/uses-permission-sdk-23" => {
+                if let Some(permission) = element.attr("name") {
+                    info.permissions.push(permission.to_string());
+                }
+            },
+            _ => {},
+        }
+    }
+    info.name = info.field(&["package"]);
+    info.version = info.field(&["versionName", "versionCode"]);
+
+    Ok(Some(info))
+}
+
+fn nupkg(
+    archive: &mut dyn ArchiveReader<'_>,
+    names: &HashSet<&str>,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    let Some(manifest) = names.iter().find(|name| is_root_nuspec(name)) else {
+        return Ok(None);
+    };
+    let text = read_text(archive, manifest)?;
+    let elements = parse_xml(&text)?;
+
+    let mut info = PackageInfo::new(PackageKind::Nupkg, manifest, text);
+    for element in &elements {
+        if let Some(key) = element.path.strip_prefix("package/metadata/") {
+            if !key.contains('/') && !element.text.is_empty() {
+                info.push_field(key, &element.text);
+            }
+        }
+        if element.path.ends_with("/dependency") {
+            if let Some(id) = element.attr("id") {
+                info.dependencies.push(match element.attr("version") {
+                    Some(version) => format!("{id} {version}"),
+                    None => id.to_string(),
+                });
+            }
+        }
+    }
+    info.name = info.field(&["id"]);
+    info.version = info.field(&["version"]);
+    info.description = info.field(&["description", "summary"]);
+    info.author = info.field(&["authors", "owners"]);
+
+    Ok(Some(info))
+}
+
+fn vsix(
+    archive: &mut dyn ArchiveReader<'_>,
+    names: &HashSet<&str>,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    const MANIFEST: &str = "extension.vsixmanifest";
+    if !names.contains(MANIFEST) {
+        return Ok(None);
+    }
+    let text = read_text(archive, MANIFEST)?;
+    let elements = parse_xml(&text)?;
+
+    let mut info = PackageInfo::new(PackageKind::Vsix, MANIFEST, text);
+    for element in &elements {
+        match element.path.as_str() {
+            // 2.0 格式的标识写在属性中
+            "PackageManifest/Metadata/Identity" | "Vsix/Identifier" => {
+                for (key, value) in &element.attrs {
+                    info.push_field(key, value);
+                }
+            },
+            path if path.starts_with("PackageManifest/Metadata/")
+                || path.starts_with("Vsix/Identifier/") =>
+            {
+                let key = path.rsplit('/').next().unwrap_or(path);
+                if !element.text.is_empty() {
+                    info.push_field(key, &element.text);
+                }
+            },
+            path if path.ends_with("Dependencies/Dependency") => {
+                if let Some(id) = element.attr("Id") {
+                    info.dependencies.push(match element.attr("Version") {
+                        Some(version) => format!("{id} {version}"),
+                        None => id.to_string(),
+                    });
+                }
+            },
+            _ => {},
+        }
+    }
+    info.name = info.field(&["Id"]);
+    info.version = info.field(&["Version"]);
+    info.description = info.field(&["Description"]);
+    info.author = info.field(&["Publisher", "Author"]);
+
+    Ok(Some(info))
+}
+
+fn web_extension(
+    archive: &mut dyn ArchiveReader<'_>,
+    names: &HashSet<&str>,
+) -> Result<Option<PackageInfo>, ArchiveError> {
+    const MANIFEST: &str = "manifest.json";
+    if !names.contains(MANIFEST) {
+        return Ok(None);
+    }
+    let text = read_text(archive, MANIFEST)?;
+    let manifest: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| ArchiveError::Other(format!("Invalid manifest.json: {e}")))?;
+
+    // `__MSG_appName__` 形式的文本需要到默认语言的 messages.json 中查找
+    let messages = match manifest["default_locale"].as_str() {
+        Some(locale) => {
+            let path = format!("_locales/{locale}/messages.json");
+            if names.contains(path.as_str()) {
+                serde_json::from_str(&read_text(archive, &path)?).unwrap_or_default()
+            } else {
+                serde_json::Value::Null
+            }
+        },
+        None => serde_json::Value::Null,
+    };
+    let localize = |value: &str| -> String {
+        let Some(key) = value
+            .strip_prefix("__MSG_")
+            .and_then(|rest| rest.strip_suffix("__"))
+        else {
+            return value.to_string();
+        };
+        messages
+            .as_object()
+            .and_then(|messages| {
+                messages
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            })
+            .and_then(|(_, message)| message["message"].as_str())
+            .unwrap_or(value)
+            .to_string()
+    };
+
+    let mut info = PackageInfo::new(PackageKind::WebExtension, MANIFEST, String::new());
+    if let Some(object) = manifest.as_object() {
+        for (key, value) in object {
+            let value = match value {
+                serde_json::Value::String(value) => localize(value),
+                serde_json::Value::Number(value) => value.to_string(),
+                serde_json::Value::Bool(value) => value.to_string(),
+                _ => continue,
+            };
+            info.push_field(key, &value);
+        }
+    }
+    let gecko = &manifest["browser_specific_settings"]["gecko"];
+    let gecko = if gecko.is_null() {
+        &manifest["applications"]["gecko"]
+    } else {
+        gecko
+    };
+    if let Some(id) = gecko["id"].as_str() {
+        info.push_field("gecko.id", id);
+    }
+    for key in ["permissions", "host_permissions"] {
+        if let Some(permissions) = manifest[key].as_array() {
+            info.permissions.extend(
+                permissions
+                    .iter()
+                    .filter_map(|permission| permission.as_str())
+                    .map(str::to_string),
+            );
+        }
+    }
+    info.manifest_text = text;
+
+    info.name = info.field(&["name", "short_name"]);
+    info.version = info.field(&["version"]);
+    info.description = info.field(&["description"]);
+    info.author = info.field(&["author"]).or_else(|| {
+        manifest["author"]["name"]
+            .as_str()
+            .or(manifest["author"]["email"].as_str())
+            .map(str::to_string)
+    });
+
+    Ok(Some(info))
+}
+
+fn is_root_nuspec(name: &str) -> bool {
+    !name.contains('/') && name.to_lowercase().ends_with(".nuspec")
+}
+
+fn is_wheel_metadata(name: &str) -> bool {
+    name.matches('/').count() == 1 && name.ends_with(".dist-info/METADATA")
+}
+
+fn read_bytes(archive: &mut dyn ArchiveReader<'_>, name: &str) -> Result<Vec<u8>, ArchiveError> {
+    archive.with_entry(name, |data| {
+        let mut buffer = Vec::new();
+        data.take(MAX_MANIFEST_SIZE).read_to_end(&mut buffer)?;
+        Ok(buffer)
+    })
+}
+
+/// 读取清单或脚本的文本，超出上限的部分忽略
+#[cfg(feature = "deb")]
+pub(crate) fn read_manifest(data: &mut dyn Read) -> Result<String, ArchiveError> {
+    let mut buffer = Vec::new();
+    data.take(MAX_MANIFEST_SIZE).read_to_end(&mut buffer)?;
+    Ok(decode_text(&buffer))
+}
+
+fn read_text(archive: &mut dyn ArchiveReader<'_>, name: &str) -> Result<String, ArchiveError> {
+    Ok(decode_text(&read_bytes(archive, name)?))
+}
+
+/// 按 UTF-8 解码并去掉 BOM
+fn decode_text(data: &[u8]) -> String {
+    let text = String::from_utf8_lossy(data);
+    text.strip_prefix('\u{feff}').unwrap_or(&text).to_string()
+}
+
+/// 解析 `MANIFEST.MF` 的主段：`Key: Value`，以单个空格开头的行是上一行的续行
+fn parse_jar_manifest(text: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        if line.is_empty() {
+            break;
+        }
+        if let Some(rest) = line.strip_prefix(' ') {
+            if let Some((_, value)) = fields.last_mut() {
+                value.push_str(rest);
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            fields.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    fields
+}
+
+/// 解析 RFC 822 风格的头部（Python 核心元数据），返回头部字段和空行之后的正文
+fn parse_email_headers(text: &str) -> (Vec<(String, String)>, &str) {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            return (headers, &text[offset..]);
+        }
+        if line.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+        } else if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    (headers, "")
+}
+
+/// 解析 Java properties 文件中的 `key=value` 行
+fn parse_properties(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '!']))
+        .filter_map(|line| line.split_once(['=', ':']))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+/// RECORD 是 CSV：`路径,哈希,大小`，路径含逗号时带引号
+fn record_path(line: &str) -> Option<String> {
+    let line = line.trim_end_matches('\r');
+    if line.is_empty() {
+        return None;
+    }
+    let Some(quoted) = line.strip_prefix('"') else {
+        return line.split(',').next().map(str::to_string);
+    };
+    let mut path = String::new();
+    let mut chars = quoted.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if chars.peek() == Some(&'"') => {
+                path.push('"');
+                chars.next();
+            },
+            '"' => break,
+            c => path.push(c),
+        }
+    }
+    Some(path)
+}
+
+/// XML 元素：`path` 为从根元素开始、以 `/` 连接的本地名，属性名同样不含命名空间前缀
+pub(crate) struct XmlElement {
+    pub(crate) path: String,
+    pub(crate) attrs: Vec<(String, String)>,
+    /// 元素自身的文本内容（不含子元素），已去掉首尾空白
+    pub(crate) text: String,
+}
+
+impl XmlElement {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 把文本 XML 展开为元素列表
+#[cfg(feature = "zip")]
+fn parse_xml(text: &str) -> Result<Vec<XmlElement>, ArchiveError> {
+    use quick_xml::events::Event;
+
+    let xml_error = |e: &dyn std::fmt::Display| ArchiveError::Other(format!("Invalid XML: {e}"));
+    let mut reader = quick_xml::Reader::from_str(text);
+    reader.config_mut().expand_empty_elements = true;
+
+    let mut elements = Vec::new();
+    let mut stack: Vec<String> = Vec::new();
+    // 每一层未闭合元素的下标和尚未反转义的文本
+    let mut open: Vec<(usize, String)> = Vec::new();
+    loop {
+        match reader.read_event().map_err(|e| xml_error(&e))? {
+            Event::Start(element) => {
+                stack.push(String::from_utf8_lossy(element.local_name().as_ref()).into_owned());
+                let attrs = element
+                    .attributes()
+                    .flatten()
+                    // `xmlns` 声明不是元素的属性
+                    .filter(|attr| attr.key.as_namespace_binding().is_none())
+                    .map(|attr| {
+                        let key = attr.key.local_name();
+                        let key = String::from_utf8_lossy(key.as_ref());
+                        let value = match attr.unescape_value() {
+                            Ok(value) => value.into_owned(),
+                            Err(_) => String::from_utf8_lossy(&attr.value).into_owned(),
+                        };
+                        (key.into_owned(), value)
+                    })
+                    .collect();
+                open.push((elements.len(), String::new()));
+                elements.push(XmlElement {
+                    path: stack.join("/"),
+                    attrs,
+                    text: String::new(),
+                });
+            },
+            Event::End(_) => {
+                stack.pop();
+                if let Some((index, raw)) = open.pop() {
+                    let text = quick_xml::escape::unescape(&raw)
+                        .map(|text| text.into_owned())
+                        .unwrap_or(raw);
+                    elements[index].text = text.trim().to_string();
+                }
+            },
+            Event::Text(text) => {
+                if let Some((_, raw)) = open.last_mut() {
+                    raw.push_str(&text.decode().map_err(|e| xml_error(&e))?);
+                }
+            },
+            Event::GeneralRef(reference) => {
+                if let Some((_, raw)) = open.last_mut() {
+                    raw.push('&');
+                    raw.push_str(&reference.decode().map_err(|e| xml_error(&e))?);
+                    raw.push(';');
+                }
+            },
+            Event::CData(data) => {
+                if let Some((_, raw)) = open.last_mut() {
+                    let data = data.decode().map_err(|e| xml_error(&e))?;
+                    raw.push_str(&quick_xml::escape::escape(data.as_ref()));
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+    Ok(elements)
+}
+
+/// 未启用 ZIP 时打不开 ZIP 软件包，不会读到 XML 清单
+#[cfg(not(feature = "zip"))]
+fn parse_xml(_text: &str) -> Result<Vec<XmlElement>, ArchiveError> {
+    Err(ArchiveError::FormatDisabled("zip"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_email_headers, parse_jar_manifest, record_path};
+
+    #[test]
+    fn parses_manifest_formats() {
+        let manifest = "Manifest-Version: 1.0\r\nBundle-SymbolicName: org.example.core;singlet\r\n on:=true\r\n\r\nName: a/B.class\r\nSHA-256-Digest: x\r\n";
+        let fields = parse_jar_manifest(manifest);
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[1].1, "org.example.core;singleton:=true");
+
+        let (headers, body) = parse_email_headers(
+            "Name: demo\nRequires-Dist: a>=1\nRequires-Dist: b; extra == 'x'\nLicense: MIT\n        line 2\n\nlong text\n",
+        );
+        assert_eq!(headers[2].1, "b; extra == 'x'");
+        assert_eq!(headers[3].1, "MIT\nline 2");
+        assert_eq!(body, "long text\n");
+
+        assert_eq!(
+            record_path("demo/__init__.py,sha256=abc,12").unwrap(),
+            "demo/__init__.py"
+        );
+        assert_eq!(record_path("\"a,\"\"b\"\".py\",,").unwrap(), "a,\"b\".py");
+    }
+
+    #[test]
+    #[cfg(feature = "zip")]
+    fn parses_xml_manifests() {
+        let elements = super::parse_xml(
+            r#"<?xml version="1.0"?><package xmlns="urn:x"><metadata><id>Demo</id><title>A &amp; B</title><dependencies><dependency id="Lib" version="1.0" /></dependencies></metadata></package>"#,
+        )
+        .unwrap();
+        let title = elements
+            .iter()
+            .find(|element| element.path == "package/metadata/title")
+            .unwrap();
+        assert_eq!(title.text, "A & B");
+        let dependency = elements.last().unwrap();
+        assert_eq!(dependency.path, "package/metadata/dependencies/dependency");
+        assert_eq!(dependency.attr("version"), Some("1.0"));
+    }
+}
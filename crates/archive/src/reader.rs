@@ -0,0 +1,509 @@
+//! 统一的归档读取接口
+//!
+//! 每种格式实现 [`ArchiveReader`]，并在 [`crate::registry`] 中登记扩展名和魔数。
+//! 归档可以来自磁盘上的文件，也可以来自外层归档中某个条目的数据流（嵌套归档）。
+
+#[cfg(feature = "rar")]
+use crate::volume::VolumeScheme;
+use crate::volume::VolumeSet;
+use crate::{
+    ArchiveError, ArchiveFormat, CancelToken, EntryTime, Extract, NameEncoding, PackageInfo,
+};
+use serde::Serialize;
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "tar",
+    feature = "cpio",
+    feature = "ar",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab"
+))]
+use std::{fs::File, io::BufReader};
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// 处理条目数据流的回调
+pub type EntryFn<'f> = dyn FnMut(&mut dyn Read) -> Result<(), ArchiveError> + 'f;
+
+/// 逐条接收列举结果的回调，返回 `false` 时停止
+pub type ListFn<'f> = dyn FnMut(Extract) -> Result<bool, ArchiveError> + 'f;
+
+/// 依次访问条目的回调：条目信息，以及解压后的数据流（无法解压时为错误）；返回 `false` 时停止
+pub type VisitFn<'f> =
+    dyn FnMut(&Extract, Result<&mut dyn Read, ArchiveError>) -> Result<bool, ArchiveError> + 'f;
+
+/// 归档数据来源
+pub enum Input<'a> {
+    /// 磁盘上的文件
+    Path(PathBuf),
+    /// 外层归档中某个条目的数据流，`name` 为该条目在外层归档中的条目名
+    Stream {
+        reader: Box<dyn Read + 'a>,
+        name: String,
+    },
+    /// 磁盘上的分卷归档，已找齐全部分卷
+    Volumes(VolumeSet),
+}
+
+impl Input<'_> {
+    /// 文件名或条目名，用于扩展名判断和单文件压缩流的命名
+    pub fn name(&self) -> String {
+        match self {
+            Self::Path(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            Self::Stream { name, .. } => name.clone(),
+            Self::Volumes(set) => set.name.clone(),
+        }
+    }
+}
+
+/// 归档自身的信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchiveMetadata {
+    /// 归档格式
+    pub format: ArchiveFormat,
+    /// 归档文件大小，来自数据流且未读入内存时为 `None`
+    pub archive_size: Option<u64>,
+    /// 归档文件的修改时间，来自数据流时为 `None`
+    pub modified: Option<EntryTime>,
+    /// 分卷数量，非分卷归档为 1
+    pub volumes: usize,
+}
+
+impl ArchiveMetadata {
+    #[allow(dead_code)] // 未启用任何格式时没有读取器使用
+    fn of_path(format: ArchiveFormat, path: &Path) -> Self {
+        let meta = std::fs::metadata(path).ok();
+        Self {
+            format,
+            archive_size: meta.as_ref().map(|meta| meta.len()),
+            modified: meta
+                .and_then(|meta| meta.modified().ok())
+                .map(EntryTime::from_system_time),
+            volumes: 1,
+        }
+    }
+}
+
+/// 归档的加密情况
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordProtection {
+    /// 没有加密的条目
+    None,
+    /// 部分文件条目加密，列举不需要密码
+    Partial,
+    /// 全部文件条目加密，列举不需要密码，打开任一文件都需要密码
+    All,
+    /// 头部（文件名等元数据）加密，不提供密码无法列举条目
+    Headers,
+}
+
+impl PasswordProtection {
+    /// 根据条目的加密标记统计，目录不计入
+    pub fn of_entries(entries: &[Extract]) -> Self {
+        let files = entries.iter().filter(|entry| !entry.dir);
+        let (total, encrypted) = files.fold((0, 0), |(total, encrypted), entry| {
+            (total + 1, encrypted + usize::from(entry.encrypted))
+        });
+        match encrypted {
+            0 => Self::None,
+            n if n == total => Self::All,
+            _ => Self::Partial,
+        }
+    }
+}
+
+/// 已打开的归档
+///
+/// `open` 只做必要的准备（如把嵌套的 ZIP 读入内存），不解析条目；
+/// 来自数据流的 TAR 系列、CPIO、AR 只能顺序读取一次，`list` 和 `read_entry` 二选一。
+pub trait ArchiveReader<'a> {
+    /// 打开归档，`password` 用于加密的条目或头部
+    fn open(input: Input<'a>, password: Option<&str>) -> Result<Self, ArchiveError>
+    where
+        Self: Sized;
+
+    /// 归档格式和自身的大小、修改时间
+    fn metadata(&self) -> ArchiveMetadata;
+
+    /// 列举条目（不解压内容），返回扁平的条目列表
+    fn list(&mut self) -> Result<Vec<Extract>, ArchiveError>;
+
+    /// 逐条列举条目，不保留完整的列表
+    ///
+    /// 默认先完整列举再逐条交出；顺序读取的格式以及 ZIP、RAR 边读边交出。
+    fn list_each(&mut self, f: &mut ListFn<'_>) -> Result<(), ArchiveError> {
+        for entry in self.list()? {
+            if !f(entry)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// 是否需要密码才能列出条目
+    fn is_encrypted(&mut self) -> Result<bool, ArchiveError>;
+
+    /// 加密情况；默认只检查头部加密，支持条目加密的格式需要列举条目统计
+    fn password_protection(&mut self) -> Result<PasswordProtection, ArchiveError> {
+        if self.is_encrypted()? {
+            Ok(PasswordProtection::Headers)
+        } else {
+            Ok(PasswordProtection::None)
+        }
+    }
+
+    /// 检查密码是否正确：只解开头部或一个加密条目，不做完整的列举或解压；
+    /// 不支持加密的格式以及没有加密内容的归档，任何密码都视为正确
+    fn verify_password(&mut self, _password: &str) -> Result<bool, ArchiveError> {
+        Ok(true)
+    }
+
+    /// 指定条目名的字符编码，需要在列举或读取条目之前调用；默认为 [`NameEncoding::Auto`]
+    ///
+    /// 只对可能以非 UTF-8 编码保存条目名的格式（ZIP、CAB，以及修正 7Z、RAR 中的乱码名称）有效。
+    fn set_name_encoding(&mut self, _encoding: NameEncoding) {}
+
+    /// 指定取消标记，需要在列举之前调用
+    ///
+    /// 条目之间的取消由调用方检查；列举时需要解压大量数据的格式（如统计单流压缩文件的大小）
+    /// 在解压过程中也检查该标记。
+    fn set_cancel(&mut self, _cancel: &CancelToken) {}
+
+    /// 定位 `entry_name`，并把它解压后的数据流交给 `f` 处理
+    fn read_entry(&mut self, entry_name: &str, f: &mut EntryFn<'_>) -> Result<(), ArchiveError>;
+
+    /// 解压 `entry_name` 到 `dest_dir`，返回写出的文件路径
+    ///
+    /// 默认把 [`ArchiveReader::read_entry`] 的数据流写入文件；解压库能直接写文件的格式（RAR）可以覆盖。
+    fn extract_entry(
+        &mut self,
+        entry_name: &str,
+        dest_dir: &Path,
+    ) -> Result<PathBuf, ArchiveError> {
+        let mut output = None;
+        self.read_entry(entry_name, &mut |data| {
+            output = Some(crate::extractors::write_entry_to_dir(
+                data, entry_name, dest_dir,
+            )?);
+            Ok(())
+        })?;
+        output.ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))
+    }
+
+    /// 按归档中的顺序解压每个文件条目（不含目录），把条目信息和数据流交给 `f`
+    ///
+    /// 单个条目解压失败时把错误交给 `f`，能继续读取时接着访问后面的条目；
+    /// 归档结构损坏、无法继续时返回错误。
+    fn for_each_entry(&mut self, f: &mut VisitFn<'_>) -> Result<(), ArchiveError>;
+
+    /// 软件包格式（deb、rpm）自身记录的名称、版本、依赖等信息，其他格式为 `None`
+    ///
+    /// 以 ZIP 打包的软件包（jar、apk 等）由 [`crate::package`] 读取包内的清单文件。
+    fn package_info(&mut self) -> Result<Option<PackageInfo>, ArchiveError> {
+        Ok(None)
+    }
+}
+
+impl<'a> dyn ArchiveReader<'a> + '_ {
+    /// 定位 `entry_name` 并返回 `f` 的处理结果
+    pub fn with_entry<T>(
+        &mut self,
+        entry_name: &str,
+        mut f: impl FnMut(&mut dyn Read) -> Result<T, ArchiveError>,
+    ) -> Result<T, ArchiveError> {
+        let mut result = None;
+        self.read_entry(entry_name, &mut |data| {
+            result = Some(f(data)?);
+            Ok(())
+        })?;
+        result.ok_or_else(|| ArchiveError::EntryNotFound(entry_name.to_string()))
+    }
+}
+
+/// 把逐条列举的结果收集为列表
+#[cfg(any(
+    feature = "zip",
+    feature = "tar",
+    feature = "cpio",
+    feature = "ar",
+    feature = "rar"
+))]
+pub(crate) fn collect_entries(
+    list_each: impl FnOnce(&mut ListFn<'_>) -> Result<(), ArchiveError>,
+) -> Result<Vec<Extract>, ArchiveError> {
+    let mut entries = Vec::new();
+    list_each(&mut |entry| {
+        entries.push(entry);
+        Ok(true)
+    })?;
+    Ok(entries)
+}
+
+/// 只能顺序读取的数据源：文件每次重新打开，数据流只能读取一次
+#[cfg(any(feature = "tar", feature = "cpio", feature = "ar"))]
+pub(crate) enum StreamInput<'a> {
+    Path(PathBuf),
+    Volumes(VolumeSet),
+    Stream(Option<Box<dyn Read + 'a>>),
+}
+
+#[cfg(any(feature = "tar", feature = "cpio", feature = "ar"))]
+impl<'a> StreamInput<'a> {
+    pub(crate) fn new(input: Input<'a>) -> Self {
+        match input {
+            Input::Path(path) => Self::Path(path),
+            Input::Volumes(set) => Self::Volumes(set),
+            Input::Stream { reader, .. } => Self::Stream(Some(reader)),
+        }
+    }
+
+    pub(crate) fn take(&mut self) -> Result<Box<dyn Read + 'a>, ArchiveError> {
+        match self {
+            Self::Path(path) => Ok(Box::new(BufReader::new(File::open(path)?))),
+            Self::Volumes(set) => Ok(Box::new(set.reader()?)),
+            Self::Stream(reader) => reader
+                .take()
+                .ok_or_else(|| ArchiveError::Other("Archive stream already consumed".into())),
+        }
+    }
+
+    pub(crate) fn metadata(&self, format: ArchiveFormat) -> ArchiveMetadata {
+        match self {
+            Self::Path(path) => ArchiveMetadata::of_path(format, path),
+            Self::Volumes(set) => set.metadata(format),
+            Self::Stream(_) => ArchiveMetadata {
+                format,
+                archive_size: None,
+                modified: None,
+                volumes: 1,
+            },
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab"
+))]
+pub(crate) trait ReadSeek: Read + std::io::Seek {}
+
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab"
+))]
+impl<T: Read + std::io::Seek> ReadSeek for T {}
+
+/// 读入内存的嵌套归档（以及 RAR 中作为数据流读取的条目）大小上限，更大的数据落地到临时文件
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab",
+    feature = "rar"
+))]
+pub(crate) const MAX_MEMORY_INPUT: u64 = 64 * 1024 * 1024;
+
+/// 需要随机访问的数据源：数据流在打开时读入内存，超过 [`MAX_MEMORY_INPUT`] 时落地到临时文件；
+/// 分卷拼接为一个数据流
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab"
+))]
+pub(crate) enum SeekInput {
+    Path(PathBuf),
+    Volumes(VolumeSet),
+    Memory(Vec<u8>),
+    Spilled(SpilledFile),
+}
+
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab"
+))]
+impl SeekInput {
+    pub(crate) fn new(input: Input<'_>) -> Result<Self, ArchiveError> {
+        match input {
+            Input::Path(path) => Ok(Self::Path(path)),
+            Input::Volumes(set) => Ok(Self::Volumes(set)),
+            Input::Stream { mut reader, name } => {
+                let mut buffer = Vec::new();
+                (&mut reader)
+                    .take(MAX_MEMORY_INPUT + 1)
+                    .read_to_end(&mut buffer)?;
+                if buffer.len() as u64 <= MAX_MEMORY_INPUT {
+                    return Ok(Self::Memory(buffer));
+                }
+                log::debug!("Spilling nested archive {} to a temporary file", name);
+                let extension = crate::format::extension_of(Path::new(&name));
+                let mut data = std::io::Cursor::new(buffer).chain(reader);
+                Ok(Self::Spilled(SpilledFile::new(&mut data, &extension)?))
+            },
+        }
+    }
+
+    /// 打开可寻址的读取器，同时返回数据长度
+    pub(crate) fn reader(&self) -> Result<(Box<dyn ReadSeek + '_>, u64), ArchiveError> {
+        match self {
+            Self::Path(path) => {
+                let file = File::open(path)?;
+                let len = file.metadata()?.len();
+                Ok((Box::new(BufReader::new(file)), len))
+            },
+            Self::Volumes(set) => {
+                let reader = set.reader()?;
+                let len = reader.len();
+                Ok((Box::new(reader), len))
+            },
+            Self::Memory(data) => Ok((
+                Box::new(std::io::Cursor::new(data.as_slice())),
+                data.len() as u64,
+            )),
+            Self::Spilled(spilled) => {
+                let file = File::open(spilled.path())?;
+                let len = file.metadata()?.len();
+                Ok((Box::new(BufReader::new(file)), len))
+            },
+        }
+    }
+
+    pub(crate) fn metadata(&self, format: ArchiveFormat) -> ArchiveMetadata {
+        match self {
+            Self::Path(path) => ArchiveMetadata::of_path(format, path),
+            Self::Volumes(set) => set.metadata(format),
+            Self::Memory(data) => ArchiveMetadata {
+                format,
+                archive_size: Some(data.len() as u64),
+                modified: None,
+                volumes: 1,
+            },
+            Self::Spilled(spilled) => ArchiveMetadata {
+                modified: None,
+                ..ArchiveMetadata::of_path(format, spilled.path())
+            },
+        }
+    }
+}
+
+/// 只能从文件打开的数据源（RAR）：数据流和按字节切分的分卷会在打开时落地到临时文件，
+/// RAR 自己的分卷由 unrar 从第一卷开始读取
+#[cfg(feature = "rar")]
+pub(crate) enum FileInput {
+    Path(PathBuf),
+    Volumes(VolumeSet),
+    Spilled(SpilledFile),
+}
+
+#[cfg(feature = "rar")]
+impl FileInput {
+    pub(crate) fn new(input: Input<'_>, extension: &str) -> Result<Self, ArchiveError> {
+        match input {
+            Input::Path(path) => Ok(Self::Path(path)),
+            Input::Volumes(set) if set.scheme == VolumeScheme::Split => Ok(Self::Spilled(
+                SpilledFile::new(&mut set.reader()?, extension)?,
+            )),
+            Input::Volumes(set) => Ok(Self::Volumes(set)),
+            Input::Stream { mut reader, .. } => {
+                Ok(Self::Spilled(SpilledFile::new(&mut reader, extension)?))
+            },
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        match self {
+            Self::Path(path) => path,
+            Self::Volumes(set) => set.first(),
+            Self::Spilled(spilled) => spilled.path(),
+        }
+    }
+
+    pub(crate) fn metadata(&self, format: ArchiveFormat) -> ArchiveMetadata {
+        match self {
+            Self::Path(path) => ArchiveMetadata::of_path(format, path),
+            Self::Volumes(set) => set.metadata(format),
+            Self::Spilled(spilled) => ArchiveMetadata {
+                modified: None,
+                ..ArchiveMetadata::of_path(format, spilled.path())
+            },
+        }
+    }
+}
+
+/// 落地到临时目录的嵌套归档数据，离开作用域时删除
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab",
+    feature = "rar"
+))]
+pub(crate) struct SpilledFile(PathBuf);
+
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab",
+    feature = "rar"
+))]
+impl SpilledFile {
+    fn new(reader: &mut dyn Read, extension: &str) -> Result<Self, ArchiveError> {
+        let spilled = Self::reserve(extension)?;
+        std::io::copy(reader, &mut std::fs::File::create(spilled.path())?)?;
+        Ok(spilled)
+    }
+
+    /// 在临时目录中分配一个尚未创建的文件路径，由调用方写入
+    pub(crate) fn reserve(extension: &str) -> Result<Self, ArchiveError> {
+        use std::sync::atomic::{AtomicU64, Ordering};
+
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+        let dir = std::env::temp_dir().join("quicklook_archive");
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self(dir.join(format!(
+            "nested_{}_{}.{extension}",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ))))
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(any(
+    feature = "zip",
+    feature = "sevenz",
+    feature = "iso",
+    feature = "squashfs",
+    feature = "cab",
+    feature = "rar"
+))]
+impl Drop for SpilledFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
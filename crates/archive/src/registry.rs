@@ -0,0 +1,284 @@
+//! 格式注册表：扩展名、魔数与读取器的唯一对应关系
+//!
+//! 新增格式只需实现 [`ArchiveReader`] 并在 [`REGISTRY`] 中登记一项。
+
+use crate::format::{extension_of, is_lzma_header, is_tar_header, read_header};
+use crate::reader::{ArchiveReader, Input};
+use crate::{volume, ArchiveError, ArchiveFormat};
+use std::{
+    fs::File,
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+};
+
+/// 打开归档读取器
+pub type OpenFn =
+    for<'a> fn(Input<'a>, Option<&str>) -> Result<Box<dyn ArchiveReader<'a> + 'a>, ArchiveError>;
+
+/// 一种归档格式的登记信息
+pub struct FormatRegistration {
+    pub format: ArchiveFormat,
+    /// 编译该格式读取器的 cargo feature
+    pub feature: &'static str,
+    /// 小写扩展名（不含 `.`）
+    pub extensions: &'static [&'static str],
+    /// 根据文件头判断是否为该格式
+    pub magic: fn(&[u8]) -> bool,
+    pub open: OpenFn,
+}
+
+/// 生成打开函数；feature 未启用时格式仍能识别，打开时返回 [`ArchiveError::FormatDisabled`]
+macro_rules! open_with {
+    ($feature:literal, $reader:path) => {{
+        #[cfg(feature = $feature)]
+        let open: OpenFn = |input, password| Ok(Box::new(<$reader>::open(input, password)?));
+        #[cfg(not(feature = $feature))]
+        let open: OpenFn = |_, _| Err(ArchiveError::FormatDisabled($feature));
+        open
+    }};
+}
+
+/// 已登记的格式，按魔数识别时依次匹配
+///
+/// 没有固定魔数、只能靠校验和识别的 TAR 放在最后。
+pub static REGISTRY: &[FormatRegistration] = &[
+    FormatRegistration {
+        format: ArchiveFormat::Zip,
+        feature: "zip",
+        extensions: &[
+            "zip", // ZIP 本质但带特殊扩展名的格式
+            "jar", "war", "ear", "apk", "aar", "whl", "vsix", "nupkg", "crx", "xpi", "egg", "kra",
+            "xps", "oxps",
+        ],
+        magic: |header| {
+            header.starts_with(b"PK\x03\x04")
+                || header.starts_with(b"PK\x05\x06")
+                || header.starts_with(b"PK\x07\x08")
+        },
+        open: open_with!("zip", crate::extractors::zip::ZipReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::SevenZ,
+        feature: "sevenz",
+        extensions: &["7z"],
+        magic: |header| header.starts_with(b"7z\xBC\xAF\x27\x1C"),
+        open: open_with!("sevenz", crate::extractors::sevenz::SevenZReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Rar,
+        feature: "rar",
+        extensions: &["rar"],
+        magic: |header| header.starts_with(b"Rar!\x1A\x07"),
+        open: open_with!("rar", crate::extractors::rar::RarReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Rpm,
+        feature: "rpm",
+        extensions: &["rpm"],
+        magic: |header| header.starts_with(&[0xED, 0xAB, 0xEE, 0xDB]),
+        open: open_with!("rpm", crate::extractors::rpm::RpmReader),
+    },
+    // deb 也是 AR，需要排在 AR 之前按第一个成员名区分
+    FormatRegistration {
+        format: ArchiveFormat::Deb,
+        feature: "deb",
+        extensions: &["deb", "udeb"],
+        magic: |header| header.starts_with(b"!<arch>\ndebian-binary"),
+        open: open_with!("deb", crate::extractors::deb::DebReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Ar,
+        feature: "ar",
+        extensions: &["ar", "a"],
+        magic: |header| header.starts_with(b"!<arch>\n"),
+        open: open_with!("ar", crate::extractors::ar::ArReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Cpio,
+        feature: "cpio",
+        extensions: &["cpio"],
+        magic: |header| {
+            header.starts_with(b"070701")
+                || header.starts_with(b"070702")
+                || header.starts_with(b"070707")
+                || header.starts_with(&[0xC7, 0x71])
+                || header.starts_with(&[0x71, 0xC7])
+        },
+        open: open_with!("cpio", crate::extractors::cpio::CpioReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Squashfs,
+        feature: "squashfs",
+        extensions: &["squashfs", "sqfs", "snap"],
+        magic: |header| header.starts_with(b"hsqs"),
+        open: open_with!("squashfs", crate::extractors::squashfs::SquashFsReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Cab,
+        feature: "cab",
+        extensions: &["cab"],
+        magic: |header| header.starts_with(b"MSCF\0\0\0\0"),
+        open: open_with!("cab", crate::extractors::cab::CabReader),
+    },
+    // MSI 的复合文档头部与 Office 97–2003 文档相同，只按扩展名识别
+    FormatRegistration {
+        format: ArchiveFormat::Msi,
+        feature: "msi",
+        extensions: &["msi", "msm"],
+        magic: |_| false,
+        open: open_with!("msi", crate::extractors::msi::MsiReader),
+    },
+    // 光盘映像的卷描述符位于 32 KiB 之后，超出文件头的范围，只能按扩展名识别；
+    // 两者使用同一个读取器，按卷识别序列选择 UDF 或 ISO 9660
+    FormatRegistration {
+        format: ArchiveFormat::Iso,
+        feature: "iso",
+        extensions: &["iso"],
+        magic: |_| false,
+        open: open_with!("iso", crate::extractors::iso::IsoReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Udf,
+        feature: "iso",
+        extensions: &["udf"],
+        magic: |_| false,
+        open: open_with!("iso", crate::extractors::iso::IsoReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarGz,
+        feature: "tar",
+        extensions: &["gz", "tgz"],
+        magic: |header| header.starts_with(&[0x1F, 0x8B]),
+        open: open_with!("tar", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarBz2,
+        feature: "tar",
+        extensions: &["bz2", "tbz2"],
+        magic: |header| header.starts_with(b"BZh"),
+        open: open_with!("tar", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarXz,
+        feature: "tar",
+        extensions: &["xz", "txz"],
+        magic: |header| header.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]),
+        open: open_with!("tar", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarZst,
+        feature: "zstd",
+        extensions: &["zst", "tzst"],
+        magic: |header| header.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]),
+        open: open_with!("zstd", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarLz4,
+        feature: "lz4",
+        extensions: &["lz4"],
+        magic: |header| header.starts_with(&[0x04, 0x22, 0x4D, 0x18]),
+        open: open_with!("lz4", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarLzip,
+        feature: "lzip",
+        extensions: &["lz"],
+        magic: |header| header.starts_with(b"LZIP\x01"),
+        open: open_with!("lzip", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarZ,
+        feature: "compress",
+        extensions: &["z", "taz"],
+        magic: |header| header.starts_with(&[0x1F, 0x9D]),
+        open: open_with!("compress", crate::extractors::compressed::CompressedReader),
+    },
+    // `.tlz` 历史上多为 tar.lzma；lzip 压缩的 .tlz 会先按魔数识别为 lzip
+    FormatRegistration {
+        format: ArchiveFormat::TarLzma,
+        feature: "lzma",
+        extensions: &["lzma", "tlz"],
+        magic: is_lzma_header,
+        open: open_with!("lzma", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::TarBr,
+        feature: "brotli",
+        extensions: &["br"],
+        magic: |_| false,
+        open: open_with!("brotli", crate::extractors::compressed::CompressedReader),
+    },
+    FormatRegistration {
+        format: ArchiveFormat::Tar,
+        feature: "tar",
+        extensions: &["tar"],
+        magic: is_tar_header,
+        open: open_with!("tar", crate::extractors::tar::TarReader),
+    },
+];
+
+/// 查找格式的登记信息
+pub fn lookup(format: ArchiveFormat) -> &'static FormatRegistration {
+    REGISTRY
+        .iter()
+        .find(|registration| registration.format == format)
+        .expect("every ArchiveFormat is registered")
+}
+
+/// 根据扩展名（不含 `.`，不区分大小写）查找格式
+pub fn by_extension(extension: &str) -> Option<&'static FormatRegistration> {
+    let extension = extension.to_lowercase();
+    REGISTRY
+        .iter()
+        .find(|registration| registration.extensions.contains(&extension.as_str()))
+}
+
+/// 根据文件头的魔数查找格式
+pub fn by_magic(header: &[u8]) -> Option<&'static FormatRegistration> {
+    REGISTRY
+        .iter()
+        .find(|registration| (registration.magic)(header))
+}
+
+/// 打开磁盘上的归档：优先根据文件头魔数识别格式，无法识别时再根据扩展名判断
+///
+/// `path` 可以是分卷归档的任意一卷，此时会找齐全部分卷，按第一卷识别格式，
+/// 按去掉分卷后缀的文件名判断扩展名；缺卷时返回 [`ArchiveError::MissingVolumes`]。
+pub fn open<P: AsRef<Path>>(
+    path: P,
+    password: Option<&str>,
+) -> Result<Box<dyn ArchiveReader<'static>>, ArchiveError> {
+    let input = volume::open_input(path.as_ref())?;
+    let (first, name) = match &input {
+        Input::Volumes(set) => (set.first(), PathBuf::from(&set.name)),
+        _ => (path.as_ref(), path.as_ref().to_path_buf()),
+    };
+    let header = read_header(&mut File::open(first)?)?;
+    let registration = by_magic(&header)
+        .or_else(|| by_extension(&extension_of(&name)))
+        .ok_or_else(|| ArchiveError::UnsupportedFormat(extension_of(&name)))?;
+
+    (registration.open)(input, password)
+}
+
+/// 打开外层归档中某个条目的数据流，`name` 为该条目的条目名
+///
+/// 根据数据的文件头识别格式，无法识别时再根据条目名的扩展名判断；
+/// 已读取的文件头会重放给读取器。
+pub fn open_stream<'a>(
+    mut reader: Box<dyn Read + 'a>,
+    name: &str,
+    password: Option<&str>,
+) -> Result<Box<dyn ArchiveReader<'a> + 'a>, ArchiveError> {
+    let header = read_header(&mut reader)?;
+    let extension = extension_of(Path::new(name));
+    let registration = by_magic(&header)
+        .or_else(|| by_extension(&extension))
+        .ok_or(ArchiveError::UnsupportedFormat(extension))?;
+
+    let input = Input::Stream {
+        reader: Box::new(Cursor::new(header).chain(reader)),
+        name: name.to_string(),
+    };
+    (registration.open)(input, password)
+}
@@ -0,0 +1,354 @@
+//! 归档安全检查：在解压前提示路径穿越、压缩炸弹等风险
+
+use crate::Extract;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 每类风险最多记录的条数，其余只计数，避免恶意归档中大量同类条目撑大报告
+pub const MAX_ISSUES_PER_KIND: usize = 100;
+
+/// 安全检查的阈值
+#[derive(Debug, Clone)]
+pub struct SafetyLimits {
+    /// 条目数量上限
+    pub max_entries: u64,
+    /// 解压后总大小上限（字节）
+    pub max_total_size: u64,
+    /// 压缩比上限（解压后大小 / 压缩后大小）
+    pub max_ratio: f64,
+    /// 参与压缩比检查的最小解压后大小，避免把高度重复的小文本误报为压缩炸弹
+    pub ratio_check_min_size: u64,
+}
+
+impl Default for SafetyLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: 100_000,
+            max_total_size: 10 * 1024 * 1024 * 1024,
+            max_ratio: 100.0,
+            ratio_check_min_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// 风险类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyIssueKind {
+    /// 条目路径包含 `..`，解压时可能写到目标目录之外
+    PathTraversal,
+    /// 条目使用绝对路径
+    AbsolutePath,
+    /// 符号链接或硬链接指向归档根目录之外
+    SymlinkOutsideRoot,
+    /// 压缩比异常（疑似压缩炸弹）
+    HighCompressionRatio,
+    /// 存在同名条目，解压时后者会覆盖前者
+    DuplicateEntry,
+    /// 条目数量超过阈值
+    TooManyEntries,
+    /// 解压后总大小超过阈值
+    TooLarge,
+}
+
+/// 单条风险
+#[derive(Debug, Clone, Serialize)]
+pub struct SafetyIssue {
+    pub kind: SafetyIssueKind,
+    /// 相关条目名，针对整个归档的风险为 `None`
+    pub entry: Option<String>,
+    /// 说明
+    pub detail: String,
+}
+
+/// 归档的安全检查结果
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SafetyReport {
+    /// 条目数量
+    pub entry_count: u64,
+    /// 解压后总大小
+    pub total_size: u64,
+    /// 归档文件自身大小，未知时为 0
+    pub archive_size: u64,
+    /// 发现的风险，每类最多 [`MAX_ISSUES_PER_KIND`] 条
+    pub issues: Vec<SafetyIssue>,
+    /// 超出每类上限、未记录到 `issues` 中的风险条数
+    pub omitted_issues: u64,
+}
+
+impl SafetyReport {
+    /// 根据扁平的条目列表计算安全报告
+    pub fn analyze(entries: &[Extract], archive_size: u64, limits: &SafetyLimits) -> Self {
+        let mut analyzer = SafetyAnalyzer::new(archive_size, limits.clone());
+        for entry in entries {
+            analyzer.push(entry);
+        }
+        analyzer.finish()
+    }
+
+    /// 是否未发现任何风险
+    pub fn is_safe(&self) -> bool {
+        self.issues.is_empty() && self.omitted_issues == 0
+    }
+}
+
+/// 逐条累积的安全检查，用于边列举边检查、不保留完整条目列表的场景
+pub struct SafetyAnalyzer {
+    report: SafetyReport,
+    limits: SafetyLimits,
+    /// 已出现的文件条目名及次数，用于发现同名条目；最多记录 `max_entries` 个条目名
+    seen: HashMap<String, u32>,
+    /// 各类风险已记录的条数
+    issue_counts: HashMap<SafetyIssueKind, usize>,
+}
+
+impl SafetyAnalyzer {
+    pub fn new(archive_size: u64, limits: SafetyLimits) -> Self {
+        Self {
+            report: SafetyReport { archive_size, ..SafetyReport::default() },
+            limits,
+            seen: HashMap::new(),
+            issue_counts: HashMap::new(),
+        }
+    }
+
+    /// 检查单个条目
+    pub fn push(&mut self, entry: &Extract) {
+        self.report.entry_count += 1;
+        self.report.total_size += entry.size;
+        let name = entry.name.replace('\\', "/");
+
+        if is_absolute(&name) {
+            self.flag(
+                SafetyIssueKind::AbsolutePath,
+                entry,
+                "条目使用绝对路径".into(),
+            );
+        } else if name.split('/').any(|part| part == "..") {
+            self.flag(
+                SafetyIssueKind::PathTraversal,
+                entry,
+                "条目路径包含 `..`".into(),
+            );
+        }
+
+        if let Some(target) = &entry.link_target {
+            if escapes_root(&name, target) {
+                self.flag(
+                    SafetyIssueKind::SymlinkOutsideRoot,
+                    entry,
+                    format!("符号链接指向归档之外: {target}"),
+                );
+            }
+        }
+
+        if let Some(target) = &entry.hardlink_target {
+            // 硬链接目标是相对归档根目录的条目名
+            if target_escapes(0, target) {
+                self.flag(
+                    SafetyIssueKind::SymlinkOutsideRoot,
+                    entry,
+                    format!("硬链接指向归档之外: {target}"),
+                );
+            }
+        }
+
+        if let Some(ratio) = entry.compression_ratio() {
+            if entry.size >= self.limits.ratio_check_min_size && ratio > self.limits.max_ratio {
+                self.flag(
+                    SafetyIssueKind::HighCompressionRatio,
+                    entry,
+                    format!("压缩比 {ratio:.0}:1"),
+                );
+            }
+        }
+
+        if !entry.dir {
+            let key = name.trim_start_matches("./");
+            // 条目名数量达到上限后（此时会报告条目过多）不再记录新的名称
+            let full = self.seen.len() as u64 >= self.limits.max_entries;
+            if full && !self.seen.contains_key(key) {
+                return;
+            }
+            let count = self.seen.entry(key.to_string()).or_default();
+            *count += 1;
+            if *count == 2 {
+                self.flag(
+                    SafetyIssueKind::DuplicateEntry,
+                    entry,
+                    "存在同名条目".into(),
+                );
+            }
+        }
+    }
+
+    /// 记录单个条目的风险，同类风险超过 [`MAX_ISSUES_PER_KIND`] 条后只计数
+    fn flag(&mut self, kind: SafetyIssueKind, entry: &Extract, detail: String) {
+        let count = self.issue_counts.entry(kind).or_default();
+        if *count >= MAX_ISSUES_PER_KIND {
+            self.report.omitted_issues += 1;
+            return;
+        }
+        *count += 1;
+        self.report.issues.push(SafetyIssue {
+            kind,
+            entry: Some(entry.name.clone()),
+            detail,
+        });
+    }
+
+    /// 结束检查，补充针对整个归档的数量、大小和整体压缩比检查
+    pub fn finish(self) -> SafetyReport {
+        let Self { mut report, limits, .. } = self;
+        if report.entry_count > limits.max_entries {
+            report.issues.push(SafetyIssue {
+                kind: SafetyIssueKind::TooManyEntries,
+                entry: None,
+                detail: format!(
+                    "共 {} 个条目，超过 {}",
+                    report.entry_count, limits.max_entries
+                ),
+            });
+        }
+        if report.total_size > limits.max_total_size {
+            report.issues.push(SafetyIssue {
+                kind: SafetyIssueKind::TooLarge,
+                entry: None,
+                detail: format!(
+                    "解压后共 {} 字节，超过 {}",
+                    report.total_size, limits.max_total_size
+                ),
+            });
+        }
+        let archive_size = report.archive_size;
+        if archive_size > 0 && report.total_size >= limits.ratio_check_min_size {
+            let ratio = report.total_size as f64 / archive_size as f64;
+            if ratio > limits.max_ratio {
+                report.issues.push(SafetyIssue {
+                    kind: SafetyIssueKind::HighCompressionRatio,
+                    entry: None,
+                    detail: format!("整体压缩比 {ratio:.0}:1"),
+                });
+            }
+        }
+
+        report
+    }
+}
+
+/// 判断路径是否为绝对路径（Unix 根路径、Windows 盘符或 UNC 路径）
+fn is_absolute(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    path.starts_with('/')
+        || (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+}
+
+/// 判断符号链接 `target`（相对于链接所在目录）解析后是否位于归档根目录之外
+fn escapes_root(link_name: &str, target: &str) -> bool {
+    // 链接所在目录的层级
+    let depth = link_name
+        .trim_end_matches('/')
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .count()
+        .saturating_sub(1);
+    target_escapes(depth as i64, target)
+}
+
+/// 从第 `depth` 层目录出发解析 `target`，判断是否会越过归档根目录
+fn target_escapes(mut depth: i64, target: &str) -> bool {
+    let target = target.replace('\\', "/");
+    if is_absolute(&target) {
+        return true;
+    }
+
+    for part in target.split('/') {
+        match part {
+            "" | "." => {},
+            ".." => {
+                depth -= 1;
+                if depth < 0 {
+                    return true;
+                }
+            },
+            _ => depth += 1,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        escapes_root, target_escapes, SafetyAnalyzer, SafetyIssueKind, SafetyLimits, SafetyReport,
+        MAX_ISSUES_PER_KIND,
+    };
+    use crate::Extract;
+
+    fn file(name: &str, size: u64) -> Extract {
+        Extract::new(name.to_string(), size, None, false)
+    }
+
+    #[test]
+    fn resolves_symlink_targets_against_link_directory() {
+        assert!(!escapes_root("a/b/link", "../c"));
+        assert!(!escapes_root("a/link", "b/../c"));
+        assert!(escapes_root("a/link", "../../etc/passwd"));
+        assert!(escapes_root("link", "/etc/passwd"));
+        assert!(escapes_root("link", ".."));
+        assert!(!target_escapes(0, "a/../b"));
+        assert!(target_escapes(0, "../b"));
+    }
+
+    #[test]
+    fn flags_traversal_duplicates_and_ratio() {
+        let mut bomb = file("bomb.bin", 1 << 30);
+        bomb.compressed_size = Some(1 << 20);
+        let entries = vec![
+            file("../evil.sh", 1),
+            file("/etc/cron.d/job", 1),
+            file("a.txt", 1),
+            file("./a.txt", 1),
+            bomb,
+        ];
+
+        let report = SafetyReport::analyze(&entries, 0, &SafetyLimits::default());
+        let kinds: Vec<SafetyIssueKind> = report.issues.iter().map(|i| i.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SafetyIssueKind::PathTraversal,
+                SafetyIssueKind::AbsolutePath,
+                SafetyIssueKind::DuplicateEntry,
+                SafetyIssueKind::HighCompressionRatio,
+            ]
+        );
+        assert!(!report.is_safe());
+    }
+
+    #[test]
+    fn caps_issues_per_kind_and_tracked_names() {
+        let limits = SafetyLimits {
+            max_entries: 500,
+            ..SafetyLimits::default()
+        };
+        let mut analyzer = SafetyAnalyzer::new(0, limits);
+        for i in 0..1000 {
+            analyzer.push(&file(&format!("../{i}"), 1));
+            analyzer.push(&file(&format!("../{i}"), 1));
+        }
+        assert_eq!(analyzer.seen.len(), 500);
+
+        let report = analyzer.finish();
+        let count = |kind| report.issues.iter().filter(|i| i.kind == kind).count();
+        assert_eq!(count(SafetyIssueKind::PathTraversal), MAX_ISSUES_PER_KIND);
+        assert_eq!(count(SafetyIssueKind::DuplicateEntry), MAX_ISSUES_PER_KIND);
+        assert_eq!(count(SafetyIssueKind::TooManyEntries), 1);
+        // 2000 条路径穿越加上前 500 个条目名的同名条目
+        assert_eq!(
+            report.omitted_issues,
+            (2000 + 500 - 2 * MAX_ISSUES_PER_KIND) as u64
+        );
+        assert!(!report.is_safe());
+    }
+}
@@ -0,0 +1,180 @@
+//! 条目修改时间
+//!
+//! 各格式记录时间的方式不同：TAR、CPIO、AR、7Z、SquashFS、gzip 头部以及 ZIP 的扩展时间戳
+//! 记录的是绝对时间；ISO 9660 和 UDF 记录本地时间及其时区偏移；ZIP 和 RAR 的 DOS 时间只记录
+//! 打包机器上的本地时间，不带时区。
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeDelta, TimeZone, Utc};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{cmp::Ordering, fmt, str::FromStr, time::SystemTime};
+
+/// Windows FILETIME 纪元（1601-01-01）与 UNIX 纪元相差的 100 纳秒数
+const FILETIME_UNIX_EPOCH_DIFF: i128 = 116_444_736_000_000_000;
+const FILETIMES_PER_SEC: i128 = 10_000_000;
+
+/// 条目修改时间
+///
+/// 序列化为 ISO 8601 字符串：绝对时间带 `Z` 后缀（如 `2024-01-01T08:00:00Z`，有小数秒时保留），
+/// 本地时间不带时区（如 `2024-01-01T08:00:00`），JavaScript 的 `new Date()` 会按本机时区解析后者。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntryTime {
+    /// 绝对时间
+    Utc(DateTime<Utc>),
+    /// 不带时区的本地时间
+    Local(NaiveDateTime),
+}
+
+impl EntryTime {
+    /// 由 UNIX 秒构造；0 通常表示打包时没有记录时间（如可复现构建），视为未知
+    pub fn from_unix(secs: i64) -> Option<Self> {
+        if secs == 0 {
+            return None;
+        }
+        DateTime::from_timestamp(secs, 0).map(Self::Utc)
+    }
+
+    /// 由系统时间构造
+    pub fn from_system_time(time: SystemTime) -> Self {
+        Self::Utc(time.into())
+    }
+
+    /// 由 Windows FILETIME（自 1601-01-01 起的 100 纳秒数）构造
+    pub fn from_filetime(raw: u64) -> Option<Self> {
+        if raw == 0 {
+            return None;
+        }
+        let ticks = i128::from(raw) - FILETIME_UNIX_EPOCH_DIFF;
+        let secs = i64::try_from(ticks.div_euclid(FILETIMES_PER_SEC)).ok()?;
+        let nanos = (ticks.rem_euclid(FILETIMES_PER_SEC) * 100) as u32;
+        DateTime::from_timestamp(secs, nanos).map(Self::Utc)
+    }
+
+    /// 由 MS-DOS 日期和时间构造（本地时间，精度 2 秒）
+    ///
+    /// 日期: bit 15-9 年-1980, bit 8-5 月, bit 4-0 日；
+    /// 时间: bit 15-11 时, bit 10-5 分, bit 4-0 秒/2
+    pub fn from_dos(date: u16, time: u16) -> Option<Self> {
+        if date == 0 {
+            return None;
+        }
+        let year = i32::from(date >> 9) + 1980;
+        let month = u32::from((date >> 5) & 0x0F);
+        let day = u32::from(date & 0x1F);
+        let hour = u32::from(time >> 11);
+        let minute = u32::from((time >> 5) & 0x3F);
+        let second = u32::from(time & 0x1F) * 2;
+
+        NaiveDate::from_ymd_opt(year, month, day)?
+            .and_hms_opt(hour, minute, second)
+            .map(Self::Local)
+    }
+
+    /// 由本地时间及其相对 UTC 的偏移（分钟）构造，偏移未知时为不带时区的本地时间
+    pub fn from_local(naive: NaiveDateTime, offset_minutes: Option<i32>) -> Self {
+        match offset_minutes {
+            Some(offset) => Self::Utc((naive - TimeDelta::minutes(i64::from(offset))).and_utc()),
+            None => Self::Local(naive),
+        }
+    }
+
+    /// 换算为 UTC 时间，本地时间按本机时区解释
+    pub fn to_utc(&self) -> DateTime<Utc> {
+        match self {
+            Self::Utc(time) => *time,
+            Self::Local(naive) => Local
+                .from_local_datetime(naive)
+                .earliest()
+                .map(|time| time.with_timezone(&Utc))
+                .unwrap_or_else(|| naive.and_utc()),
+        }
+    }
+}
+
+impl Ord for EntryTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.to_utc().cmp(&other.to_utc()).then_with(|| {
+            // 换算后相同时按变体排序，保证与 `Eq` 一致
+            let rank = |time: &Self| matches!(time, Self::Local(_));
+            rank(self).cmp(&rank(other))
+        })
+    }
+}
+
+impl PartialOrd for EntryTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for EntryTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Utc(time) => f.write_str(&time.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            Self::Local(naive) => write!(f, "{}", naive.format("%Y-%m-%dT%H:%M:%S")),
+        }
+    }
+}
+
+impl FromStr for EntryTime {
+    type Err = chrono::ParseError;
+
+    /// 解析 [`fmt::Display`] 输出的格式：带 `Z` 的为绝对时间，否则为本地时间
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.ends_with('Z') {
+            Ok(Self::Utc(
+                DateTime::parse_from_rfc3339(s)?.with_timezone(&Utc),
+            ))
+        } else {
+            Ok(Self::Local(NaiveDateTime::parse_from_str(
+                s,
+                "%Y-%m-%dT%H:%M:%S",
+            )?))
+        }
+    }
+}
+
+impl Serialize for EntryTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for EntryTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EntryTime;
+
+    #[test]
+    fn decodes_and_serializes_each_source() {
+        let unix = EntryTime::from_unix(1_700_000_000).unwrap();
+        assert_eq!(unix.to_string(), "2023-11-14T22:13:20Z");
+        assert_eq!(EntryTime::from_unix(0), None);
+
+        // 2023-11-14 22:13:20 UTC
+        let filetime = EntryTime::from_filetime(133_444_736_000_000_000).unwrap();
+        assert_eq!(filetime, unix);
+        assert_eq!(EntryTime::from_filetime(0), None);
+
+        // 2024-01-02 03:04:06
+        let dos = EntryTime::from_dos((44 << 9) | (1 << 5) | 2, (3 << 11) | (4 << 5) | 3);
+        assert_eq!(dos.unwrap().to_string(), "2024-01-02T03:04:06");
+        assert_eq!(EntryTime::from_dos(0, 0), None);
+        assert_eq!(
+            serde_json::to_string(&Some(unix)).unwrap(),
+            "\"2023-11-14T22:13:20Z\""
+        );
+
+        // 序列化后能原样读回，包括 FILETIME 的小数秒
+        let precise = EntryTime::from_filetime(133_444_736_001_234_500).unwrap();
+        assert_eq!(precise.to_string(), "2023-11-14T22:13:20.123450Z");
+        for time in [unix, dos.unwrap(), precise] {
+            assert_eq!(time.to_string().parse::<EntryTime>(), Ok(time));
+        }
+    }
+}
@@ -0,0 +1,695 @@
+//! 分卷归档：由任意一卷找到同组的全部分卷，作为一个逻辑归档读取
+//!
+//! 支持的命名方式：
+//! - `data.7z.001`、`data.zip.001`、`data.tar.001`：按字节切分，依次拼接即为完整归档；
+//! - `data.part1.rar`、`data.part2.rar`……：RAR 新式分卷；
+//! - `data.rar`、`data.r00`、`data.r01`……：RAR 旧式分卷；
+//! - `data.z01`、`data.z02`……`data.zip`：PKWARE 分卷 ZIP，最后一卷为 `.zip`。
+//!
+//! RAR 分卷由 unrar 从第一卷开始自行打开；其余方式拼接为一个可寻址的数据流，
+//! 分卷 ZIP 还需把中央目录中按卷记录的偏移改写为拼接后的绝对偏移。
+
+use crate::reader::{ArchiveMetadata, Input};
+use crate::{ArchiveError, ArchiveFormat, EntryTime};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+/// 分卷命名方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VolumeScheme {
+    /// `data.7z.001`：按字节切分
+    Split,
+    /// `data.part1.rar`：RAR 新式分卷
+    RarParts,
+    /// `data.rar` + `data.r00`：RAR 旧式分卷
+    RarLegacy,
+    /// `data.z01` …… `data.zip`：PKWARE 分卷 ZIP
+    ZipSplit,
+}
+
+/// 同一归档的一组分卷
+#[derive(Debug, Clone)]
+pub struct VolumeSet {
+    pub scheme: VolumeScheme,
+    /// 去掉分卷后缀后的逻辑文件名，如 `data.7z`、`data.rar`
+    pub name: String,
+    /// 已找到的分卷，按卷序排列
+    pub volumes: Vec<PathBuf>,
+    /// 缺失的分卷文件名，按卷序排列
+    pub missing: Vec<String>,
+}
+
+impl VolumeSet {
+    /// 有分卷缺失时返回 [`ArchiveError::MissingVolumes`]
+    pub fn ensure_complete(&self) -> Result<(), ArchiveError> {
+        if self.missing.is_empty() {
+            Ok(())
+        } else {
+            Err(ArchiveError::MissingVolumes(self.missing.clone()))
+        }
+    }
+
+    /// 第一卷，格式识别和 unrar 都从这里开始
+    pub fn first(&self) -> &Path {
+        &self.volumes[0]
+    }
+
+    /// 全部分卷的总大小和第一卷的修改时间
+    #[allow(dead_code)] // 未启用任何格式时没有读取器使用
+    pub(crate) fn metadata(&self, format: ArchiveFormat) -> ArchiveMetadata {
+        let sizes: Option<u64> = self
+            .volumes
+            .iter()
+            .map(|volume| std::fs::metadata(volume).ok().map(|meta| meta.len()))
+            .sum();
+        ArchiveMetadata {
+            format,
+            archive_size: sizes,
+            modified: std::fs::metadata(self.first())
+                .and_then(|meta| meta.modified())
+                .ok()
+                .map(EntryTime::from_system_time),
+            volumes: self.volumes.len(),
+        }
+    }
+
+    /// 把全部分卷拼接为一个可寻址的数据流（RAR 分卷不适用）
+    #[allow(dead_code)] // 只启用 RAR 时没有读取器使用
+    pub(crate) fn reader(&self) -> Result<VolumeReader, ArchiveError> {
+        let mut reader = VolumeReader::new(&self.volumes)?;
+        if self.scheme == VolumeScheme::ZipSplit {
+            reader = split_zip_reader(reader)?;
+        }
+        Ok(reader)
+    }
+}
+
+/// 由任意一卷查找同组的分卷；不是分卷命名（或 `.rar`/`.zip` 没有后续分卷）时返回 `None`
+pub fn discover_volumes(path: &Path) -> Result<Option<VolumeSet>, ArchiveError> {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(None);
+    };
+    let lower = file_name.to_lowercase();
+    let Some((stem, suffix)) = lower.rsplit_once('.') else {
+        return Ok(None);
+    };
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let base = &file_name[..stem.len()];
+
+    let set = if suffix.len() >= 3 && is_digits(suffix) {
+        let siblings = Siblings::read(dir)?;
+        let found = siblings.numbered(&format!("{stem}."), "", Some(suffix.len()));
+        let mut last = found.keys().next_back().copied().unwrap_or(0);
+        if let Some(first) = found.get(&1) {
+            last = last.max(expected_7z_volumes(first, &found)?.unwrap_or(0));
+        }
+        let width = suffix.len();
+        let (volumes, missing) = collect(&found, 1..=last, |n| format!("{base}.{n:0width$}"));
+        VolumeSet {
+            scheme: VolumeScheme::Split,
+            name: base.to_string(),
+            volumes,
+            missing,
+        }
+    } else if let Some((prefix, digits)) = rar_part(stem).filter(|_| suffix == "rar") {
+        let siblings = Siblings::read(dir)?;
+        let found = siblings.numbered(&format!("{prefix}.part"), ".rar", Some(digits));
+        let last = found.keys().next_back().copied().unwrap_or(1);
+        let base = &file_name[..prefix.len()];
+        let (volumes, missing) =
+            collect(&found, 1..=last, |n| format!("{base}.part{n:0digits$}.rar"));
+        VolumeSet {
+            scheme: VolumeScheme::RarParts,
+            name: format!("{base}.rar"),
+            volumes,
+            missing,
+        }
+    } else if is_numbered_suffix(suffix, 'r') || suffix == "rar" {
+        let siblings = Siblings::read(dir)?;
+        let found = siblings.numbered(&format!("{stem}.r"), "", Some(2));
+        if found.is_empty() && suffix == "rar" {
+            return Ok(None);
+        }
+        // 第一卷 `.rar` 记为 0，`.r00` 起依次为 1、2……
+        let mut all: BTreeMap<u32, PathBuf> = found.into_iter().map(|(n, p)| (n + 1, p)).collect();
+        if let Some(first) = siblings.get(&format!("{stem}.rar")) {
+            all.insert(0, first.clone());
+        }
+        let last = all.keys().next_back().copied().unwrap_or(0);
+        let (volumes, missing) = collect(&all, 0..=last, |n| match n {
+            0 => format!("{base}.rar"),
+            n => format!("{base}.r{:02}", n - 1),
+        });
+        VolumeSet {
+            scheme: VolumeScheme::RarLegacy,
+            name: format!("{base}.rar"),
+            volumes,
+            missing,
+        }
+    } else if is_numbered_suffix(suffix, 'z') || suffix == "zip" {
+        let siblings = Siblings::read(dir)?;
+        let found = siblings.numbered(&format!("{stem}.z"), "", None);
+        if found.is_empty() && suffix == "zip" {
+            return Ok(None);
+        }
+        let last_volume = siblings.get(&format!("{stem}.zip"));
+        // 最后一卷的目录结束记录给出总卷数
+        let disks = match last_volume {
+            Some(last) => zip_disk_count(last)?,
+            None => None,
+        };
+        let last = match disks {
+            Some(disks) => disks.saturating_sub(1),
+            None => found.keys().next_back().copied().unwrap_or(0),
+        };
+        let (mut volumes, mut missing) = collect(&found, 1..=last, |n| format!("{base}.z{n:02}"));
+        match last_volume {
+            Some(path) => volumes.push(path.clone()),
+            None => missing.push(format!("{base}.zip")),
+        }
+        VolumeSet {
+            scheme: VolumeScheme::ZipSplit,
+            name: format!("{base}.zip"),
+            volumes,
+            missing,
+        }
+    } else {
+        return Ok(None);
+    };
+
+    Ok(Some(set))
+}
+
+/// 打开磁盘上的文件作为读取器输入：分卷归档会找齐全部分卷，缺卷时报错
+pub fn open_input(path: &Path) -> Result<Input<'static>, ArchiveError> {
+    match discover_volumes(path)? {
+        Some(set) => {
+            set.ensure_complete()?;
+            Ok(Input::Volumes(set))
+        },
+        None => Ok(Input::Path(path.to_path_buf())),
+    }
+}
+
+/// unrar 需要打开的文件：RAR 分卷的第一卷，其他情况为 `path` 本身
+#[allow(dead_code)] // 只在启用 RAR 时使用
+pub(crate) fn first_volume(path: &Path) -> Result<PathBuf, ArchiveError> {
+    match discover_volumes(path)? {
+        Some(set) if matches!(set.scheme, VolumeScheme::RarParts | VolumeScheme::RarLegacy) => {
+            set.ensure_complete()?;
+            Ok(set.volumes[0].clone())
+        },
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+fn is_digits(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// `r00`、`z01` 之类的分卷后缀
+fn is_numbered_suffix(suffix: &str, letter: char) -> bool {
+    suffix.len() >= 3 && suffix.starts_with(letter) && is_digits(&suffix[1..])
+}
+
+/// 拆分 `data.part01`，返回 `("data", 2)`（前缀和卷号位数）
+fn rar_part(stem: &str) -> Option<(&str, usize)> {
+    let (prefix, part) = stem.rsplit_once(".part")?;
+    is_digits(part).then_some((prefix, part.len()))
+}
+
+/// 按卷号范围整理已找到和缺失的分卷
+fn collect(
+    found: &BTreeMap<u32, PathBuf>,
+    range: std::ops::RangeInclusive<u32>,
+    name_of: impl Fn(u32) -> String,
+) -> (Vec<PathBuf>, Vec<String>) {
+    let mut volumes = Vec::new();
+    let mut missing = Vec::new();
+    for n in range {
+        match found.get(&n) {
+            Some(path) => volumes.push(path.clone()),
+            None => missing.push(name_of(n)),
+        }
+    }
+    (volumes, missing)
+}
+
+/// 同目录下的文件，以小写文件名为键（Windows 文件名不区分大小写）
+struct Siblings(HashMap<String, PathBuf>);
+
+impl Siblings {
+    fn read(dir: &Path) -> Result<Self, ArchiveError> {
+        let mut files = HashMap::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            if let Some(name) = entry.file_name().to_str() {
+                files.insert(name.to_lowercase(), entry.path());
+            }
+        }
+        Ok(Self(files))
+    }
+
+    fn get(&self, lower_name: &str) -> Option<&PathBuf> {
+        self.0.get(lower_name)
+    }
+
+    /// 文件名形如 `{prefix}{数字}{suffix}` 的文件，以数字为键；`width` 限定数字位数
+    fn numbered(&self, prefix: &str, suffix: &str, width: Option<usize>) -> BTreeMap<u32, PathBuf> {
+        self.0
+            .iter()
+            .filter_map(|(name, path)| {
+                let digits = name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                if !is_digits(digits) || width.is_some_and(|width| digits.len() != width) {
+                    return None;
+                }
+                Some((digits.parse().ok()?, path.clone()))
+            })
+            .collect()
+    }
+}
+
+/// 按字节切分的 7Z 的总卷数
+///
+/// 起始头记录了尾部头的位置，据此得到完整归档的长度；分卷工具按第一卷的大小等分，
+/// 由此可以发现末尾缺失的分卷。第一卷不是 7Z 时返回 `None`。
+fn expected_7z_volumes(
+    first: &Path,
+    found: &BTreeMap<u32, PathBuf>,
+) -> Result<Option<u32>, ArchiveError> {
+    let mut file = File::open(first)?;
+    let mut header = [0u8; 32];
+    if file.read_exact(&mut header).is_err() || !header.starts_with(b"7z\xBC\xAF\x27\x1C") {
+        return Ok(None);
+    }
+    let next_header_offset = u64_at(&header, 12);
+    let next_header_size = u64_at(&header, 20);
+    let total = 32u64
+        .saturating_add(next_header_offset)
+        .saturating_add(next_header_size);
+    let volume_size = file.metadata()?.len();
+    if volume_size == 0 || found.len() as u64 * volume_size >= total {
+        return Ok(None);
+    }
+    Ok(u32::try_from(total.div_ceil(volume_size)).ok())
+}
+
+fn u16_at(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+fn u32_at(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes(data[pos..pos + 4].try_into().unwrap())
+}
+
+fn u64_at(data: &[u8], pos: usize) -> u64 {
+    u64::from_le_bytes(data[pos..pos + 8].try_into().unwrap())
+}
+
+/// 拼接的一段数据
+enum Part {
+    File { path: PathBuf, start: u64, len: u64 },
+    Memory { start: u64, data: Vec<u8> },
+}
+
+impl Part {
+    fn start(&self) -> u64 {
+        match self {
+            Self::File { start, .. } | Self::Memory { start, .. } => *start,
+        }
+    }
+
+    fn len(&self) -> u64 {
+        match self {
+            Self::File { len, .. } => *len,
+            Self::Memory { data, .. } => data.len() as u64,
+        }
+    }
+}
+
+/// 把多个分卷首尾相接成一个可寻址的数据流，同一时间只打开一个分卷文件
+pub(crate) struct VolumeReader {
+    parts: Vec<Part>,
+    len: u64,
+    pos: u64,
+    /// 当前打开的分卷：段序号、文件、文件内位置
+    current: Option<(usize, BufReader<File>, u64)>,
+}
+
+impl VolumeReader {
+    fn new(volumes: &[PathBuf]) -> Result<Self, ArchiveError> {
+        let mut parts = Vec::with_capacity(volumes.len());
+        let mut start = 0;
+        for path in volumes {
+            let len = std::fs::metadata(path)?.len();
+            parts.push(Part::File { path: path.clone(), start, len });
+            start += len;
+        }
+        Ok(Self::from_parts(parts))
+    }
+
+    fn from_parts(parts: Vec<Part>) -> Self {
+        let len = parts.last().map_or(0, |part| part.start() + part.len());
+        Self { parts, len, pos: 0, current: None }
+    }
+
+    /// 拼接后的总长度
+    pub(crate) fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// 第 `index` 卷在拼接数据中的起始位置
+    fn volume_start(&self, index: usize) -> Option<u64> {
+        self.parts.get(index).map(Part::start)
+    }
+
+    fn read_at(&mut self, pos: u64, len: usize) -> io::Result<Vec<u8>> {
+        let mut data = vec![0; len];
+        self.seek(SeekFrom::Start(pos))?;
+        self.read_exact(&mut data)?;
+        Ok(data)
+    }
+}
+
+impl Read for VolumeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.pos >= self.len {
+            return Ok(0);
+        }
+        let index = self
+            .parts
+            .partition_point(|part| part.start() + part.len() <= self.pos);
+        let part = &self.parts[index];
+        let offset = self.pos - part.start();
+        let want = buf.len().min((part.len() - offset) as usize);
+
+        let read = match part {
+            Part::Memory { data, .. } => {
+                let offset = offset as usize;
+                buf[..want].copy_from_slice(&data[offset..offset + want]);
+                want
+            },
+            Part::File { path, .. } => {
+                if !matches!(&self.current, Some((current, ..)) if *current == index) {
+                    self.current = Some((index, BufReader::new(File::open(path)?), 0));
+                }
+                let (_, file, file_pos) = self.current.as_mut().unwrap();
+                if *file_pos != offset {
+                    file.seek(SeekFrom::Start(offset))?;
+                }
+                let read = file.read(&mut buf[..want])?;
+                if read == 0 {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+                *file_pos = offset + read as u64;
+                read
+            },
+        };
+        self.pos += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for VolumeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(pos) => Some(pos),
+            SeekFrom::End(delta) => self.len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.pos.checked_add_signed(delta),
+        };
+        self.pos = target.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )
+        })?;
+        Ok(self.pos)
+    }
+}
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const EOCD64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const EOCD64_SIGNATURE: u32 = 0x0606_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+/// 目录结束记录（22 字节）加最长 65535 字节的注释
+const EOCD_SEARCH_LEN: u64 = 22 + 0xFFFF;
+
+/// 读取分卷 ZIP 最后一卷的末尾，返回数据和其中目录结束记录的位置
+fn read_zip_tail(
+    reader: &mut (impl Read + Seek),
+    len: u64,
+) -> io::Result<Option<(Vec<u8>, usize)>> {
+    // 多读 20 字节以包含 ZIP64 定位记录
+    let tail_len = len.min(EOCD_SEARCH_LEN + 20);
+    reader.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    reader.read_exact(&mut tail)?;
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&pos| u32_at(&tail, pos) == EOCD_SIGNATURE);
+    Ok(eocd.map(|pos| (tail, pos)))
+}
+
+/// 分卷 ZIP 的总卷数，由最后一卷（`.zip`）的目录结束记录给出
+fn zip_disk_count(last: &Path) -> Result<Option<u32>, ArchiveError> {
+    let mut file = File::open(last)?;
+    let len = file.metadata()?.len();
+    let Some((tail, eocd)) = read_zip_tail(&mut file, len)? else {
+        return Ok(None);
+    };
+    let disk = u16_at(&tail, eocd + 4);
+    if disk == 0xFFFF && eocd >= 20 && u32_at(&tail, eocd - 20) == EOCD64_LOCATOR_SIGNATURE {
+        return Ok(Some(u32_at(&tail, eocd - 20 + 16)));
+    }
+    Ok(Some(u32::from(disk) + 1))
+}
+
+/// 把分卷 ZIP 转换为单卷 ZIP 的数据流
+///
+/// 条目数据原样拼接；中央目录读入内存，把每个条目的（卷号, 卷内偏移）改写为拼接后的
+/// 绝对偏移，再追加一条单卷的目录结束记录。
+fn split_zip_reader(mut reader: VolumeReader) -> Result<VolumeReader, ArchiveError> {
+    let corrupt = |msg: &str| ArchiveError::Other(format!("Invalid split zip: {msg}"));
+    let len = reader.len();
+    let (tail, eocd) = read_zip_tail(&mut reader, len)?
+        .ok_or_else(|| corrupt("end of central directory not found"))?;
+    let comment_len = usize::from(u16_at(&tail, eocd + 20));
+    let comment = tail
+        .get(eocd + 22..eocd + 22 + comment_len)
+        .unwrap_or_default()
+        .to_vec();
+
+    let mut cd_disk = u32::from(u16_at(&tail, eocd + 6));
+    let mut entries = u64::from(u16_at(&tail, eocd + 10));
+    let mut cd_size = u64::from(u32_at(&tail, eocd + 12));
+    let mut cd_offset = u64::from(u32_at(&tail, eocd + 16));
+    if eocd >= 20 && u32_at(&tail, eocd - 20) == EOCD64_LOCATOR_SIGNATURE {
+        let locator = eocd - 20;
+        let disk = u32_at(&tail, locator + 4) as usize;
+        let offset = u64_at(&tail, locator + 8);
+        let start = reader
+            .volume_start(disk)
+            .ok_or_else(|| corrupt("zip64 end of central directory on a missing volume"))?;
+        let record = reader.read_at(start + offset, 56)?;
+        if u32_at(&record, 0) != EOCD64_SIGNATURE {
+            return Err(corrupt("zip64 end of central directory not found"));
+        }
+        cd_disk = u32_at(&record, 20);
+        entries = u64_at(&record, 32);
+        cd_size = u64_at(&record, 40);
+        cd_offset = u64_at(&record, 48);
+    }
+
+    let cd_start = reader
+        .volume_start(cd_disk as usize)
+        .ok_or_else(|| corrupt("central directory on a missing volume"))?
+        + cd_offset;
+    let cd_len = usize::try_from(cd_size).map_err(|_| corrupt("central directory too large"))?;
+    let mut cd = reader.read_at(cd_start, cd_len)?;
+
+    let mut pos = 0;
+    for _ in 0..entries {
+        if pos + 46 > cd.len() || u32_at(&cd, pos) != CENTRAL_HEADER_SIGNATURE {
+            return Err(corrupt("broken central directory"));
+        }
+        let name_len = usize::from(u16_at(&cd, pos + 28));
+        let extra_len = usize::from(u16_at(&cd, pos + 30));
+        let comment_len = usize::from(u16_at(&cd, pos + 32));
+        let extra_start = pos + 46 + name_len;
+        let extra_end = extra_start + extra_len;
+        if extra_end + comment_len > cd.len() {
+            return Err(corrupt("broken central directory"));
+        }
+
+        // ZIP64 扩展字段（0x0001）按固定顺序只保存取值溢出的字段
+        let (mut offset_at, mut disk_at) = (None, None);
+        let mut field = extra_start;
+        while field + 4 <= extra_end {
+            let id = u16_at(&cd, field);
+            let size = usize::from(u16_at(&cd, field + 2));
+            if id == 0x0001 {
+                let mut value = field + 4;
+                value += 8 * usize::from(u32_at(&cd, pos + 24) == u32::MAX);
+                value += 8 * usize::from(u32_at(&cd, pos + 20) == u32::MAX);
+                if u32_at(&cd, pos + 42) == u32::MAX {
+                    offset_at = Some(value);
+                    value += 8;
+                }
+                if u16_at(&cd, pos + 34) == u16::MAX {
+                    disk_at = Some(value);
+                }
+                break;
+            }
+            field += 4 + size;
+        }
+        let fits = |at: usize, width: usize| at + width <= extra_end;
+
+        let disk = match disk_at {
+            Some(at) if fits(at, 4) => u32_at(&cd, at),
+            _ => u32::from(u16_at(&cd, pos + 34)),
+        };
+        let local_offset = match offset_at {
+            Some(at) if fits(at, 8) => u64_at(&cd, at),
+            _ => u64::from(u32_at(&cd, pos + 42)),
+        };
+        let absolute = reader
+            .volume_start(disk as usize)
+            .ok_or_else(|| corrupt("entry on a missing volume"))?
+            + local_offset;
+
+        match offset_at {
+            Some(at) if fits(at, 8) => cd[at..at + 8].copy_from_slice(&absolute.to_le_bytes()),
+            _ => {
+                let absolute = u32::try_from(absolute)
+                    .ok()
+                    .filter(|offset| *offset != u32::MAX)
+                    .ok_or_else(|| corrupt("entry offset exceeds 4 GiB without zip64"))?;
+                cd[pos + 42..pos + 46].copy_from_slice(&absolute.to_le_bytes());
+            },
+        }
+        match disk_at {
+            Some(at) if fits(at, 4) => cd[at..at + 4].copy_from_slice(&0u32.to_le_bytes()),
+            _ => cd[pos + 34..pos + 36].copy_from_slice(&0u16.to_le_bytes()),
+        }
+        pos = extra_end + comment_len;
+    }
+
+    cd.extend_from_slice(&end_of_central_directory(
+        entries, cd_size, cd_start, &comment,
+    ));
+
+    // 条目数据截止到中央目录开始处，之后换成改写过的中央目录
+    let mut parts = Vec::new();
+    for part in reader.parts {
+        let Part::File { path, start, len } = part else {
+            continue;
+        };
+        if start >= cd_start {
+            break;
+        }
+        parts.push(Part::File {
+            path,
+            start,
+            len: len.min(cd_start - start),
+        });
+    }
+    parts.push(Part::Memory { start: cd_start, data: cd });
+    Ok(VolumeReader::from_parts(parts))
+}
+
+/// 单卷的目录结束记录，取值超出范围时先写 ZIP64 记录和定位记录
+fn end_of_central_directory(entries: u64, cd_size: u64, cd_start: u64, comment: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let zip64 = entries >= 0xFFFF || cd_size >= 0xFFFF_FFFF || cd_start >= 0xFFFF_FFFF;
+    if zip64 {
+        let record_start = cd_start + cd_size;
+        out.extend_from_slice(&EOCD64_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&44u64.to_le_bytes());
+        out.extend_from_slice(&45u16.to_le_bytes());
+        out.extend_from_slice(&45u16.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&entries.to_le_bytes());
+        out.extend_from_slice(&entries.to_le_bytes());
+        out.extend_from_slice(&cd_size.to_le_bytes());
+        out.extend_from_slice(&cd_start.to_le_bytes());
+
+        out.extend_from_slice(&EOCD64_LOCATOR_SIGNATURE.to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes());
+        out.extend_from_slice(&record_start.to_le_bytes());
+        out.extend_from_slice(&1u32.to_le_bytes());
+    }
+
+    let entries16 = entries.min(0xFFFF) as u16;
+    out.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out.extend_from_slice(&entries16.to_le_bytes());
+    out.extend_from_slice(&entries16.to_le_bytes());
+    out.extend_from_slice(&(cd_size.min(0xFFFF_FFFF) as u32).to_le_bytes());
+    out.extend_from_slice(&(cd_start.min(0xFFFF_FFFF) as u32).to_le_bytes());
+    out.extend_from_slice(&(comment.len() as u16).to_le_bytes());
+    out.extend_from_slice(comment);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{discover_volumes, VolumeScheme};
+    use std::path::PathBuf;
+
+    fn scratch_dir(name: &str, files: &[&str]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("quicklook_volume_{}_{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), b"data").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn discovers_siblings_and_missing_volumes() {
+        let dir = scratch_dir(
+            "discover",
+            &[
+                "Data.7z.001",
+                "Data.7z.003",
+                "set.part1.rar",
+                "set.part2.rar",
+                "old.rar",
+                "old.r01",
+                "plain.zip",
+            ],
+        );
+
+        let split = discover_volumes(&dir.join("Data.7z.003")).unwrap().unwrap();
+        assert_eq!(split.scheme, VolumeScheme::Split);
+        assert_eq!(split.name, "Data.7z");
+        assert_eq!(split.volumes.len(), 2);
+        assert_eq!(split.missing, ["Data.7z.002"]);
+
+        let parts = discover_volumes(&dir.join("set.part2.rar"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(parts.scheme, VolumeScheme::RarParts);
+        assert_eq!(parts.volumes[0], dir.join("set.part1.rar"));
+        assert!(parts.missing.is_empty());
+
+        let legacy = discover_volumes(&dir.join("old.r01")).unwrap().unwrap();
+        assert_eq!(legacy.scheme, VolumeScheme::RarLegacy);
+        assert_eq!(legacy.volumes[0], dir.join("old.rar"));
+        assert_eq!(legacy.missing, ["old.r00"]);
+
+        assert!(discover_volumes(&dir.join("plain.zip")).unwrap().is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -17,6 +17,21 @@ pub enum QuickLookError {
     #[error("压缩文件解析失败: {0}")]
     ArchiveParse(String),
 
+    #[error("分卷压缩文件缺少分卷: {0}")]
+    ArchiveMissingVolumes(String),
+
+    #[error("压缩文件需要密码")]
+    ArchivePasswordRequired,
+
+    #[error("压缩文件密码错误")]
+    ArchiveWrongPassword,
+
+    #[error("压缩文件操作已取消")]
+    ArchiveCancelled,
+
+    #[error("条目是链接，没有可预览的内容: {0}")]
+    ArchiveLinkEntry(String),
+
     // ── 文档 ──────────────────────────────────────
     #[error("文档解析失败: {0}")]
     DocumentParse(String),
@@ -78,12 +93,52 @@ pub enum QuickLookError {
     Other(String),
 }
 
+impl QuickLookError {
+    /// 稳定的错误代码，前端据此区分错误类型，不依赖可能调整的错误信息
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QuickLookError::FileNotFound(_) => "file_not_found",
+            QuickLookError::Io(_) => "io",
+            QuickLookError::UnsupportedArchiveFormat(_) => "unsupported_archive_format",
+            QuickLookError::ArchiveParse(_) => "archive_parse",
+            QuickLookError::ArchiveMissingVolumes(_) => "archive_missing_volumes",
+            QuickLookError::ArchivePasswordRequired => "archive_password_required",
+            QuickLookError::ArchiveWrongPassword => "archive_wrong_password",
+            QuickLookError::ArchiveCancelled => "archive_cancelled",
+            QuickLookError::ArchiveLinkEntry(_) => "archive_link_entry",
+            QuickLookError::DocumentParse(_) => "document_parse",
+            QuickLookError::UnsupportedDocumentFormat(_) => "unsupported_document_format",
+            QuickLookError::ModelParse(_) => "model_parse",
+            QuickLookError::UnsupportedModelFormat(_) => "unsupported_model_format",
+            QuickLookError::ImageProcessing(_) => "image_processing",
+            QuickLookError::AudioMetadata(_) => "audio_metadata",
+            QuickLookError::LrcParse(_) => "lrc_parse",
+            QuickLookError::FfmpegNotFound => "ffmpeg_not_found",
+            QuickLookError::VideoConversion(_) => "video_conversion",
+            QuickLookError::VideoConversionCancelled => "video_conversion_cancelled",
+            QuickLookError::ConfigRead(_) => "config_read",
+            QuickLookError::ConfigField(_) => "config_field",
+            QuickLookError::WindowsApi(_) => "windows_api",
+            QuickLookError::PdfRendering(_) => "pdf_rendering",
+            QuickLookError::PdfOutline(_) => "pdf_outline",
+            QuickLookError::Utf8(_) => "utf8",
+            QuickLookError::Other(_) => "other",
+        }
+    }
+}
+
+/// 序列化为 `{ kind, message }`，`message` 为展示给用户的错误信息
 impl serde::Serialize for QuickLookError {
     fn serialize<S: serde::Serializer>(
         &self,
         serializer: S,
     ) -> std::result::Result<S::Ok, S::Error> {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("QuickLookError", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
     }
 }
 
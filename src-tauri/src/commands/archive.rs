@@ -1,59 +1,135 @@
-use quicklook_archive::{extractors, Extract};
-use tauri::command;
+use quicklook_archive::{
+    ArchiveError, ArchiveListing, CancelToken, Extract, ImageFilesystem, ImageInfo, ListProgress,
+    ListSummary, ListingCache, NameEncoding, PackageInfo, PasswordProtection, TestProgress,
+    TestReport,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::{Mutex, PoisonError};
+use tauri::{command, ipc::Channel, AppHandle};
 
 use crate::error::QuickLookError;
+use crate::preview::{window::read_custom_extensions, WebRoute};
+use crate::utils::get_file_info;
 
 #[command]
-pub async fn archive_is_password_protected(path: String) -> Result<bool, QuickLookError> {
+pub async fn archive_is_password_protected(
+    path: String,
+) -> Result<PasswordProtection, QuickLookError> {
     tokio::task::spawn_blocking(move || {
-        let needed = Extract::is_password_protected(&path)
-            .map_err(|e| QuickLookError::ArchiveParse(e.to_string()))?;
-        log::info!("检测压缩文件路径: {}, 是否需要密码: {}", path, needed);
-        Ok(needed)
+        let protection = Extract::is_password_protected(&path).map_err(archive_error)?;
+        log::info!("检测压缩文件路径: {}, 加密情况: {:?}", path, protection);
+        Ok(protection)
     })
     .await
     .map_err(|e| QuickLookError::ArchiveParse(format!("密码检测任务执行失败: {}", e)))?
 }
 
+/// 检查密码是否正确，只解开头部或一个加密条目，不做完整的列举。
+#[command]
+pub async fn archive_verify_password(
+    path: String,
+    password: String,
+) -> Result<bool, QuickLookError> {
+    tokio::task::spawn_blocking(move || {
+        let valid = Extract::verify_password(&path, &password).map_err(archive_error)?;
+        log::info!("校验压缩文件密码: {}, 结果: {}", path, valid);
+        Ok(valid)
+    })
+    .await
+    .map_err(|e| QuickLookError::ArchiveParse(format!("密码校验任务执行失败: {}", e)))?
+}
+
+/// 每批推送给前端的条目数
+const LIST_BATCH_SIZE: usize = 1000;
+
+/// 正在进行的列举的取消标记，同一时间只有一个预览窗口在列举
+static LIST_CANCEL: Mutex<Option<CancelToken>> = Mutex::new(None);
+
+/// 列举压缩文件时通过 `Channel` 推送给前端的消息
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum ArchiveListEvent {
+    /// 一批扁平的条目（含目录），按归档中的顺序，由前端构建目录树
+    Entries(Vec<Extract>),
+    /// 列举进度
+    Progress(ListProgress),
+}
+
+/// 流式列举压缩文件，条目分批通过 `on_event` 推送，返回安全检查报告等汇总信息。
+///
+/// 较大的归档（如 .tar.xz）列举结果会缓存到磁盘，再次打开时直接读取缓存。
+/// `encoding` 指定 ZIP 等格式条目名的编码（如 `gbk`、`shift_jis`），未指定时自动识别。
 #[command]
 pub async fn archive(
     path: String,
     mode: String,
     password: Option<String>,
-) -> Result<Vec<Extract>, QuickLookError> {
+    encoding: Option<String>,
+    on_event: Channel<ArchiveListEvent>,
+) -> Result<ListSummary, QuickLookError> {
+    let encoding = name_encoding(encoding.as_deref())?;
+    let cancel = CancelToken::new();
+    if let Some(previous) = LIST_CANCEL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .replace(cancel.clone())
+    {
+        previous.cancel();
+    }
+
     tokio::task::spawn_blocking(move || {
-        log::info!("开始处理压缩文件: {}, 扩展名: {}", path, mode);
-        let pw = password.as_deref();
-        let result = match mode.as_str() {
-            "zip" => extractors::zip::zip_extract(&path, pw)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "tar" => extractors::tar::list_tar_entries(&path)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "gz" | "tgz" => extractors::tar::list_tar_gz_entries(&path)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "bz2" | "tbz2" => extractors::tar::list_tar_bz2_entries(&path)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "xz" | "txz" => extractors::tar::list_tar_xz_entries(&path)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "zst" | "tzst" => extractors::zst::list_tar_zst_entries(&path)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "7z" => extractors::sevenz::list_7z_entries(&path, pw)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "rar" => extractors::rar::list_rar_entries(&path, pw)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "cpio" => extractors::cpio::list_cpio_entries(&path)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "ar" | "deb" | "a" => extractors::ar::list_ar_entries(&path)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            "jar" | "war" | "ear" | "apk" | "aar" | "whl" | "vsix" | "nupkg" | "crx" | "xpi"
-            | "egg" | "kra" | "xps" | "oxps" => extractors::zip::zip_extract(&path, pw)
-                .map_err(|e| QuickLookError::ArchiveParse(e.to_string())),
-            _ => return Err(QuickLookError::UnsupportedArchiveFormat(mode)),
-        };
+        log::info!(
+            "开始处理压缩文件: {}, 扩展名: {}, 条目名编码: {}",
+            path,
+            mode,
+            encoding
+        );
+        let mut batch = Vec::with_capacity(LIST_BATCH_SIZE);
+        let progress_channel = on_event.clone();
+        // 格式优先根据文件头识别，无法识别时才按扩展名判断
+        let result = Extract::list_archive_each_cached(
+            &path,
+            &[],
+            password.as_deref(),
+            encoding,
+            &ListingCache::default(),
+            &cancel,
+            &mut |entry| {
+                batch.push(entry);
+                if batch.len() < LIST_BATCH_SIZE {
+                    return Ok(true);
+                }
+                let entries = std::mem::replace(&mut batch, Vec::with_capacity(LIST_BATCH_SIZE));
+                // 前端已关闭时不再继续列举
+                Ok(send_list_event(
+                    &on_event,
+                    ArchiveListEvent::Entries(entries),
+                ))
+            },
+            &mut |progress| {
+                send_list_event(
+                    &progress_channel,
+                    ArchiveListEvent::Progress(progress.clone()),
+                );
+            },
+        )
+        .map_err(|e| match e {
+            ArchiveError::UnsupportedFormat(_) => QuickLookError::UnsupportedArchiveFormat(mode),
+            e => archive_error(e),
+        });
+        if result.is_ok() && !batch.is_empty() {
+            send_list_event(&on_event, ArchiveListEvent::Entries(batch));
+        }
 
         match &result {
-            Ok(entries) => {
-                log::info!("成功处理压缩文件，共{}个条目", entries.len());
+            Ok(summary) => {
+                log::info!(
+                    "成功处理压缩文件，共{}个条目，风险{}项，使用缓存: {}",
+                    summary.entries,
+                    summary.safety.issues.len(),
+                    summary.from_cache
+                );
             },
             Err(e) => {
                 log::error!("压缩文件处理失败: {}", e);
@@ -65,3 +141,350 @@ pub async fn archive(
     .await
     .map_err(|e| QuickLookError::ArchiveParse(format!("压缩文件处理任务执行失败: {}", e)))?
 }
+
+/// 推送列举消息，返回是否推送成功
+fn send_list_event(channel: &Channel<ArchiveListEvent>, event: ArchiveListEvent) -> bool {
+    match channel.send(event) {
+        Ok(()) => true,
+        Err(e) => {
+            log::warn!("推送压缩文件条目失败: {}", e);
+            false
+        },
+    }
+}
+
+/// 取消正在进行的列举。
+#[command]
+pub fn archive_cancel_list() {
+    if let Some(cancel) = LIST_CANCEL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+    {
+        log::info!("取消列举压缩文件");
+        cancel.cancel();
+    }
+}
+
+/// 列举压缩包内嵌套的压缩包，`entries` 为从外到内的条目名。
+#[command]
+pub async fn archive_nested(
+    path: String,
+    entries: Vec<String>,
+    password: Option<String>,
+    encoding: Option<String>,
+) -> Result<ArchiveListing, QuickLookError> {
+    let encoding = name_encoding(encoding.as_deref())?;
+    tokio::task::spawn_blocking(move || {
+        log::info!("开始处理嵌套压缩文件: {}, 条目: {:?}", path, entries);
+        let result =
+            Extract::inspect_nested_archive(&path, &entries, password.as_deref(), encoding)
+                .map_err(archive_error);
+
+        match &result {
+            Ok(listing) => {
+                log::info!(
+                    "成功处理嵌套压缩文件，共{}个条目，风险{}项",
+                    listing.safety.entry_count,
+                    listing.safety.issues.len()
+                );
+            },
+            Err(e) => {
+                log::error!("嵌套压缩文件处理失败: {}", e);
+            },
+        }
+
+        result
+    })
+    .await
+    .map_err(|e| QuickLookError::ArchiveParse(format!("嵌套压缩文件处理任务执行失败: {}", e)))?
+}
+
+/// 读取 jar、apk、whl 等软件包的清单信息，不是软件包时返回 `None`。
+#[command]
+pub async fn archive_package_info(
+    path: String,
+    nested: Option<Vec<String>>,
+    password: Option<String>,
+) -> Result<Option<PackageInfo>, QuickLookError> {
+    tokio::task::spawn_blocking(move || {
+        let nested = nested.unwrap_or_default();
+        let result =
+            Extract::package_info(&path, &nested, password.as_deref()).map_err(archive_error);
+
+        match &result {
+            Ok(Some(info)) => {
+                log::info!(
+                    "读取软件包清单: {}, 类型: {:?}, 名称: {:?}, 版本: {:?}",
+                    path,
+                    info.kind,
+                    info.name,
+                    info.version
+                );
+            },
+            Ok(None) => {},
+            Err(e) => {
+                log::warn!("读取软件包清单失败: {}", e);
+            },
+        }
+
+        result
+    })
+    .await
+    .map_err(|e| QuickLookError::ArchiveParse(format!("软件包清单读取任务执行失败: {}", e)))?
+}
+
+/// 读取 `docker save` 导出的 TAR 或 OCI 镜像布局中的镜像清单和配置，不是容器镜像时返回 `None`。
+#[command]
+pub async fn archive_image_info(
+    path: String,
+    nested: Option<Vec<String>>,
+    password: Option<String>,
+) -> Result<Option<ImageInfo>, QuickLookError> {
+    tokio::task::spawn_blocking(move || {
+        let nested = nested.unwrap_or_default();
+        let result =
+            Extract::image_info(&path, &nested, password.as_deref()).map_err(archive_error);
+
+        match &result {
+            Ok(Some(info)) => {
+                log::info!(
+                    "读取容器镜像清单: {}, 布局: {:?}, 镜像数: {}",
+                    path,
+                    info.layout,
+                    info.images.len()
+                );
+            },
+            Ok(None) => {},
+            Err(e) => {
+                log::warn!("读取容器镜像清单失败: {}", e);
+            },
+        }
+
+        result
+    })
+    .await
+    .map_err(|e| QuickLookError::ArchiveParse(format!("容器镜像清单读取任务执行失败: {}", e)))?
+}
+
+/// 叠加容器镜像的各层并处理 whiteout，返回合并后的文件系统，`image` 为镜像的下标。
+#[command]
+pub async fn archive_image_filesystem(
+    path: String,
+    nested: Option<Vec<String>>,
+    password: Option<String>,
+    image: usize,
+) -> Result<ImageFilesystem, QuickLookError> {
+    tokio::task::spawn_blocking(move || {
+        let nested = nested.unwrap_or_default();
+        let result = Extract::image_filesystem(&path, &nested, password.as_deref(), image)
+            .map_err(archive_error);
+
+        match &result {
+            Ok(filesystem) => {
+                log::info!(
+                    "合并容器镜像文件系统: {}, 镜像: {}, 共{}个文件",
+                    path,
+                    image,
+                    filesystem.origins.len()
+                );
+            },
+            Err(e) => {
+                log::error!("合并容器镜像文件系统失败: {}", e);
+            },
+        }
+
+        result
+    })
+    .await
+    .map_err(|e| QuickLookError::ArchiveParse(format!("容器镜像文件系统合并任务执行失败: {}", e)))?
+}
+
+/// 正在进行的完整性测试的取消标记，同一时间只有一个测试
+static TEST_CANCEL: Mutex<Option<CancelToken>> = Mutex::new(None);
+
+/// 完整性测试：解压每个条目并校验 CRC，测试进度通过 `on_progress` 推送。
+#[command]
+pub async fn archive_test(
+    path: String,
+    password: Option<String>,
+    on_progress: Channel<TestProgress>,
+) -> Result<TestReport, QuickLookError> {
+    let cancel = CancelToken::new();
+    if let Some(previous) = TEST_CANCEL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .replace(cancel.clone())
+    {
+        previous.cancel();
+    }
+
+    tokio::task::spawn_blocking(move || {
+        log::info!("开始测试压缩文件: {}", path);
+        let result = Extract::test_archive(&path, password.as_deref(), &cancel, &mut |progress| {
+            if let Err(e) = on_progress.send(progress.clone()) {
+                log::warn!("推送测试进度失败: {}", e);
+            }
+        })
+        .map_err(archive_error);
+
+        match &result {
+            Ok(report) => {
+                log::info!(
+                    "压缩文件测试完成，通过{}项，失败{}项，错误: {:?}",
+                    report.passed,
+                    report.failed,
+                    report.error
+                );
+            },
+            Err(e) => {
+                log::error!("压缩文件测试失败: {}", e);
+            },
+        }
+
+        result
+    })
+    .await
+    .map_err(|e| QuickLookError::ArchiveParse(format!("压缩文件测试任务执行失败: {}", e)))?
+}
+
+/// 取消正在进行的完整性测试。
+#[command]
+pub fn archive_cancel_test() {
+    if let Some(cancel) = TEST_CANCEL
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+    {
+        log::info!("取消压缩文件测试");
+        cancel.cancel();
+    }
+}
+
+/// 解压压缩包中的单个文件到临时目录，返回预览该文件所用的前端路由。
+///
+/// `nested` 不为空时，`entry` 位于 `nested` 指定的嵌套压缩包中。
+#[command]
+pub async fn archive_extract_entry(
+    app: AppHandle,
+    path: String,
+    entry: String,
+    password: Option<String>,
+    nested: Option<Vec<String>>,
+    encoding: Option<String>,
+) -> Result<String, QuickLookError> {
+    let encoding = name_encoding(encoding.as_deref())?;
+    tokio::task::spawn_blocking(move || {
+        let nested = nested.unwrap_or_default();
+        log::info!(
+            "解压压缩文件条目: {}, 嵌套: {:?}, 条目: {}",
+            path,
+            nested,
+            entry
+        );
+        let dest_dir = archive_entry_dir(&path, &nested, &entry);
+        let extracted = Extract::extract_nested_entry(
+            &path,
+            &nested,
+            &entry,
+            password.as_deref(),
+            encoding,
+            &dest_dir,
+        )
+        .map_err(archive_error)?;
+        let extracted = extracted.to_string_lossy().to_string();
+
+        let (custom_code_exts, custom_video_exts) = read_custom_extensions(&app);
+        let file_info = get_file_info(&extracted, &custom_code_exts, &custom_video_exts)
+            .ok_or_else(|| QuickLookError::Other(format!("不支持预览该文件: {}", entry)))?;
+        let type_str = file_info.get_file_type().to_string();
+        let route = WebRoute::get_route(&type_str, file_info);
+        log::info!("条目已解压到: {}, 预览类型: {}", extracted, type_str);
+
+        Ok(route.to_url())
+    })
+    .await
+    .map_err(|e| QuickLookError::ArchiveParse(format!("条目解压任务执行失败: {}", e)))?
+}
+
+/// 解析前端指定的条目名编码，未指定时自动识别
+fn name_encoding(encoding: Option<&str>) -> Result<NameEncoding, QuickLookError> {
+    encoding
+        .map_or(Ok(NameEncoding::Auto), str::parse)
+        .map_err(archive_error)
+}
+
+/// 压缩库错误转换为统一错误
+///
+/// 缺少分卷、缺少密码、密码错误、取消单独区分，前端据此提示补齐文件或重新输入密码。
+fn archive_error(e: ArchiveError) -> QuickLookError {
+    match e {
+        ArchiveError::MissingVolumes(names) => {
+            QuickLookError::ArchiveMissingVolumes(names.join(", "))
+        },
+        ArchiveError::PasswordRequired => QuickLookError::ArchivePasswordRequired,
+        ArchiveError::WrongPassword => QuickLookError::ArchiveWrongPassword,
+        ArchiveError::Cancelled => QuickLookError::ArchiveCancelled,
+        ArchiveError::LinkEntry { entry, target } => {
+            QuickLookError::ArchiveLinkEntry(match target {
+                Some(target) => format!("{entry} -> {target}"),
+                None => entry,
+            })
+        },
+        e => QuickLookError::ArchiveParse(e.to_string()),
+    }
+}
+
+/// 每个条目在临时目录下的独立解压目录，以压缩包路径、嵌套层级、完整条目名和修改时间区分
+///
+/// 解压出的文件只保留条目的文件名，不同目录下的同名条目（如 `a/README` 和 `b/README`）
+/// 放在各自的目录中，不会覆盖可能仍在预览的文件。
+fn archive_entry_dir(path: &str, nested: &[String], entry: &str) -> PathBuf {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    nested.hash(&mut hasher);
+    entry.hash(&mut hasher);
+    if let Ok(meta) = std::fs::metadata(path) {
+        if let Ok(modified) = meta.modified() {
+            modified.hash(&mut hasher);
+        }
+    }
+
+    std::env::temp_dir()
+        .join("quicklook_archive")
+        .join(format!("{:x}", hasher.finish()))
+}
+
+/// 清理压缩包条目解压缓存目录和列举缓存。
+/// 返回被删除的目录/文件数量。
+pub(crate) fn clear_archive_cache_sync() -> Result<u32, QuickLookError> {
+    let listing = ListingCache::default().clear().map_err(archive_error)?;
+    log::info!("共清理 {} 个压缩包列举缓存", listing);
+
+    let archive_dir = std::env::temp_dir().join("quicklook_archive");
+    if !archive_dir.exists() {
+        log::info!("quicklook_archive 目录不存在，无需清理");
+        return Ok(listing);
+    }
+    let entries = std::fs::read_dir(&archive_dir)?;
+
+    let mut removed = 0u32;
+    for entry in entries.flatten() {
+        let result = if entry.path().is_dir() {
+            std::fs::remove_dir_all(entry.path())
+        } else {
+            std::fs::remove_file(entry.path())
+        };
+        match result {
+            Ok(_) => removed += 1,
+            Err(e) => {
+                log::warn!("清理解压缓存失败: {}, 错误: {}", entry.path().display(), e);
+            },
+        }
+    }
+    log::info!("共清理 {} 个压缩包解压缓存", removed);
+    Ok(removed + listing)
+}
@@ -7,7 +7,11 @@ pub mod pdf;
 pub mod system;
 pub mod video;
 
-pub use archive::{archive, archive_is_password_protected};
+pub use archive::{
+    archive, archive_cancel_list, archive_cancel_test, archive_extract_entry,
+    archive_image_filesystem, archive_image_info, archive_is_password_protected, archive_nested,
+    archive_package_info, archive_test, archive_verify_password,
+};
 pub use audio::{parse_lrc, read_audio_info};
 pub use book::{get_epub_chapter, get_epub_info, resolve_epub_link};
 pub use document::document;
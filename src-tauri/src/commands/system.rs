@@ -57,7 +57,7 @@ pub async fn get_default_program_name(path: String) -> Result<String, QuickLookE
         .map_err(|e| QuickLookError::WindowsApi(format!("获取默认程序任务执行失败: {}", e)))?
 }
 
-/// 汇总清理所有 quicklook 产生的缓存，包含 ffmpeg HLS 转码缓存、图片转码缓存和 PDF 渲染缓存。
+/// 汇总清理所有 quicklook 产生的缓存，包含 ffmpeg HLS 转码缓存、图片转码缓存、PDF 渲染缓存、压缩包条目解压缓存和列举缓存。
 /// 返回被删除的目录/文件总数量。
 #[command]
 pub async fn clear_cache() -> Result<u32, QuickLookError> {
@@ -66,6 +66,7 @@ pub async fn clear_cache() -> Result<u32, QuickLookError> {
         total += ffmp::clear_ffmpeg_cache()?;
         total += crate::commands::image::clear_image_cache_sync()?;
         total += pdf_helper::clear_pdf_cache()?;
+        total += crate::commands::archive::clear_archive_cache_sync()?;
         log::info!("缓存清理完成，共删除 {} 个目录/文件", total);
         Ok(total)
     })
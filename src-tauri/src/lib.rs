@@ -13,7 +13,10 @@ use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_store::StoreExt;
 
 use commands::{
-    archive, archive_is_password_protected, cancel_video_conversion, check_ffmpeg, clear_cache,
+    archive, archive_cancel_list, archive_cancel_test, archive_extract_entry,
+    archive_image_filesystem, archive_image_info, archive_is_password_protected, archive_nested,
+    archive_package_info, archive_test, archive_verify_password, cancel_video_conversion,
+    check_ffmpeg, clear_cache,
     clear_image_cache, clear_pdf_cache, convert_to_png, convert_video_to_hls, document,
     prepare_video_for_preview,
     get_default_program_name, get_epub_chapter, get_epub_info, get_monitor_info, get_pdf_outline,
@@ -135,6 +138,15 @@ pub fn run() {
             show_open_with_dialog,
             archive,
             archive_is_password_protected,
+            archive_verify_password,
+            archive_test,
+            archive_cancel_test,
+            archive_cancel_list,
+            archive_extract_entry,
+            archive_nested,
+            archive_package_info,
+            archive_image_info,
+            archive_image_filesystem,
             document,
             get_monitor_info,
             get_default_program_name,
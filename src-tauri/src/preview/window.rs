@@ -32,6 +32,28 @@ fn navigate_to(window: &tauri::WebviewWindow, url: &str) {
     }
 }
 
+/// 从 store 读取用户自定义的代码、视频扩展名
+pub(crate) fn read_custom_extensions(app: &AppHandle) -> (Vec<String>, Vec<String>) {
+    let store = match app.store("config.data") {
+        Ok(store) => Some(store),
+        Err(err) => {
+            log::warn!("Failed to open config.data store: {:?}", err);
+            None
+        },
+    };
+    let custom_code_exts: Vec<String> = store
+        .as_ref()
+        .and_then(|s| s.get("customCodeExtensions"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    let custom_video_exts: Vec<String> = store
+        .as_ref()
+        .and_then(|s| s.get("customVideoExtensions"))
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    (custom_code_exts, custom_video_exts)
+}
+
 impl PreviewFile {
     fn calc_window_size(file_type: &str) -> (f64, f64) {
         let monitor_info = monitor::get_monitor_info();
@@ -73,25 +95,7 @@ impl PreviewFile {
             },
         };
 
-        // 从 store 读取用户自定义扩展名
-        let store = match app.store("config.data") {
-            Ok(store) => Some(store),
-            Err(err) => {
-                log::warn!("Failed to open config.data store: {:?}", err);
-                None
-            },
-        };
-        let custom_code_exts: Vec<String> = store
-            .as_ref()
-            .and_then(|s| s.get("customCodeExtensions"))
-            .and_then(|v| serde_json::from_value(v).ok())
-            .unwrap_or_default();
-        let custom_video_exts: Vec<String> = store
-            .as_ref()
-            .and_then(|s| s.get("customVideoExtensions"))
-            .and_then(|v| serde_json::from_value(v).ok())
-            .unwrap_or_default();
-
+        let (custom_code_exts, custom_video_exts) = read_custom_extensions(&app);
         let file_info = match get_file_info(&file_path, &custom_code_exts, &custom_video_exts) {
             Some(info) => info,
             None => return Ok(()),
@@ -159,7 +159,8 @@ pub fn get_file_info(
     // 先从内置映射表中查找文件类型
     let file_type_opt = FILE_TYPE_MAPPING
         .get(extension.as_str())
-        .map(|s| s.to_string());
+        .map(|s| s.to_string())
+        .or_else(|| is_archive_volume(&extension).then(|| "Archive".to_string()));
 
     // 如果内置映射表中没有匹配，检查用户自定义扩展名
     let file_type_opt = file_type_opt.or_else(|| {
@@ -191,6 +192,15 @@ pub fn get_file_info(
     }
 }
 
+/// 分卷压缩文件的后续分卷（`002`、`z02`、`r01` 等），从任意一卷打开都能找到其他分卷
+fn is_archive_volume(extension: &str) -> bool {
+    let (digits, min_len) = match extension.strip_prefix(['z', 'r']) {
+        Some(digits) => (digits, 2),
+        None => (extension, 3),
+    };
+    digits.len() >= min_len && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
 // 返回一个文件扩展名到文件类型的映射
 static FILE_TYPE_MAPPING: LazyLock<HashMap<&'static str, &'static str>> = LazyLock::new(|| {
     HashMap::from([
@@ -266,10 +276,32 @@ static FILE_TYPE_MAPPING: LazyLock<HashMap<&'static str, &'static str>> = LazyLo
         ("txz", "Archive"),  // tar.xz 的简写
         ("zst", "Archive"),  // zstandard 压缩文件
         ("tzst", "Archive"), // tar.zst 的简写
+        ("lz4", "Archive"),  // lz4 压缩文件
+        ("lzma", "Archive"), // 旧式 lzma 压缩文件
+        ("tlz", "Archive"),  // tar.lzma 的简写
+        ("lz", "Archive"),   // lzip 压缩文件
+        ("br", "Archive"),   // brotli 压缩文件
+        ("z", "Archive"),    // Unix compress 压缩文件（.Z，扩展名已转为小写）
+        ("taz", "Archive"),  // tar.Z 的简写
         ("cpio", "Archive"), // CPIO 归档
         ("ar", "Archive"),   // Unix ar 归档
-        ("deb", "Archive"),  // Debian 软件包（外层 ar）
+        ("deb", "Archive"),  // Debian 软件包
+        ("udeb", "Archive"), // Debian 安装器使用的精简软件包
+        ("rpm", "Archive"),  // RPM 软件包
         ("a", "Archive"),    // 静态库
+        ("cab", "Archive"),  // Microsoft Cabinet
+        ("msi", "Archive"),  // Windows Installer 安装包
+        ("msm", "Archive"),  // Windows Installer 合并模块
+        // 光盘与文件系统映像
+        ("iso", "Archive"),      // ISO 9660 光盘映像
+        ("udf", "Archive"),      // UDF 光盘映像
+        ("squashfs", "Archive"), // SquashFS 文件系统映像
+        ("sqfs", "Archive"),     // SquashFS 的简写
+        ("snap", "Archive"),     // Snap 软件包（SquashFS）
+        // 分卷压缩文件的第一卷，后续分卷见 is_archive_volume
+        ("001", "Archive"), // 按字节切分的分卷，如 data.7z.001
+        ("z01", "Archive"), // 分卷 ZIP
+        ("r00", "Archive"), // 旧式分卷 RAR
         // ZIP 本质但带特殊扩展名
         ("jar", "Archive"),   // Java 归档
         ("war", "Archive"),   // Java Web 归档
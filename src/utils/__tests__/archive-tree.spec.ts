@@ -0,0 +1,68 @@
+import { describe, expect, it } from 'vitest'
+import { TreeBuilder, toFileNodes, type ExtractedFile, type FileNode } from '@/utils/archive-tree'
+
+function entry(name: string, size: number, dir = false): ExtractedFile {
+    return {
+        name,
+        size,
+        last_modified: null,
+        dir,
+        encrypted: false,
+        encryption_method: null,
+        file_count: null,
+        children: null,
+    }
+}
+
+function build(entries: ExtractedFile[]): FileNode[] {
+    const builder = new TreeBuilder()
+    entries.forEach(file => builder.add(file))
+    return builder.finish()
+}
+
+describe('TreeBuilder', () => {
+    it('synthesizes missing parents', () => {
+        const tree = build([entry('src/main.rs', 3), entry('README', 1)])
+        expect(tree.map(node => [node.path, node.isDir])).toEqual([
+            ['src/', true],
+            ['README', false],
+        ])
+        expect(tree[0].fileCount).toBe(1)
+        expect(tree[0].size).toBe(3)
+    })
+
+    it('keeps files that also have children', () => {
+        for (const entries of [
+            [entry('a', 7), entry('a/b', 2)],
+            [entry('a/b', 2), entry('a', 7)],
+        ]) {
+            const tree = build(entries)
+            expect(tree.map(node => [node.name, node.path, node.isDir, node.size])).toEqual([
+                ['a', 'a/', true, 2],
+                ['a', 'a', false, 7],
+            ])
+            expect(tree[0].fileCount).toBe(1)
+            expect(tree[0].children.map(node => node.path)).toEqual(['a/b'])
+        }
+    })
+
+    it('keeps nested files that also have children under their parent', () => {
+        const tree = build([entry('x/a', 7), entry('x/a/b', 2), entry('x/', 0, true)])
+        expect(tree).toHaveLength(1)
+        expect(tree[0].size).toBe(9)
+        expect(tree[0].fileCount).toBe(2)
+        expect(tree[0].children.map(node => [node.path, node.isDir])).toEqual([
+            ['x/a/', true],
+            ['x/a', false],
+        ])
+    })
+})
+
+describe('FileNode.key', () => {
+    it('is unique across directories, files and nested archives', () => {
+        const tree = build([entry('a/README', 1), entry('b/README', 1), entry('c', 1), entry('c/d', 1)])
+        const nested = toFileNodes([entry('a/README', 1)], ['inner.zip'])
+        const keys = [...tree.flatMap(node => [node, ...node.children]), ...nested].map(node => node.key)
+        expect(new Set(keys).size).toBe(keys.length)
+    })
+})
@@ -0,0 +1,17 @@
+import { describe, expect, it } from 'vitest'
+import { errorKind, errorMessage } from '@/utils/index'
+
+describe('command errors', () => {
+    it('reads kind and message from QuickLookError', () => {
+        const err = { kind: 'archive_wrong_password', message: '压缩文件密码错误' }
+        expect(errorKind(err)).toBe('archive_wrong_password')
+        expect(errorMessage(err)).toBe('压缩文件密码错误')
+    })
+
+    it('falls back for other errors', () => {
+        expect(errorKind('failed')).toBeUndefined()
+        expect(errorMessage('failed')).toBe('failed')
+        expect(errorKind(new Error('boom'))).toBeUndefined()
+        expect(errorMessage(new Error('boom'))).toBe('boom')
+    })
+})
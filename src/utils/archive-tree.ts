@@ -0,0 +1,141 @@
+// 压缩包预览的目录树，规则与后端 Extract::build_tree 保持一致
+
+export interface ExtractedFile {
+    name: string
+    size: number
+    last_modified: string | null // ISO 8601，格式未记录时间时为 null
+    dir: boolean
+    encrypted: boolean
+    encryption_method: string | null // zipcrypto、aes-256 等
+    file_count: number | null
+    children: ExtractedFile[] | null
+}
+
+export class FileNode {
+    name: string
+    path: string // 压缩包内的完整条目名
+    size: number
+    last_modified: string | null
+    isDir: boolean
+    fileCount: number // 目录下的文件总数
+    encryption: string | null // 加密方式，未加密时为 null
+    chain: string[] // 条目所在的嵌套压缩包，从外到内
+    children: FileNode[]
+    constructor(
+        name: string,
+        path: string,
+        size: number,
+        last_modified: string | null,
+        isDir: boolean,
+        chain: string[] = [],
+    ) {
+        this.name = name
+        this.path = path
+        this.size = size
+        this.last_modified = last_modified
+        this.isDir = isDir
+        this.fileCount = 0
+        this.encryption = null
+        this.chain = chain
+        this.children = [] // 子目录或文件
+    }
+
+    // el-tree 的节点键：嵌套链加完整条目名，不同目录下的同名文件、同名的文件和目录互不冲突
+    get key(): string {
+        return JSON.stringify([...this.chain, this.path])
+    }
+}
+
+// 同级条目目录在前，再按名称排序
+export function compareNodes(a: FileNode, b: FileNode): number {
+    if (a.isDir !== b.isDir) return a.isDir ? -1 : 1
+    const lowerA = a.name.toLowerCase()
+    const lowerB = b.name.toLowerCase()
+    if (lowerA !== lowerB) return lowerA < lowerB ? -1 : 1
+    return a.name < b.name ? -1 : a.name > b.name ? 1 : 0
+}
+
+function encryptionOf(file: ExtractedFile): string | null {
+    return file.encrypted ? (file.encryption_method ?? '已加密') : null
+}
+
+// 由流式推送的扁平条目逐批构建目录树：补出没有单独条目的父目录；
+// 文件条目下还有子条目时，文件保持原样，同名目录另行补出；
+// 目录的大小、文件数和排序在全部条目到达后统一计算
+export class TreeBuilder {
+    private root = new FileNode('', '', 0, null, true)
+    private nodes = new Map<string, FileNode>() // 以去掉首尾 `/` 的路径为键
+    private files = new Set<string>() // 显式条目为文件的路径
+
+    add(file: ExtractedFile) {
+        const key = file.name.replace(/^(\.\/)+/, '').replace(/^\/+|\/+$/g, '')
+        // 跳过 `./`、`/` 这类指向归档根目录的条目
+        if (!key) return
+
+        let node = this.root
+        let path = ''
+        for (const part of key.split('/').filter(part => part)) {
+            path = path ? `${path}/${part}` : part
+            let child = this.nodes.get(path)
+            if (!child) {
+                // 先按隐式目录创建，遇到显式条目时再覆盖
+                child = new FileNode(part, `${path}/`, 0, null, true)
+                this.nodes.set(path, child)
+                node.children.push(child)
+            }
+            node = child
+        }
+        // 同名条目中，显式的目录条目不覆盖已有的文件条目
+        if (file.dir && this.files.has(path)) return
+        if (file.dir) this.files.delete(path)
+        else this.files.add(path)
+
+        node.path = file.name
+        node.size = file.size
+        node.last_modified = file.last_modified
+        node.isDir = file.dir
+        node.encryption = encryptionOf(file)
+    }
+
+    finish(): FileNode[] {
+        for (const [path, node] of this.nodes) {
+            if (node.isDir || node.children.length === 0) continue
+            // 文件条目下还有子条目（如同时存在 `a` 和 `a/b`）时保留文件本身，目录另行补出
+            const file = new FileNode(node.name, node.path, node.size, node.last_modified, false)
+            file.encryption = node.encryption
+            const slash = path.lastIndexOf('/')
+            const parent = slash < 0 ? this.root : this.nodes.get(path.slice(0, slash))
+            parent?.children.push(file)
+            node.path = `${path}/`
+            node.last_modified = null
+            node.encryption = null
+            node.isDir = true
+        }
+        TreeBuilder.summarize(this.root)
+        return this.root.children
+    }
+
+    private static summarize(node: FileNode) {
+        if (!node.isDir) return
+        node.size = 0
+        node.fileCount = 0
+        for (const child of node.children) {
+            TreeBuilder.summarize(child)
+            node.size += child.size
+            node.fileCount += child.isDir ? child.fileCount : 1
+        }
+        node.children.sort(compareNodes)
+    }
+}
+
+// 嵌套压缩包由后端返回目录树（隐式父目录已补全、目录在前排序），这里只转换为节点对象
+export function toFileNodes(files: ExtractedFile[], chain: string[] = []): FileNode[] {
+    return files.map(file => {
+        const name = file.name.replace(/\/+$/, '').split('/').pop() ?? file.name
+        const node = new FileNode(name, file.name, file.size, file.last_modified, file.dir, chain)
+        node.fileCount = file.file_count ?? 0
+        node.encryption = encryptionOf(file)
+        node.children = toFileNodes(file.children ?? [], chain)
+        return node
+    })
+}
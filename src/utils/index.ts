@@ -139,3 +139,32 @@ export const readTextFile = async (path: string, conf = {}): Promise<string> =>
         return ''
     }
 }
+
+// 后端命令的错误（QuickLookError）序列化为 { kind, message }，kind 为稳定的错误代码
+interface CommandError {
+    kind: string
+    message: string
+}
+
+function isCommandError(err: unknown): err is CommandError {
+    return Type.isObject(err) && typeof (err as CommandError).kind === 'string'
+}
+
+/**
+ * 获取后端命令的错误代码，其他错误返回 undefined
+ * @param err
+ * @returns
+ */
+export const errorKind = (err: unknown): string | undefined => {
+    return isCommandError(err) ? err.kind : undefined
+}
+
+/**
+ * 获取展示给用户的错误信息
+ * @param err
+ * @returns
+ */
+export const errorMessage = (err: unknown): string => {
+    if (isCommandError(err)) return err.message
+    return err instanceof Error ? err.message : String(err)
+}